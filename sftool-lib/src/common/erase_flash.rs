@@ -1,4 +1,4 @@
-use crate::common::ram_command::{Command, RamCommand};
+use crate::common::ram_command::{Command, RamCommand, Response};
 use crate::common::serial_io::for_tool;
 use crate::progress::{EraseFlashStyle, EraseRegionStyle, ProgressOperation, ProgressStatus};
 use crate::utils::Utils;
@@ -8,6 +8,11 @@ use crate::{Error, Result, SifliToolTrait};
 pub struct EraseOps;
 
 impl EraseOps {
+    /// 单条 burn_erase 覆盖的最大范围，是 NOR 64KB 块和 NAND 128KB 块的整数倍
+    pub const ERASE_CHUNK_SIZE: u32 = 256 * 1024;
+    /// 空白检查每条 burn_verify 覆盖的范围，也是报告非空白区域的粒度
+    pub const BLANK_CHECK_CHUNK_SIZE: u32 = 64 * 1024;
+
     /// 擦除整个Flash的通用实现
     pub fn erase_all<T>(tool: &mut T, address: u32) -> Result<()>
     where
@@ -23,10 +28,11 @@ impl EraseOps {
         // 发送擦除所有命令
         let _ = tool.command(Command::EraseAll { address });
 
+        let timeout = tool.base().timeouts.erase;
         let mut io = for_tool(tool);
         io.wait_for_pattern(
             b"OK",
-            std::time::Duration::from_millis(30_000),
+            timeout,
             &format!("erasing flash at 0x{:08X}", address),
         )?;
 
@@ -37,36 +43,126 @@ impl EraseOps {
 
     /// 擦除指定区域的通用实现
     pub fn erase_region<T>(tool: &mut T, address: u32, len: u32) -> Result<()>
+    where
+        T: SifliToolTrait + RamCommand,
+    {
+        Self::erase_region_with_style(tool, address, len, EraseRegionStyle::Range)
+    }
+
+    /// 按块对齐拆分区域擦除，每擦完一块更新一次字节进度，并在块之间响应取消
+    pub fn erase_region_with_style<T>(
+        tool: &mut T,
+        address: u32,
+        len: u32,
+        style: EraseRegionStyle,
+    ) -> Result<()>
     where
         T: SifliToolTrait + RamCommand,
     {
         tool.check_cancelled()?;
         let progress = tool.progress();
-        let progress_bar = progress.create_spinner(ProgressOperation::EraseRegion {
-            address,
-            len,
-            style: EraseRegionStyle::Range,
-        });
+        let progress_bar = progress.create_bar(
+            len as u64,
+            ProgressOperation::EraseRegion {
+                address,
+                len,
+                style,
+            },
+        );
 
-        // 发送擦除区域命令
-        let _ = tool.command(Command::Erase { address, len });
+        for (chunk_address, chunk_len) in Self::split_region(address, len) {
+            tool.check_cancelled()?;
 
-        let mut io = for_tool(tool);
-        io.wait_for_pattern(
-            b"OK",
-            std::time::Duration::from_millis(30_000),
-            &format!(
-                "erasing region 0x{:08X}..0x{:08X}",
-                address,
-                address + len.saturating_sub(1)
-            ),
-        )?;
+            // 发送擦除区域命令
+            let _ = tool.command(Command::Erase {
+                address: chunk_address,
+                len: chunk_len,
+            });
+
+            let timeout = tool
+                .base()
+                .timeouts
+                .erase_region_timeout(chunk_len, &tool.base().memory_type);
+            let mut io = for_tool(tool);
+            io.wait_for_pattern(
+                b"OK",
+                timeout,
+                &format!(
+                    "erasing region 0x{:08X}..0x{:08X}",
+                    chunk_address,
+                    chunk_address + chunk_len.saturating_sub(1)
+                ),
+            )?;
+
+            progress_bar.inc(chunk_len as u64);
+        }
 
         progress_bar.finish(ProgressStatus::Success);
 
         Ok(())
     }
 
+    /// 空白检查：逐块用 `burn_verify` 比对全 0xFF 数据的 CRC，遇到第一个非空白块即返回
+    /// [`Error::NotBlank`]
+    pub fn verify_blank<T>(tool: &mut T, address: u32, len: u32) -> Result<()>
+    where
+        T: SifliToolTrait + RamCommand,
+    {
+        tool.check_cancelled()?;
+        let progress = tool.progress();
+        let progress_bar =
+            progress.create_bar(len as u64, ProgressOperation::VerifyBlank { address, len });
+
+        let full_chunk_crc = Utils::calculate_crc32(&[0xFF; Self::BLANK_CHECK_CHUNK_SIZE as usize]);
+        let mut current = address;
+        let mut remaining = len;
+        while remaining > 0 {
+            tool.check_cancelled()?;
+            let chunk_len = remaining.min(Self::BLANK_CHECK_CHUNK_SIZE);
+            let crc = if chunk_len == Self::BLANK_CHECK_CHUNK_SIZE {
+                full_chunk_crc
+            } else {
+                Utils::calculate_crc32(&vec![0xFF; chunk_len as usize])
+            };
+
+            let response = tool.command(Command::Verify {
+                address: current,
+                len: chunk_len,
+                crc,
+            })?;
+            if response != Response::Ok {
+                let error = Error::NotBlank {
+                    address: current,
+                    len: chunk_len,
+                };
+                progress_bar.finish(ProgressStatus::Failed(error.to_string()));
+                return Err(error);
+            }
+
+            progress_bar.inc(chunk_len as u64);
+            current = current.wrapping_add(chunk_len);
+            remaining -= chunk_len;
+        }
+
+        progress_bar.finish(ProgressStatus::Success);
+        Ok(())
+    }
+
+    /// 把 `[address, address + len)` 拆分为不跨越 [`Self::ERASE_CHUNK_SIZE`] 边界的子区间
+    pub fn split_region(address: u32, len: u32) -> Vec<(u32, u32)> {
+        let mut chunks = Vec::new();
+        let mut current = address;
+        let mut remaining = len;
+        while remaining > 0 {
+            let boundary_offset = Self::ERASE_CHUNK_SIZE - current % Self::ERASE_CHUNK_SIZE;
+            let chunk_len = remaining.min(boundary_offset);
+            chunks.push((current, chunk_len));
+            current = current.wrapping_add(chunk_len);
+            remaining -= chunk_len;
+        }
+        chunks
+    }
+
     /// 解析擦除地址参数
     pub fn parse_address(address_str: &str) -> Result<u32> {
         Utils::str_to_u32(address_str)
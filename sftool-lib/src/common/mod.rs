@@ -1,6 +1,8 @@
 // 公共模块，包含可在不同芯片间复用的逻辑
 
 pub mod erase_flash;
+pub mod net_port;
+pub mod port;
 pub mod ram_command;
 pub mod read_flash;
 pub mod reset;
@@ -0,0 +1,486 @@
+//! Serial ports reached over the network, e.g. boards behind ser2net.
+//!
+//! * `tcp://host:port` – raw TCP; bytes are forwarded unchanged and line
+//!   settings (baud rate, RTS/DTR) stay whatever the server configured.
+//! * `rfc2217://host:port` – Telnet with the RFC 2217 COM-PORT-OPTION, so
+//!   baud rate changes, RTS/DTR toggling and buffer purges reach the remote
+//!   UART.
+
+use crate::{Error, Result};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const TCP_SCHEME: &str = "tcp://";
+const RFC2217_SCHEME: &str = "rfc2217://";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Telnet 命令
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+// Telnet 选项
+const OPT_BINARY: u8 = 0;
+const OPT_SGA: u8 = 3;
+const OPT_COM_PORT: u8 = 44;
+
+// RFC 2217 客户端到服务器的子命令，服务器的应答为 +100
+const SET_BAUDRATE: u8 = 1;
+const SET_DATASIZE: u8 = 2;
+const SET_PARITY: u8 = 3;
+const SET_STOPSIZE: u8 = 4;
+const SET_CONTROL: u8 = 5;
+const PURGE_DATA: u8 = 12;
+
+const CONTROL_NO_FLOW: u8 = 1;
+const CONTROL_DTR_ON: u8 = 8;
+const CONTROL_DTR_OFF: u8 = 9;
+const CONTROL_RTS_ON: u8 = 11;
+const CONTROL_RTS_OFF: u8 = 12;
+
+const PURGE_RX: u8 = 1;
+const PURGE_TX: u8 = 2;
+const PURGE_BOTH: u8 = 3;
+
+/// Returns true if `port_name` is a `tcp://` or `rfc2217://` URL.
+pub fn is_network_port(port_name: &str) -> bool {
+    port_name.starts_with(TCP_SCHEME) || port_name.starts_with(RFC2217_SCHEME)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Raw,
+    Rfc2217,
+}
+
+#[derive(Default)]
+enum TelnetState {
+    #[default]
+    Data,
+    Iac,
+    Negotiate(u8),
+    Sub(Vec<u8>),
+    SubIac(Vec<u8>),
+}
+
+/// 在所有克隆之间共享的接收状态
+#[derive(Default)]
+struct Shared {
+    decoded: VecDeque<u8>,
+    telnet: TelnetState,
+    /// 已经应答过的协商，避免协商循环
+    answered: HashSet<(u8, u8)>,
+    remote_baud_rate: Option<u32>,
+}
+
+/// A [`SerialPort`] backed by a TCP connection.
+pub struct NetworkSerialPort {
+    name: String,
+    stream: TcpStream,
+    protocol: Protocol,
+    shared: Arc<Mutex<Shared>>,
+    baud_rate: u32,
+    timeout: Duration,
+}
+
+impl NetworkSerialPort {
+    /// Connect to a `tcp://host:port` or `rfc2217://host:port` URL.
+    pub fn open(url: &str, baud_rate: u32, timeout: Duration) -> Result<Self> {
+        let (protocol, address) = if let Some(address) = url.strip_prefix(TCP_SCHEME) {
+            (Protocol::Raw, address)
+        } else if let Some(address) = url.strip_prefix(RFC2217_SCHEME) {
+            (Protocol::Rfc2217, address)
+        } else {
+            return Err(Error::invalid_input(format!(
+                "unsupported network port '{}', expected tcp://host:port or rfc2217://host:port",
+                url
+            )));
+        };
+        let address = address.trim_end_matches('/');
+
+        let socket_address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::invalid_input(format!("cannot resolve '{}'", address)))?;
+        let stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+
+        let mut port = Self {
+            name: url.to_string(),
+            stream,
+            protocol,
+            shared: Arc::new(Mutex::new(Shared::default())),
+            baud_rate,
+            timeout,
+        };
+
+        if protocol == Protocol::Rfc2217 {
+            for (verb, option) in [
+                (WILL, OPT_COM_PORT),
+                (WILL, OPT_BINARY),
+                (DO, OPT_BINARY),
+                (DO, OPT_SGA),
+            ] {
+                port.send_raw(&[IAC, verb, option])?;
+            }
+            port.send_com_port(SET_BAUDRATE, &baud_rate.to_be_bytes())?;
+            port.send_com_port(SET_DATASIZE, &[8])?;
+            port.send_com_port(SET_PARITY, &[1])?;
+            port.send_com_port(SET_STOPSIZE, &[1])?;
+            port.send_com_port(SET_CONTROL, &[CONTROL_NO_FLOW])?;
+        }
+
+        Ok(port)
+    }
+
+    /// Baud rate last acknowledged by an RFC 2217 server.
+    pub fn remote_baud_rate(&self) -> Option<u32> {
+        self.shared.lock().unwrap().remote_baud_rate
+    }
+
+    fn send_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.stream.write_all(bytes)
+    }
+
+    fn send_com_port(&mut self, command: u8, value: &[u8]) -> io::Result<()> {
+        let mut frame = vec![IAC, SB, OPT_COM_PORT, command];
+        for &byte in value {
+            frame.push(byte);
+            if byte == IAC {
+                frame.push(IAC);
+            }
+        }
+        frame.extend_from_slice(&[IAC, SE]);
+        self.send_raw(&frame)
+    }
+
+    /// 处理从网络收到的原始字节，RFC 2217 模式下剥离 Telnet 命令
+    fn process_incoming(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut shared = self.shared.lock().unwrap();
+        if self.protocol == Protocol::Raw {
+            shared.decoded.extend(bytes.iter().copied());
+            return Ok(());
+        }
+
+        let mut replies = Vec::new();
+        for &byte in bytes {
+            let state = std::mem::take(&mut shared.telnet);
+            shared.telnet = match state {
+                TelnetState::Data if byte == IAC => TelnetState::Iac,
+                TelnetState::Data => {
+                    shared.decoded.push_back(byte);
+                    TelnetState::Data
+                }
+                TelnetState::Iac => match byte {
+                    IAC => {
+                        shared.decoded.push_back(IAC);
+                        TelnetState::Data
+                    }
+                    DO | DONT | WILL | WONT => TelnetState::Negotiate(byte),
+                    SB => TelnetState::Sub(Vec::new()),
+                    _ => TelnetState::Data,
+                },
+                TelnetState::Negotiate(verb) => {
+                    if let Some(reply) = negotiation_reply(verb, byte)
+                        && shared.answered.insert((verb, byte))
+                    {
+                        replies.extend_from_slice(&[IAC, reply, byte]);
+                    }
+                    TelnetState::Data
+                }
+                TelnetState::Sub(data) if byte == IAC => TelnetState::SubIac(data),
+                TelnetState::Sub(mut data) => {
+                    data.push(byte);
+                    TelnetState::Sub(data)
+                }
+                TelnetState::SubIac(mut data) => match byte {
+                    SE => {
+                        handle_subnegotiation(&mut shared, &data);
+                        TelnetState::Data
+                    }
+                    _ => {
+                        data.push(byte);
+                        TelnetState::Sub(data)
+                    }
+                },
+            };
+        }
+        drop(shared);
+
+        if !replies.is_empty() {
+            self.send_raw(&replies)?;
+        }
+        Ok(())
+    }
+
+    /// 读取套接字中当前可用的数据，不阻塞
+    fn drain_socket(&mut self) -> io::Result<()> {
+        self.stream.set_nonblocking(true)?;
+        let mut buffer = [0u8; 1024];
+        let result = loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => break Ok(()),
+                Ok(count) => {
+                    if let Err(e) = self.process_incoming(&buffer[..count]) {
+                        break Err(e);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break Ok(()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => break Err(e),
+            }
+        };
+        self.stream.set_nonblocking(false)?;
+        result
+    }
+
+    fn set_control(&mut self, value: u8) -> serialport::Result<()> {
+        if self.protocol == Protocol::Raw {
+            tracing::debug!("Ignoring modem control on raw TCP port {}", self.name);
+            return Ok(());
+        }
+        self.send_com_port(SET_CONTROL, &[value])?;
+        Ok(())
+    }
+}
+
+fn negotiation_reply(verb: u8, option: u8) -> Option<u8> {
+    let supported = matches!(option, OPT_BINARY | OPT_SGA | OPT_COM_PORT);
+    match verb {
+        DO if supported => Some(WILL),
+        DO => Some(WONT),
+        WILL if supported => Some(DO),
+        WILL => Some(DONT),
+        // DONT/WONT 无需应答
+        _ => None,
+    }
+}
+
+fn handle_subnegotiation(shared: &mut Shared, data: &[u8]) {
+    if let [OPT_COM_PORT, command, value @ ..] = data
+        && *command == SET_BAUDRATE + 100
+        && value.len() == 4
+    {
+        let baud = u32::from_be_bytes([value[0], value[1], value[2], value[3]]);
+        tracing::debug!("RFC 2217 server acknowledged baud rate {}", baud);
+        shared.remote_baud_rate = Some(baud);
+    }
+}
+
+impl Read for NetworkSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            {
+                let mut shared = self.shared.lock().unwrap();
+                if !shared.decoded.is_empty() {
+                    let count = buf.len().min(shared.decoded.len());
+                    for slot in buf.iter_mut().take(count) {
+                        *slot = shared.decoded.pop_front().unwrap();
+                    }
+                    return Ok(count);
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::new(ErrorKind::TimedOut, "operation timed out"));
+            }
+            self.stream.set_read_timeout(Some(remaining))?;
+
+            let mut raw = [0u8; 4096];
+            match self.stream.read(&mut raw) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        ErrorKind::ConnectionAborted,
+                        "network serial port closed by peer",
+                    ));
+                }
+                Ok(count) => self.process_incoming(&raw[..count])?,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    return Err(io::Error::new(ErrorKind::TimedOut, "operation timed out"));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Write for NetworkSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.protocol == Protocol::Raw || !buf.contains(&IAC) {
+            self.stream.write_all(buf)?;
+            return Ok(buf.len());
+        }
+
+        let mut escaped = Vec::with_capacity(buf.len() + 16);
+        for &byte in buf {
+            escaped.push(byte);
+            if byte == IAC {
+                escaped.push(IAC);
+            }
+        }
+        self.stream.write_all(&escaped)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl SerialPort for NetworkSerialPort {
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.baud_rate = baud_rate;
+        if self.protocol == Protocol::Raw {
+            tracing::warn!(
+                "Raw TCP port {} cannot change the remote baud rate to {}",
+                self.name,
+                baud_rate
+            );
+            return Ok(());
+        }
+        self.send_com_port(SET_BAUDRATE, &baud_rate.to_be_bytes())?;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.set_control(if level {
+            CONTROL_RTS_ON
+        } else {
+            CONTROL_RTS_OFF
+        })
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.set_control(if level {
+            CONTROL_DTR_ON
+        } else {
+            CONTROL_DTR_OFF
+        })
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.shared.lock().unwrap().decoded.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        // clear 只拿到 &self，通过克隆的连接完成本地排空和远端清空
+        let mut port = self.try_clone_port()?;
+        if matches!(buffer_to_clear, ClearBuffer::Input | ClearBuffer::All) {
+            port.drain_socket()?;
+            port.shared.lock().unwrap().decoded.clear();
+        }
+        if port.protocol == Protocol::Rfc2217 {
+            let purge = match buffer_to_clear {
+                ClearBuffer::Input => PURGE_RX,
+                ClearBuffer::Output => PURGE_TX,
+                ClearBuffer::All => PURGE_BOTH,
+            };
+            port.send_com_port(PURGE_DATA, &[purge])?;
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.try_clone_port()?))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+impl NetworkSerialPort {
+    fn try_clone_port(&self) -> io::Result<Self> {
+        Ok(Self {
+            name: self.name.clone(),
+            stream: self.stream.try_clone()?,
+            protocol: self.protocol,
+            shared: self.shared.clone(),
+            baud_rate: self.baud_rate,
+            timeout: self.timeout,
+        })
+    }
+}
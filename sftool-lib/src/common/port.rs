@@ -0,0 +1,34 @@
+use crate::Result;
+use crate::SifliToolBase;
+use crate::common::net_port::{NetworkSerialPort, is_network_port};
+use crate::transcript::{RecordingSerialPort, TranscriptMode};
+use serialport::SerialPort;
+use std::time::Duration;
+
+/// 工具默认的初始波特率
+pub const DEFAULT_BAUD: u32 = 1000000;
+
+/// 根据基础配置打开串口，按需包装录制或回放
+pub fn open_port(base: &SifliToolBase) -> Result<Box<dyn SerialPort>> {
+    if let Some(TranscriptMode::Replay(replay)) = &base.transcript {
+        return Ok(Box::new(replay.clone()));
+    }
+
+    let port = open_serial(&base.port_name, DEFAULT_BAUD, Duration::from_secs(5))?;
+
+    match &base.transcript {
+        Some(TranscriptMode::Record(path)) => {
+            Ok(Box::new(RecordingSerialPort::create(port, path)?))
+        }
+        _ => Ok(port),
+    }
+}
+
+/// 打开本地串口，或 `tcp://`、`rfc2217://` 形式的网络串口
+pub fn open_serial(port_name: &str, baud: u32, timeout: Duration) -> Result<Box<dyn SerialPort>> {
+    if is_network_port(port_name) {
+        return Ok(Box::new(NetworkSerialPort::open(port_name, baud, timeout)?));
+    }
+
+    Ok(serialport::new(port_name, baud).timeout(timeout).open()?)
+}
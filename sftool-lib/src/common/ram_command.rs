@@ -1,6 +1,10 @@
 use crate::common::serial_io::SerialIo;
+use crate::policy::{RetryPolicy, TimeoutPolicy};
 use crate::{Error, Result};
+use serialport::ClearBuffer;
+use std::io::ErrorKind;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 use strum::{Display, EnumString};
 
 /// 通用的RAM命令枚举，可在不同芯片间复用
@@ -31,6 +35,13 @@ pub enum Command {
     SetBaud { baud: u32, delay: u32 },
 }
 
+impl Command {
+    /// 重复执行不会改变设备状态的命令，失败后可以安全重试
+    pub fn is_idempotent(&self) -> bool {
+        matches!(self, Command::Verify { .. } | Command::Read { .. })
+    }
+}
+
 /// 通用的命令响应枚举
 #[derive(EnumString, Display, Debug, Clone, PartialEq, Eq)]
 pub enum Response {
@@ -64,6 +75,7 @@ pub struct CommandConfig {
     pub compat_mode: bool,
     pub chunk_size: usize,
     pub chunk_delay_ms: u64,
+    pub response_timeout: Duration,
 }
 
 impl Default for CommandConfig {
@@ -72,6 +84,7 @@ impl Default for CommandConfig {
             compat_mode: false,
             chunk_size: 256,
             chunk_delay_ms: 10,
+            response_timeout: TimeoutPolicy::default().command,
         }
     }
 }
@@ -84,46 +97,64 @@ pub fn is_sd_memory(memory_type: &str) -> bool {
     memory_type == "sd" || memory_type.starts_with("sd_")
 }
 
-impl RamOps {
-    const DEFAULT_TIMEOUT_MS: u128 = 4000;
-    const ERASE_ALL_TIMEOUT_MS: u128 = 30 * 1000;
+pub fn is_nand_memory(memory_type: &str) -> bool {
+    let memory_type = memory_type.to_ascii_lowercase();
+    memory_type == "nand" || memory_type.starts_with("nand_")
+}
 
+impl RamOps {
     /// 发送命令并等待响应的通用实现
+    ///
+    /// 幂等命令在超时后按 `retry` 重新发送。
     pub fn send_command_and_wait_response(
         io: &mut SerialIo<'_>,
         cmd: Command,
         command_str: &str,
         memory_type: &str,
+        timeouts: &TimeoutPolicy,
+        retry: &RetryPolicy,
     ) -> Result<Response> {
         tracing::debug!("command: {:?}", cmd);
 
-        // 发送命令
-        io.write_all(command_str.as_bytes())?;
-        io.flush()?;
-        // 在macOS上，FTDI的驱动似乎不高兴我们清除输入缓冲区，这可能会导致后续要发送的内容被截断
-        // 因此这个地方我们不再需要清理缓冲区，应该在后续的操作中滤除掉额外的信息
-        // port.clear(serialport::ClearBuffer::All)?;
-
-        // 确定超时时间
-        let timeout = match cmd {
-            Command::EraseAll { .. } => Self::ERASE_ALL_TIMEOUT_MS,
-            _ => Self::DEFAULT_TIMEOUT_MS,
-        };
-        let timeout = if is_sd_memory(memory_type) {
-            timeout * 3
+        let timeout = timeouts.command_timeout(&cmd, memory_type);
+        let retries = if cmd.is_idempotent() {
+            retry.retries
         } else {
-            timeout
+            0
         };
 
-        // 某些命令直接返回成功，不等待响应
-        match cmd {
-            Command::SetBaud { .. } | Command::Read { .. } | Command::Erase { .. } => {
-                return Ok(Response::Ok);
+        let mut attempt = 0;
+        loop {
+            // 发送命令
+            io.write_all(command_str.as_bytes())?;
+            io.flush()?;
+            // 在macOS上，FTDI的驱动似乎不高兴我们清除输入缓冲区，这可能会导致后续要发送的内容被截断
+            // 因此这个地方我们不再需要清理缓冲区，应该在后续的操作中滤除掉额外的信息
+            // port.clear(serialport::ClearBuffer::All)?;
+
+            // 某些命令直接返回成功，不等待响应
+            match cmd {
+                Command::SetBaud { .. } | Command::Read { .. } | Command::Erase { .. } => {
+                    return Ok(Response::Ok);
+                }
+                _ => (),
             }
-            _ => (),
-        }
 
-        Self::wait_for_response(io, timeout)
+            match Self::wait_for_response(io, timeout) {
+                Err(e) if attempt < retries && RetryPolicy::is_retryable(&e) => {
+                    attempt += 1;
+                    tracing::warn!(
+                        "{:?} failed ({}), retrying ({}/{})",
+                        cmd,
+                        e,
+                        attempt,
+                        retries
+                    );
+                    io.sleep(retry.delay)?;
+                }
+                result => return result,
+            }
+        }
     }
 
     /// 发送数据并等待响应的通用实现
@@ -141,18 +172,18 @@ impl RamOps {
             for chunk in data.chunks(config.chunk_size) {
                 io.write_all(chunk)?;
                 io.flush()?;
-                io.sleep(std::time::Duration::from_millis(config.chunk_delay_ms))?;
+                io.sleep(Duration::from_millis(config.chunk_delay_ms))?;
             }
         }
 
-        Self::wait_for_response(io, Self::DEFAULT_TIMEOUT_MS)
+        Self::wait_for_response(io, config.response_timeout)
     }
 
     /// 等待响应的通用实现
-    fn wait_for_response(io: &mut SerialIo<'_>, timeout_ms: u128) -> Result<Response> {
+    fn wait_for_response(io: &mut SerialIo<'_>, timeout: Duration) -> Result<Response> {
         let matched = io.wait_for_patterns(
             &RESPONSE_STR_TABLE.map(str::as_bytes),
-            std::time::Duration::from_millis(timeout_ms as u64),
+            timeout,
             "RAM command response",
         )?;
         tracing::debug!(
@@ -163,6 +194,44 @@ impl RamOps {
             .map_err(|e| Error::invalid_input(e.to_string()))
     }
 
+    /// 丢弃设备仍在发送的数据（例如中断的 burn_read），直到重新看到 shell 提示符
+    pub fn resync_shell(io: &mut SerialIo<'_>, max_wait: Duration) -> Result<()> {
+        let deadline = Instant::now() + max_wait;
+        let mut buffer = [0u8; 1024];
+        loop {
+            if Instant::now() >= deadline {
+                return Err(Error::timeout("waiting for the device to go quiet"));
+            }
+            match io.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(_) => continue,
+                Err(Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) =>
+                {
+                    break;
+                }
+                Err(Error::Io(e)) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        io.clear(ClearBuffer::Input)?;
+        Self::wait_for_shell_prompt(io, b"msh >", 200, 5)
+    }
+
+    /// 等待 stub 启动后的提示符，并返回此前的启动日志
+    pub fn capture_stub_log(
+        io: &mut SerialIo<'_>,
+        prompt: &[u8],
+        retry_interval_ms: u64,
+        max_retries: u32,
+    ) -> Result<Vec<u8>> {
+        io.wait_for_prompt_capture(
+            prompt,
+            Duration::from_millis(retry_interval_ms),
+            max_retries,
+        )
+    }
+
     /// 等待shell提示符的通用实现
     pub fn wait_for_shell_prompt(
         io: &mut SerialIo<'_>,
@@ -172,7 +241,7 @@ impl RamOps {
     ) -> Result<()> {
         io.wait_for_prompt(
             prompt,
-            std::time::Duration::from_millis(retry_interval_ms),
+            Duration::from_millis(retry_interval_ms),
             max_retries,
         )
     }
@@ -1,5 +1,6 @@
-use crate::common::ram_command::{Command, RamCommand};
-use crate::common::serial_io::{SerialIo, for_tool};
+use crate::common::ram_command::{Command, RamCommand, RamOps};
+use crate::common::serial_io::{SerialIo, for_tool, sleep_with_cancel};
+use crate::policy::RetryPolicy;
 use crate::progress::{ProgressHandle, ProgressOperation, ProgressStatus};
 use crate::utils::Utils;
 use crate::{Error, Result, SifliToolTrait};
@@ -22,7 +23,6 @@ pub struct FlashReader;
 
 impl FlashReader {
     const START_TRANS_MARKER: &'static [u8] = b"start_trans\r\n";
-    const READ_TIMEOUT_MS: u128 = 10_000;
     const READ_CHUNK_SIZE: usize = 16 * 1024;
     const CRC_32_ALGO: Algorithm<u32> = Algorithm {
         width: 32,
@@ -63,6 +63,8 @@ impl FlashReader {
     }
 
     /// 从Flash读取数据的通用实现
+    ///
+    /// 超时或 CRC 不一致时按 `RetryPolicy` 重新发送 burn_read。
     pub fn read_flash_data<T>(
         tool: &mut T,
         address: u32,
@@ -73,28 +75,71 @@ impl FlashReader {
         T: SifliToolTrait + RamCommand,
     {
         tool.check_cancelled()?;
+        let retry = tool.base().retry.clone();
+        let timeout = tool.base().timeouts.read_timeout(&tool.base().memory_type);
         let progress = tool.progress();
-        let progress_bar =
-            progress.create_bar(size as u64, ProgressOperation::ReadFlash { address, size });
 
         let mut temp_file = tempfile()?;
+        let mut attempt = 0;
+        loop {
+            let progress_bar =
+                progress.create_bar(size as u64, ProgressOperation::ReadFlash { address, size });
+            match Self::read_once(tool, address, size, &mut temp_file, &progress_bar, timeout) {
+                Ok(()) => {
+                    progress_bar.finish(ProgressStatus::Success);
+                    break;
+                }
+                Err(e) if attempt < retry.retries && RetryPolicy::is_retryable(&e) => {
+                    attempt += 1;
+                    progress_bar.finish(ProgressStatus::Retry);
+                    tracing::warn!(
+                        "Reading 0x{:08X} failed ({}), retrying ({}/{})",
+                        address,
+                        e,
+                        attempt,
+                        retry.retries
+                    );
+                    sleep_with_cancel(&tool.base().cancel_token, retry.delay)?;
+                    {
+                        let mut io = for_tool(tool);
+                        RamOps::resync_shell(&mut io, timeout)?;
+                    }
+                    temp_file.set_len(0)?;
+                    temp_file.rewind()?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
 
-        // 读取一次即可，由设备负责连续发送数据
-        tool.command(Command::Read { address, len: size })?;
+        temp_file.rewind()?;
+        let mut output_file = File::create(output_path)?;
+        std::io::copy(&mut temp_file, &mut output_file)?;
 
-        let (expected_crc, actual_crc) = {
-            let mut io = for_tool(tool);
+        Ok(())
+    }
 
-            Self::wait_for_marker(&mut io, Self::START_TRANS_MARKER, "start_trans marker")?;
+    fn read_once<T>(
+        tool: &mut T,
+        address: u32,
+        size: u32,
+        temp_file: &mut File,
+        progress_bar: &ProgressHandle,
+        timeout: Duration,
+    ) -> Result<()>
+    where
+        T: SifliToolTrait + RamCommand,
+    {
+        // 读取一次即可，由设备负责连续发送数据
+        tool.command(Command::Read { address, len: size })?;
 
-            let actual_crc =
-                Self::receive_payload(&mut io, size, &mut temp_file, &progress_bar, address)?;
+        let mut io = for_tool(tool);
+        io.wait_for_pattern(Self::START_TRANS_MARKER, timeout, "start_trans marker")?;
 
-            let expected_crc = Self::read_crc_value(&mut io)?;
-            Self::expect_ok(&mut io)?;
+        let actual_crc =
+            Self::receive_payload(&mut io, size, temp_file, progress_bar, address, timeout)?;
 
-            (expected_crc, actual_crc)
-        };
+        let expected_crc = Self::read_crc_value(&mut io, timeout)?;
+        Self::expect_ok(&mut io, timeout)?;
 
         if actual_crc != expected_crc {
             return Err(Error::CrcMismatch {
@@ -103,21 +148,6 @@ impl FlashReader {
             });
         }
 
-        progress_bar.finish(ProgressStatus::Success);
-
-        temp_file.seek(std::io::SeekFrom::Start(0))?;
-        let mut output_file = File::create(output_path)?;
-        std::io::copy(&mut temp_file, &mut output_file)?;
-
-        Ok(())
-    }
-
-    fn wait_for_marker(io: &mut SerialIo<'_>, marker: &[u8], context: &str) -> Result<()> {
-        io.wait_for_pattern(
-            marker,
-            Duration::from_millis(Self::READ_TIMEOUT_MS as u64),
-            context,
-        )?;
         Ok(())
     }
 
@@ -127,6 +157,7 @@ impl FlashReader {
         temp_file: &mut File,
         progress_bar: &ProgressHandle,
         address: u32,
+        timeout: Duration,
     ) -> Result<u32> {
         let mut remaining = size as usize;
         let buffer_len = remaining.clamp(1usize, Self::READ_CHUNK_SIZE);
@@ -143,7 +174,7 @@ impl FlashReader {
             let current_address = address.saturating_add(processed as u32);
             io.read_exact_with_timeout(
                 chunk,
-                Duration::from_millis(Self::READ_TIMEOUT_MS as u64),
+                timeout,
                 &format!("reading flash at 0x{:08X}", current_address),
             )?;
 
@@ -158,8 +189,8 @@ impl FlashReader {
         Ok(digest.finalize())
     }
 
-    fn read_crc_value(io: &mut SerialIo<'_>) -> Result<u32> {
-        let line = Self::read_non_empty_line(io, "CRC response")?;
+    fn read_crc_value(io: &mut SerialIo<'_>, timeout: Duration) -> Result<u32> {
+        let line = Self::read_non_empty_line(io, timeout, "CRC response")?;
         let lower = line.to_ascii_lowercase();
         let prefix = "crc:0x";
 
@@ -172,17 +203,21 @@ impl FlashReader {
             .map_err(|e| Error::protocol(format!("invalid CRC '{}': {}", line, e)))
     }
 
-    fn expect_ok(io: &mut SerialIo<'_>) -> Result<()> {
-        let line = Self::read_non_empty_line(io, "OK response")?;
+    fn expect_ok(io: &mut SerialIo<'_>, timeout: Duration) -> Result<()> {
+        let line = Self::read_non_empty_line(io, timeout, "OK response")?;
         if line != "OK" {
             return Err(Error::protocol(format!("unexpected response: {}", line)));
         }
         Ok(())
     }
 
-    fn read_non_empty_line(io: &mut SerialIo<'_>, context: &str) -> Result<String> {
+    fn read_non_empty_line(
+        io: &mut SerialIo<'_>,
+        timeout: Duration,
+        context: &str,
+    ) -> Result<String> {
         loop {
-            let line = Self::read_line(io, context)?;
+            let line = io.read_line_with_timeout(timeout, context)?;
             let trimmed = line.trim().to_string();
             if trimmed.is_empty() {
                 continue;
@@ -190,8 +225,4 @@ impl FlashReader {
             return Ok(trimmed);
         }
     }
-
-    fn read_line(io: &mut SerialIo<'_>, context: &str) -> Result<String> {
-        io.read_line_with_timeout(Duration::from_millis(Self::READ_TIMEOUT_MS as u64), context)
-    }
 }
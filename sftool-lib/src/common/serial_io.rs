@@ -320,6 +320,18 @@ impl<'a> SerialIo<'a> {
         retry_interval: Duration,
         max_retries: u32,
     ) -> Result<()> {
+        self.wait_for_prompt_capture(prompt, retry_interval, max_retries)
+            .map(|_| ())
+    }
+
+    /// 与 [`Self::wait_for_prompt`] 相同，但返回等待期间收到的全部输出（含提示符）
+    pub fn wait_for_prompt_capture(
+        &mut self,
+        prompt: &[u8],
+        retry_interval: Duration,
+        max_retries: u32,
+    ) -> Result<Vec<u8>> {
+        let mut captured = Vec::new();
         let mut retry_count = 0u32;
         let mut window = VecDeque::with_capacity(prompt.len().max(1));
         let mut last_retry = Instant::now();
@@ -347,6 +359,7 @@ impl<'a> SerialIo<'a> {
             match self.port.read(&mut byte) {
                 Ok(0) => self.sleep(IDLE_BACKOFF)?,
                 Ok(_) => {
+                    captured.push(byte[0]);
                     window.push_back(byte[0]);
                     if window.len() > prompt.len() {
                         window.pop_front();
@@ -355,7 +368,7 @@ impl<'a> SerialIo<'a> {
                     if window.len() == prompt.len()
                         && window.iter().copied().eq(prompt.iter().copied())
                     {
-                        return Ok(());
+                        return Ok(captured);
                     }
                 }
                 Err(error)
@@ -1,7 +1,8 @@
 use crate::common::serial_io::{
     CancelableReader, CancelableWriter, for_tool, is_cancelled_io_error, sleep_with_cancel,
 };
-use crate::{Error, Result, SifliTool};
+use crate::efuse::EfuseLayout;
+use crate::{Error, Result, SifliTool, SifliToolTrait};
 use probe_rs::architecture::arm::armv8m::Dcrdr;
 use probe_rs::{MemoryMappedRegister, memory_mapped_bitfield_register};
 use std::cmp::{max, min};
@@ -11,6 +12,8 @@ use std::time::{Duration, Instant};
 
 pub const START_WORD: [u8; 2] = [0x7E, 0x79];
 pub const DEFUALT_RECV_TIMEOUT: Duration = Duration::from_secs(3);
+/// 单次 MEMRead 读取的最大字数
+pub const MEM_READ_MAX_WORDS: usize = 256;
 
 #[derive(Debug)]
 pub enum SifliUartCommand<'a> {
@@ -137,6 +140,7 @@ pub trait SifliDebug {
     fn debug_command(&mut self, command: SifliUartCommand) -> Result<SifliUartResponse>;
     fn debug_write_word32(&mut self, addr: u32, data: u32) -> Result<()>;
     fn debug_read_word32(&mut self, addr: u32) -> Result<u32>;
+    fn debug_read_memory(&mut self, addr: u32, len: usize) -> Result<Vec<u8>>;
     fn debug_write_core_reg(&mut self, reg: u16, data: u32) -> Result<()>;
     fn debug_write_memory(&mut self, addr: u32, data: &[u8]) -> Result<()>;
     fn debug_run(&mut self) -> Result<()>;
@@ -144,6 +148,24 @@ pub trait SifliDebug {
     fn debug_step(&mut self) -> Result<()>;
 }
 
+/// 可以通过 UART 调试通道访问的芯片
+///
+/// eFuse、RTT、寄存器和 RAM 运行都基于这个 trait 实现，芯片只需提供下面几个差异点。
+pub trait DebugChip: SifliDebug + SifliToolTrait {
+    /// 进入调试模式，按 `connect_attempts` 重试
+    fn debug_connect(&mut self) -> Result<()>;
+
+    /// 复位芯片前暂停其余内核，默认不需要
+    fn prepare_reset(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// eFuse 控制器参数，不支持读取 eFuse 时返回 `None`
+    fn efuse_layout(&self) -> Option<EfuseLayout> {
+        None
+    }
+}
+
 // Trait defining chip-specific frame formatting behavior
 pub trait ChipFrameFormat {
     /// Create chip-specific header with appropriate endianness and fields
@@ -200,6 +222,7 @@ pub fn send_command<F: ChipFrameFormat, W: Write>(
 
 pub fn recv_response<F: ChipFrameFormat, R: Read>(
     reader: &mut BufReader<R>,
+    timeout: Duration,
 ) -> Result<SifliUartResponse> {
     let start_time = Instant::now();
     let mut temp: Vec<u8> = vec![];
@@ -209,11 +232,8 @@ pub fn recv_response<F: ChipFrameFormat, R: Read>(
     let mut buffer = vec![];
 
     loop {
-        if start_time.elapsed() >= DEFUALT_RECV_TIMEOUT {
-            tracing::warn!(
-                "Receive timeout: {} seconds",
-                DEFUALT_RECV_TIMEOUT.as_secs()
-            );
+        if start_time.elapsed() >= timeout {
+            tracing::warn!("Receive timeout: {} ms", timeout.as_millis());
             return Err(RecvError::Timeout.into());
         }
 
@@ -331,6 +351,13 @@ pub mod common_debug {
         command: SifliUartCommand,
     ) -> Result<SifliUartResponse> {
         tracing::info!("Command: {}", command);
+        if let SifliUartCommand::Exit = command {
+            // 退出调试模式是收尾操作，取消之后也要发出，不经过可取消的写端
+            let mut writer = BufWriter::new(tool.port().try_clone()?);
+            send_command::<F, _>(&mut writer, &command)?;
+            return Ok(SifliUartResponse::Exit);
+        }
+
         let writer: CancelableWriter = {
             let mut io = for_tool(tool);
             io.try_clone_writer()?
@@ -343,16 +370,14 @@ pub mod common_debug {
         };
         let mut buf_reader = BufReader::new(reader);
 
+        let timeout = tool.base().timeouts.debug;
         let ret = send_command::<F, _>(&mut buf_writer, &command);
         if let Err(e) = ret {
             tracing::error!("Command send error: {:?}", e);
             return Err(e);
         }
 
-        match command {
-            SifliUartCommand::Exit => Ok(SifliUartResponse::Exit),
-            _ => recv_response::<F, _>(&mut buf_reader),
-        }
+        recv_response::<F, _>(&mut buf_reader, timeout)
     }
 
     /// Common implementation for debug_read_word32
@@ -380,6 +405,46 @@ pub mod common_debug {
         }
     }
 
+    /// Common implementation for debug_read_memory
+    pub fn debug_read_memory_impl<T: SifliTool, F: ChipFrameFormat>(
+        tool: &mut T,
+        address: u32,
+        len: usize,
+    ) -> Result<Vec<u8>> {
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Read whole words covering the requested range, then cut it out
+        let start_aligned = address & !3;
+        let end_aligned = (address as usize + len).div_ceil(4) * 4;
+        let total_words = (end_aligned - start_aligned as usize) / 4;
+
+        let mut buffer = Vec::with_capacity(total_words * 4);
+        while buffer.len() < total_words * 4 {
+            let words = min(total_words - buffer.len() / 4, MEM_READ_MAX_WORDS);
+            let block_addr = start_aligned + buffer.len() as u32;
+            let command = SifliUartCommand::MEMRead {
+                addr: F::map_address(block_addr),
+                len: words as u16,
+            };
+            match debug_command_impl::<T, F>(tool, command)? {
+                SifliUartResponse::MEMRead { data } if data.len() == words * 4 => {
+                    for word in data.chunks_exact(4) {
+                        buffer.extend_from_slice(&F::decode_response_data(word).to_le_bytes());
+                    }
+                }
+                SifliUartResponse::MEMRead { .. } => {
+                    return Err(Error::invalid_input("invalid response length"));
+                }
+                _ => return Err(Error::invalid_input("invalid response")),
+            }
+        }
+
+        let offset = (address - start_aligned) as usize;
+        Ok(buffer[offset..offset + len].to_vec())
+    }
+
     /// Common implementation for debug_write_word32
     pub fn debug_write_word32_impl<T: SifliTool, F: ChipFrameFormat>(
         tool: &mut T,
@@ -560,7 +625,7 @@ mod tests {
     use crate::{
         BeforeOperation, CancelToken, EraseFlashParams, EraseFlashTrait, EraseRegionParams,
         ReadFlashParams, ReadFlashTrait, Result, SifliTool, SifliToolBase, SifliToolTrait,
-        WriteFlashParams, WriteFlashTrait,
+        VerifyParams, VerifyResult, WriteFlashParams, WriteFlashTrait,
     };
     use serialport::SerialPort;
     use std::sync::{Arc, Mutex};
@@ -586,6 +651,10 @@ mod tests {
             Ok(())
         }
 
+        fn negotiate_speed(&mut self, candidates: &[u32]) -> Result<u32> {
+            Ok(candidates[0])
+        }
+
         fn soft_reset(&mut self) -> Result<()> {
             Ok(())
         }
@@ -595,6 +664,10 @@ mod tests {
         fn write_flash(&mut self, _params: &WriteFlashParams) -> Result<()> {
             Ok(())
         }
+
+        fn verify_flash(&mut self, _params: &VerifyParams) -> Result<Vec<VerifyResult>> {
+            Ok(Vec::new())
+        }
     }
 
     impl ReadFlashTrait for TestTool {
@@ -611,10 +684,14 @@ mod tests {
         fn erase_region(&mut self, _params: &EraseRegionParams) -> Result<()> {
             Ok(())
         }
+
+        fn verify_blank(&mut self, _params: &EraseRegionParams) -> Result<()> {
+            Ok(())
+        }
     }
 
     impl SifliTool for TestTool {
-        fn create_tool(_base_param: SifliToolBase) -> Box<dyn SifliTool>
+        fn create_tool(_base_param: SifliToolBase) -> Result<Box<dyn SifliTool>>
         where
             Self: Sized,
         {
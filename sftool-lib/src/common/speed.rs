@@ -1,12 +1,54 @@
-use crate::common::ram_command::{Command, RamCommand, RamOps};
+use crate::common::port::DEFAULT_BAUD;
+use crate::common::ram_command::{
+    Command, DownloadStub, RamCommand, RamOps, Response, is_nand_memory, is_sd_memory,
+};
 use crate::common::serial_io::{for_tool, sleep_with_cancel};
-use crate::{Result, SifliToolTrait};
+use crate::{Error, Result, SifliToolTrait};
 use std::time::Duration;
 
+/// 波特率协商时 burn_verify 使用的地址，按存储类型区分
+///
+/// 地址取自各芯片 stub 自身：NAND 为 stub 初始化 NAND 句柄时使用的映射地址，
+/// SD 为 stub 里 SD 卡映射窗口的基地址，保证 burn_verify 真正访问 stub 所驱动的存储。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeAddresses {
+    pub nor: u32,
+    pub nand: u32,
+    pub sd: u32,
+}
+
+impl ProbeAddresses {
+    /// 按存储类型取对应的起始地址
+    pub fn for_memory(&self, memory_type: &str) -> u32 {
+        if is_sd_memory(memory_type) {
+            self.sd
+        } else if is_nand_memory(memory_type) {
+            self.nand
+        } else {
+            self.nor
+        }
+    }
+}
+
 /// 通用的速度设置操作实现
 pub struct SpeedOps;
 
 impl SpeedOps {
+    /// 握手校验时 burn_verify 覆盖的字节数
+    const PROBE_LEN: u32 = 0x100;
+
+    /// 发送回车并等待 stub 的提示符
+    ///
+    /// SF32LB55/58 通过 DFU 下载 stub 后不会等待 shell 启动，切换速率前需要先同步一次，
+    /// 避免 burn_speed 在 stub 就绪前发出而丢失。
+    pub fn sync_prompt<T>(tool: &mut T) -> Result<()>
+    where
+        T: SifliToolTrait,
+    {
+        let mut io = for_tool(tool);
+        RamOps::wait_for_shell_prompt(&mut io, b"msh >", 200, 5)
+    }
+
     /// 设置串口速度的通用实现
     pub fn set_speed<T>(tool: &mut T, speed: u32) -> Result<()>
     where
@@ -28,4 +70,117 @@ impl SpeedOps {
 
         Ok(())
     }
+
+    /// 依次尝试 `candidates` 中的波特率，返回第一个通过握手校验的速率
+    ///
+    /// 每个速率切换后除了等待提示符，还会对当前存储的起始地址（见
+    /// [`ProbeAddresses`]）发送一次 burn_verify，只要 stub 给出 OK 或 Fail
+    /// 即认为链路可用。校验失败时先尝试切回上一个可用速率，失败则重新下载 stub。
+    /// 全部失败时保持在最后一个可用速率。
+    pub fn negotiate_speed<T>(
+        tool: &mut T,
+        candidates: &[u32],
+        probe: ProbeAddresses,
+    ) -> Result<u32>
+    where
+        T: SifliToolTrait + RamCommand + DownloadStub,
+    {
+        let probe_address = probe.for_memory(&tool.base().memory_type);
+        Self::sync_prompt(tool)?;
+        let mut current = tool.port().baud_rate()?;
+
+        for &baud in candidates {
+            tool.check_cancelled()?;
+            let result = if baud == current {
+                Self::probe(tool, probe_address)
+            } else {
+                Self::set_speed(tool, baud).and_then(|_| Self::probe(tool, probe_address))
+            };
+
+            match result {
+                Ok(()) => {
+                    tracing::info!("Baud rate {} passed validation", baud);
+                    return Ok(baud);
+                }
+                Err(Error::Cancelled) => return Err(Error::Cancelled),
+                Err(e) => {
+                    tracing::warn!("Baud rate {} failed validation: {}", baud, e);
+                    if baud != current {
+                        current = Self::fall_back(tool, baud, current)?;
+                    }
+                }
+            }
+        }
+
+        Ok(current)
+    }
+
+    /// 通过一次 burn_verify 往返确认当前速率下命令和应答都能正确传输
+    fn probe<T>(tool: &mut T, probe_address: u32) -> Result<()>
+    where
+        T: SifliToolTrait + RamCommand,
+    {
+        match tool.command(Command::Verify {
+            address: probe_address,
+            len: Self::PROBE_LEN,
+            crc: 0,
+        })? {
+            Response::Ok | Response::Fail => Ok(()),
+            Response::RxWait => Err(Error::protocol("unexpected RX_WAIT during baud probe")),
+        }
+    }
+
+    /// 从校验失败的速率恢复，返回恢复后的可用速率
+    fn fall_back<T>(tool: &mut T, failed: u32, good: u32) -> Result<u32>
+    where
+        T: SifliToolTrait + RamCommand + DownloadStub,
+    {
+        // stub 可能已经切换到新速率，只是链路质量不足，先尝试让它切回来
+        let command = tool.format_command(&Command::SetBaud {
+            baud: good,
+            delay: 10,
+        });
+        let switched_back = {
+            let mut io = for_tool(tool);
+            io.set_baud_rate(failed)?;
+            io.write_all(command.as_bytes())?;
+            io.flush()?;
+            io.sleep(Duration::from_millis(50))?;
+            io.set_baud_rate(good)?;
+            io.clear(serialport::ClearBuffer::All)?;
+            RamOps::wait_for_shell_prompt(&mut io, b"msh >", 100, 3)
+        };
+        match switched_back {
+            Ok(()) => return Ok(good),
+            Err(Error::Cancelled) => return Err(Error::Cancelled),
+            Err(_) => {}
+        }
+
+        // 仍然没有响应，复位后重新下载 stub，stub 启动时使用默认速率
+        tracing::warn!(
+            "Lost the stub after switching to {} baud, reloading",
+            failed
+        );
+        let reset = tool.base().before.requires_reset();
+        {
+            let mut io = for_tool(tool);
+            io.set_baud_rate(DEFAULT_BAUD)?;
+            // SF32LB55/58 的 stub 下载不会自行复位芯片，先用 RTS 复位回到 ROM
+            if reset {
+                io.write_request_to_send(true)?;
+                io.sleep(Duration::from_millis(100))?;
+                io.write_request_to_send(false)?;
+                io.sleep(Duration::from_millis(100))?;
+            }
+        }
+        tool.download_stub()
+            .and_then(|_| Self::sync_prompt(tool))
+            .map_err(|e| {
+                Error::protocol(format!(
+                    "failed to recover after switching to {} baud: {}",
+                    failed, e
+                ))
+            })?;
+        Ok(DEFAULT_BAUD)
+    }
 }
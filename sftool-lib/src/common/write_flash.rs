@@ -1,7 +1,7 @@
 use crate::common::ram_command::{Command, RamCommand, Response};
 use crate::common::serial_io::for_tool;
 use crate::progress::{ProgressOperation, ProgressStatus};
-use crate::{Error, Result, SifliToolTrait, WriteFlashFile};
+use crate::{Error, Result, SifliToolTrait, VerifyResult, WriteFlashFile};
 use std::io::{BufReader, Read};
 
 /// 通用的Flash写入操作实现
@@ -44,17 +44,38 @@ impl FlashWriter {
 
         let response = tool.command(Command::Verify { address, len, crc })?;
         if response != Response::Ok {
-            return Err(Error::protocol(format!(
-                "verify failed for 0x{:08X}..0x{:08X}",
-                address,
-                address + len.saturating_sub(1)
-            )));
+            let error = Error::VerifyFailed { address, len };
+            spinner.finish(ProgressStatus::Failed(error.to_string()));
+            return Err(error);
         }
 
         spinner.finish(ProgressStatus::Success);
         Ok(())
     }
 
+    /// 逐个文件校验 Flash 内容，不写入任何数据；CRC 不匹配记录在结果中而不是作为错误返回
+    pub fn verify_files<T>(tool: &mut T, files: &[WriteFlashFile]) -> Result<Vec<VerifyResult>>
+    where
+        T: SifliToolTrait + RamCommand,
+    {
+        let mut results = Vec::with_capacity(files.len());
+        for file in files.iter() {
+            let len = file.file.metadata()?.len() as u32;
+            let matched = match Self::verify(tool, file.address, len, file.crc32) {
+                Ok(()) => true,
+                Err(Error::VerifyFailed { .. }) => false,
+                Err(e) => return Err(e),
+            };
+            results.push(VerifyResult {
+                address: file.address,
+                len,
+                crc32: file.crc32,
+                matched,
+            });
+        }
+        Ok(results)
+    }
+
     /// 写入单个文件到Flash（非全擦除模式）
     pub fn write_file_incremental<T>(
         tool: &mut T,
@@ -0,0 +1,128 @@
+//! DFU 镜像解析
+//!
+//! SF32LB55/58 的 stub 以 DFU 镜像的形式通过 `dfu_recv` 下载，镜像布局：
+//!
+//! | 偏移 | 长度 | 内容 |
+//! |------|------|------|
+//! | 0    | 328  | 镜像头（32 字节 + 296 字节签名头，ROM 校验） |
+//! | 328  | 548*N | 数据块：32 字节校验值、块内偏移(u32 LE)、最多 512 字节数据 |
+//!
+//! 块内偏移从 0 开始按 512 递增，只有最后一块的数据可以不足 512 字节。
+//! 签名头经过混淆，只有偏移 32 处的数据总长度（u32 LE，与 [`DFU_LENGTH_KEY`] 异或）
+//! 是已知字段，块数由它按 512 字节向上取整得出。
+//! 下载前先用 [`DfuImage::parse`] 检查块结构，避免损坏的镜像在传输中途才被 ROM 拒绝。
+//! 混淆值只在随附的 stub 上验证过，其他签名工具生成的镜像可能不同，
+//! 因此声明长度与实际不符只记录警告，由 [`DfuImage::declared_size_matches`] 查询。
+
+use crate::{Error, Result};
+
+/// 镜像头长度
+pub const DFU_HEADER_SIZE: usize = 32 + 296;
+/// 每块的数据长度
+pub const DFU_BLOCK_SIZE: usize = 512;
+/// 每块数据前的校验值和偏移
+pub const DFU_CHUNK_OVERHEAD: usize = 32 + 4;
+/// 完整块的长度
+pub const DFU_CHUNK_SIZE: usize = DFU_CHUNK_OVERHEAD + DFU_BLOCK_SIZE;
+/// 镜像头中数据总长度字段的偏移
+pub const DFU_LENGTH_OFFSET: usize = 32;
+/// 数据总长度字段的混淆值，所有随附的 55X/58X stub 相同，其他镜像不保证
+pub const DFU_LENGTH_KEY: u32 = 0xDE92_6C8A;
+
+/// 校验过的 DFU 镜像
+#[derive(Debug, Clone, Copy)]
+pub struct DfuImage<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DfuImage<'a> {
+    /// 解析并校验镜像头长度、每块偏移和长度；头中声明的数据长度不符时只记录警告
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        if data.len() <= DFU_HEADER_SIZE {
+            return Err(Error::invalid_input(format!(
+                "DFU image is {} bytes, expected a {}-byte header followed by data chunks",
+                data.len(),
+                DFU_HEADER_SIZE
+            )));
+        }
+
+        let image = Self { data };
+        for (index, chunk) in image.chunks().enumerate() {
+            if chunk.len() <= DFU_CHUNK_OVERHEAD {
+                return Err(Error::invalid_input(format!(
+                    "DFU image: chunk {} is {} bytes, shorter than its {}-byte chunk header (image truncated?)",
+                    index,
+                    chunk.len(),
+                    DFU_CHUNK_OVERHEAD
+                )));
+            }
+            let offset = chunk_offset(chunk);
+            let expected = index * DFU_BLOCK_SIZE;
+            if offset as usize != expected {
+                return Err(Error::invalid_input(format!(
+                    "DFU image: chunk {index} has offset 0x{offset:X}, expected 0x{expected:X} \
+                     (not a DFU image or corrupted)"
+                )));
+            }
+        }
+
+        if !image.declared_size_matches() {
+            tracing::warn!(
+                "DFU image header declares {} bytes, but the image carries {} bytes",
+                image.declared_payload_size(),
+                image.payload_size()
+            );
+        }
+        Ok(image)
+    }
+
+    /// 原始镜像数据
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// 镜像头
+    pub fn header(&self) -> &'a [u8] {
+        &self.data[..DFU_HEADER_SIZE]
+    }
+
+    /// 镜像头中声明的数据总长度
+    pub fn declared_payload_size(&self) -> usize {
+        let bytes = &self.data[DFU_LENGTH_OFFSET..DFU_LENGTH_OFFSET + 4];
+        (u32::from_le_bytes(bytes.try_into().unwrap()) ^ DFU_LENGTH_KEY) as usize
+    }
+
+    /// 头中声明的数据长度与数据块实际携带的长度是否一致
+    pub fn declared_size_matches(&self) -> bool {
+        self.declared_payload_size() == self.payload_size()
+    }
+
+    /// 按下载顺序返回每个数据块（含块头）
+    pub fn chunks(&self) -> std::slice::Chunks<'a, u8> {
+        self.data[DFU_HEADER_SIZE..].chunks(DFU_CHUNK_SIZE)
+    }
+
+    /// 数据块数量
+    pub fn chunk_count(&self) -> usize {
+        (self.data.len() - DFU_HEADER_SIZE).div_ceil(DFU_CHUNK_SIZE)
+    }
+
+    /// 所有数据块中有效数据的总长度
+    pub fn payload_size(&self) -> usize {
+        self.chunks()
+            .map(|chunk| chunk.len() - DFU_CHUNK_OVERHEAD)
+            .sum()
+    }
+
+    /// 最后一块的有效数据长度
+    pub fn last_block_size(&self) -> usize {
+        self.chunks()
+            .last()
+            .map_or(0, |chunk| chunk.len() - DFU_CHUNK_OVERHEAD)
+    }
+}
+
+fn chunk_offset(chunk: &[u8]) -> u32 {
+    let bytes = &chunk[DFU_CHUNK_OVERHEAD - 4..DFU_CHUNK_OVERHEAD];
+    u32::from_le_bytes(bytes.try_into().unwrap())
+}
@@ -0,0 +1,39 @@
+use crate::dfu_image::DfuImage;
+use crate::progress::{ProgressHandle, ProgressOperation, ProgressStatus};
+use crate::{DfuWriteParams, Error, Result, SifliToolTrait};
+
+/// ROM 的 DFU 流程（`dfu_recv`）
+pub trait DfuDownload: SifliToolTrait {
+    /// 把镜像下载到 `flash_id`，有进度条时按已发送的字节推进
+    fn download_image(
+        &mut self,
+        image: &DfuImage,
+        flash_id: u8,
+        progress: Option<&ProgressHandle>,
+    ) -> Result<()>;
+}
+
+/// 通过 ROM 的 DFU 流程直接下载已签名的镜像，不经过 RAM stub
+///
+/// 用于开启安全启动后无法运行 RAM stub 的设备；目前只有 SF32LB55/58 支持。
+pub fn dfu_write<T: SifliToolTrait + ?Sized>(tool: &mut T, params: &DfuWriteParams) -> Result<()> {
+    let tool = tool.dfu_download().ok_or_else(|| {
+        Error::UnsupportedChip("dfu_write is only supported on SF32LB55 and SF32LB58".to_string())
+    })?;
+
+    // 先校验全部镜像，避免写到一半才发现后面的镜像损坏
+    let images = params
+        .files
+        .iter()
+        .map(|file| Ok((file.flash_id, DfuImage::parse(&file.data)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    for (flash_id, image) in images {
+        let size = image.data().len() as u64;
+        let progress = tool.progress();
+        let bar = progress.create_bar(size, ProgressOperation::DfuWrite { flash_id, size });
+        tool.download_image(&image, flash_id, Some(&bar))?;
+        bar.finish(ProgressStatus::Success);
+    }
+    Ok(())
+}
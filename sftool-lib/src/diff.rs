@@ -0,0 +1,87 @@
+//! Flash 内容与本地镜像的差异比较
+//!
+//! 读回的数据与本地文件逐字节比较，结果按连续的差异区间和包含差异的扇区汇总，
+//! 便于定位 `verify` 失败的具体位置。
+
+/// 汇总差异扇区时使用的扇区大小
+pub const DIFF_SECTOR_SIZE: u32 = 4 * 1024;
+
+/// 一段连续的差异字节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRange {
+    pub address: u32,
+    pub len: u32,
+}
+
+/// 单个文件段的比较结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffReport {
+    pub address: u32,
+    pub len: u32,
+    /// 不相同的字节数
+    pub differing_bytes: u64,
+    /// 连续的差异区间，按地址排序
+    pub ranges: Vec<DiffRange>,
+    /// 含有差异的扇区起始地址，按 [`DIFF_SECTOR_SIZE`] 对齐
+    pub sectors: Vec<u32>,
+}
+
+impl DiffReport {
+    pub fn is_identical(&self) -> bool {
+        self.differing_bytes == 0
+    }
+
+    /// 把相邻的差异扇区合并成区间，整片擦写出错时不会列出成千上万个扇区
+    pub fn sector_ranges(&self) -> Vec<DiffRange> {
+        let mut ranges: Vec<DiffRange> = Vec::new();
+        for &sector in &self.sectors {
+            match ranges.last_mut() {
+                Some(last) if last.address.wrapping_add(last.len) == sector => {
+                    last.len += DIFF_SECTOR_SIZE
+                }
+                _ => ranges.push(DiffRange {
+                    address: sector,
+                    len: DIFF_SECTOR_SIZE,
+                }),
+            }
+        }
+        ranges
+    }
+}
+
+/// 比较位于 `address` 的期望数据与读回数据；长度不同时多出的部分都算作差异
+pub fn compare(address: u32, expected: &[u8], actual: &[u8]) -> DiffReport {
+    let len = expected.len().max(actual.len());
+    let mut ranges: Vec<DiffRange> = Vec::new();
+    let mut sectors: Vec<u32> = Vec::new();
+    let mut differing_bytes = 0u64;
+
+    for offset in 0..len {
+        if expected.get(offset) == actual.get(offset) {
+            continue;
+        }
+        differing_bytes += 1;
+
+        let byte_address = address.wrapping_add(offset as u32);
+        match ranges.last_mut() {
+            Some(last) if last.address.wrapping_add(last.len) == byte_address => last.len += 1,
+            _ => ranges.push(DiffRange {
+                address: byte_address,
+                len: 1,
+            }),
+        }
+
+        let sector = byte_address - byte_address % DIFF_SECTOR_SIZE;
+        if sectors.last() != Some(&sector) {
+            sectors.push(sector);
+        }
+    }
+
+    DiffReport {
+        address,
+        len: len as u32,
+        differing_bytes,
+        ranges,
+        sectors,
+    }
+}
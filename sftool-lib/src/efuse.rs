@@ -0,0 +1,207 @@
+//! eFuse 读取
+//!
+//! 通过 UART 调试通道访问 eFuse 控制器（EFUSEC），读取流程与 SDK 中
+//! `HAL_EFUSE_Read` 一致：
+//!
+//! 1. 临时把 LDO 电压调高（读完后恢复原值，SF32LB57 不需要）；
+//! 2. 对每个 bank 写 `CR = bank << 2 | EN`，等待 `SR.DONE` 后写 1 清除；
+//! 3. 从 `BANKn_DATA0..7`（`0x30 + n * 0x20`）读出 32 字节。
+//!
+//! 读取期间内核处于暂停状态，结束后恢复运行，不会复位芯片。
+//!
+//! 只解码随附 stub 中 `HAL_EFUSE_Read` 的调用方实际使用的字段：
+//!
+//! - 所有芯片：bank 0 前 16 字节的芯片唯一 ID；
+//! - SF32LB52：bank 1 中的 ADC 校准值（SDK 中 `FACTORY_CFG_ID_ADC` 的来源）；
+//! - SF32LB57：bank 0 第 228 位起 8 位的封装 ID（pid），stub 据此选择 PSRAM 配置。
+//!
+//! 安全配置和其余校准值在 stub 中没有读取，位置随芯片和 ROM 版本变化无法核实，
+//! 按 bank 原样输出，由调用方对照芯片手册解析。
+
+use crate::common::sifli_debug::{SifliDebug, SifliUartCommand};
+use crate::{Error, Result, SifliToolTrait};
+
+/// bank 数量
+pub const EFUSE_BANK_COUNT: usize = 4;
+/// 每个 bank 的字节数
+pub const EFUSE_BANK_SIZE: usize = 32;
+/// 芯片唯一 ID 的长度（bank 0 起始处）
+pub const EFUSE_UID_SIZE: usize = 16;
+
+const EFUSEC_CR: u32 = 0x00;
+const EFUSEC_SR: u32 = 0x08;
+const EFUSEC_BANK0_DATA: u32 = 0x30;
+const EFUSEC_CR_EN: u32 = 1 << 0;
+const EFUSEC_SR_DONE: u32 = 1 << 0;
+/// 等待 DONE 的最多轮询次数
+const DONE_POLL_LIMIT: usize = 100;
+
+/// 芯片相关的 eFuse 控制器参数和字段位置，取自各芯片 RAM stub 中的 `HAL_EFUSE_Read` 及其调用方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EfuseLayout {
+    efusec_base: u32,
+    /// 读取期间需要调高的 LDO 电压寄存器及 4 位电压字段的位置
+    ldo: Option<(u32, u32)>,
+    /// 封装 ID 在 eFuse 中的起始位（8 位）
+    pid_bit: Option<usize>,
+    /// bank 1 是否保存 ADC 校准值
+    adc_calibration: bool,
+}
+
+impl EfuseLayout {
+    pub const SF32LB52: Self = Self {
+        efusec_base: 0x5000_C000,
+        ldo: Some((0x500C_A094, 0)),
+        pid_bit: None,
+        adc_calibration: true,
+    };
+    pub const SF32LB56: Self = Self {
+        efusec_base: 0x4000_C000,
+        ldo: Some((0x5004_A02C, 2)),
+        pid_bit: None,
+        adc_calibration: false,
+    };
+    pub const SF32LB57: Self = Self {
+        efusec_base: 0x5000_C000,
+        ldo: None,
+        pid_bit: Some(228),
+        adc_calibration: false,
+    };
+}
+
+/// SF32LB52 bank 1 中的 ADC 校准值，字段与 SDK 的 `FACTORY_CFG_ADC_T` 对应
+///
+/// 两个参考点的 ADC 读数和电压，以及电池电压通道的读数和电压。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdcCalibration {
+    pub vol10: u16,
+    pub low_mv: u16,
+    pub vol25: u16,
+    pub high_mv: u16,
+    pub vbat_reg: u16,
+    pub vbat_mv: u16,
+}
+
+/// eFuse 读取结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EfuseData {
+    pub banks: [[u8; EFUSE_BANK_SIZE]; EFUSE_BANK_COUNT],
+    pub layout: EfuseLayout,
+}
+
+impl EfuseData {
+    /// 芯片唯一 ID
+    pub fn uid(&self) -> [u8; EFUSE_UID_SIZE] {
+        let mut uid = [0; EFUSE_UID_SIZE];
+        uid.copy_from_slice(&self.banks[0][..EFUSE_UID_SIZE]);
+        uid
+    }
+
+    /// 封装 ID，只有 SF32LB57 有
+    pub fn pid(&self) -> Option<u8> {
+        let bit = self.layout.pid_bit?;
+        Some(self.bits(bit, 8) as u8)
+    }
+
+    /// ADC 校准值；只有 SF32LB52 有，bank 1 首字节为 0（未烧写）时与 stub 一样视为没有
+    pub fn adc_calibration(&self) -> Option<AdcCalibration> {
+        const BANK1: usize = EFUSE_BANK_SIZE * 8;
+        if !self.layout.adc_calibration || self.banks[1][0] == 0 {
+            return None;
+        }
+        let field = |offset: usize, len: usize| self.bits(BANK1 + offset, len) as u16;
+        Some(AdcCalibration {
+            vol10: field(32, 12),
+            low_mv: field(44, 5) * 100,
+            vol25: field(49, 12),
+            high_mv: field(61, 5) * 100,
+            vbat_reg: field(66, 12),
+            vbat_mv: field(78, 6) * 100,
+        })
+    }
+
+    /// 从第 `bit` 位起读取 `len` 位，低位在前
+    fn bits(&self, bit: usize, len: usize) -> u32 {
+        (0..len).fold(0, |value, index| {
+            let position = bit + index;
+            let byte = self.banks[position / (EFUSE_BANK_SIZE * 8)][position / 8 % EFUSE_BANK_SIZE];
+            value | (((byte >> (position % 8)) & 1) as u32) << index
+        })
+    }
+}
+
+/// 连接调试通道读取全部 eFuse bank，结束后退出调试模式
+pub fn read_efuse<T: SifliToolTrait + ?Sized>(tool: &mut T) -> Result<EfuseData> {
+    let unsupported = || {
+        Error::UnsupportedChip(
+            "efuse read is only supported on SF32LB52, SF32LB56 and SF32LB57".to_string(),
+        )
+    };
+    let chip = tool.debug_chip().ok_or_else(unsupported)?;
+    let layout = chip.efuse_layout().ok_or_else(unsupported)?;
+
+    chip.debug_connect()?;
+    chip.debug_halt()?;
+    let data = read_banks(chip, layout);
+    // 恢复内核运行并退出调试模式
+    chip.debug_run()?;
+    chip.debug_command(SifliUartCommand::Exit)?;
+    data
+}
+
+/// 在已进入调试模式并暂停内核的芯片上读取全部 bank
+fn read_banks<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    layout: EfuseLayout,
+) -> Result<EfuseData> {
+    let Some((ldo_reg, ldo_shift)) = layout.ldo else {
+        return read_banks_raw(tool, layout);
+    };
+
+    let ldo_mask = 0xF << ldo_shift;
+    let ldo = tool.debug_read_word32(ldo_reg)?;
+    let level = (ldo & ldo_mask) >> ldo_shift;
+    let raised = level.clamp(11, 12) + 3;
+    tool.debug_write_word32(ldo_reg, (ldo & !ldo_mask) | (raised << ldo_shift))?;
+
+    let result = read_banks_raw(tool, layout);
+    // 无论读取是否成功都恢复电压
+    let restored = tool.debug_write_word32(ldo_reg, ldo);
+    let data = result?;
+    restored?;
+    Ok(data)
+}
+
+fn read_banks_raw<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    layout: EfuseLayout,
+) -> Result<EfuseData> {
+    let base = layout.efusec_base;
+    let mut banks = [[0u8; EFUSE_BANK_SIZE]; EFUSE_BANK_COUNT];
+    for (bank, data) in banks.iter_mut().enumerate() {
+        tool.check_cancelled()?;
+        let select = (bank as u32) << 2;
+        tool.debug_write_word32(base + EFUSEC_CR, select)?;
+        tool.debug_write_word32(base + EFUSEC_CR, select | EFUSEC_CR_EN)?;
+
+        let mut done = false;
+        for _ in 0..DONE_POLL_LIMIT {
+            let sr = tool.debug_read_word32(base + EFUSEC_SR)?;
+            if sr & EFUSEC_SR_DONE != 0 {
+                tool.debug_write_word32(base + EFUSEC_SR, sr | EFUSEC_SR_DONE)?;
+                done = true;
+                break;
+            }
+        }
+        if !done {
+            return Err(Error::timeout(format!("reading eFuse bank {bank}")));
+        }
+
+        let bank_base = base + EFUSEC_BANK0_DATA + bank as u32 * EFUSE_BANK_SIZE as u32;
+        for (index, word) in data.chunks_exact_mut(4).enumerate() {
+            let value = tool.debug_read_word32(bank_base + 4 * index as u32)?;
+            word.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+    Ok(EfuseData { banks, layout })
+}
@@ -3,4 +3,6 @@ use crate::{EraseFlashParams, EraseRegionParams, Result};
 pub trait EraseFlashTrait {
     fn erase_flash(&mut self, params: &EraseFlashParams) -> Result<()>;
     fn erase_region(&mut self, params: &EraseRegionParams) -> Result<()>;
+    /// 确认各区域已全部擦除（内容全为 0xFF）
+    fn verify_blank(&mut self, params: &EraseRegionParams) -> Result<()>;
 }
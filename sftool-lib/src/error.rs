@@ -47,6 +47,12 @@ pub enum Error {
     #[error("CRC mismatch: expected {expected:#010X}, got {actual:#010X}")]
     CrcMismatch { expected: u32, actual: u32 },
 
+    #[error("verify failed for {address:#010X} ({len:#X} bytes)")]
+    VerifyFailed { address: u32, len: u32 },
+
+    #[error("region at {address:#010X} ({len:#X} bytes) is not blank")]
+    NotBlank { address: u32, len: u32 },
+
     #[error("embedded asset `{0}` not found")]
     MissingEmbeddedAsset(&'static str),
 }
@@ -0,0 +1,257 @@
+//! 产线校准（factory calibration）
+//!
+//! 校准镜像（`factory_cali*.bin`）是链接在 [`FACTORY_CALI_LOAD_ADDRESS`] 的裸二进制，
+//! 通过 [`run_ram::load_and_run`] 按链接地址写入 RAM 运行，启动后提供 msh 命令行。
+//! 校准命令同步执行，结束后才回到提示符，期间的输出例如：
+//!
+//! ```text
+//! crystal_cali start PB08 5 20 !
+//! crystal_cali end, value 0x1f
+//! CRYSTAL_CALI_PASS 0x1f
+//! ```
+//!
+//! ```text
+//! battery_cali start
+//!  first measure: ref_value 4000 meas_value 3987 cali_value 1003
+//!  battery_cali judge: volt 3998
+//! ```
+//!
+//! 晶振校准超时打印 `crystal_cali error, timeout %d s`，失败时打印 `CRYSTAL_CALI_FAIL`；
+//! 电池校准按判定电压是否落在参考电压 ± 判定容差内决定是否通过。
+//! 晶振校准结果由镜像自己写入 OTP 的 `FACTORY_CFG_ID_CRYSTAL`，无需另外保存。
+//!
+//! 加载依赖 UART 调试通道，SF32LB55/58 只能通过 DFU 下载签名镜像，因此这两个系列不支持校准。
+
+use crate::common::serial_io::for_tool;
+use crate::run_ram::{self, RAM_RANGE, RunRamParams};
+use crate::{ChipType, Error, Result, SifliToolTrait};
+use serialport::ClearBuffer;
+use std::time::Duration;
+
+/// 校准镜像的链接地址
+///
+/// 三个镜像的 scatter-load 表都指向镜像偏移 0x7FC 处的解压函数 0x2005_07FD，向量表位于镜像开头。
+pub const FACTORY_CALI_LOAD_ADDRESS: u32 = 0x2005_0000;
+
+/// 返回芯片对应的内嵌校准镜像文件名
+pub fn factory_cali_image_name(chip: &ChipType) -> Result<&'static str> {
+    match chip {
+        ChipType::SF32LB52 => Ok("factory_cali.bin"),
+        ChipType::SF32LB56 => Ok("factory_cali_56X.bin"),
+        ChipType::SF32LB58 => Err(Error::UnsupportedChip(
+            "SF32LB58 has no UART debug channel to load factory_cali_58X.bin".to_string(),
+        )),
+        ChipType::SF32LB55 | ChipType::SF32LB57 => Err(Error::UnsupportedChip(format!(
+            "no factory calibration image for {chip:?}"
+        ))),
+    }
+}
+
+/// 检查镜像能否在 `load_address` 处运行
+///
+/// 镜像开头须是向量表：SP 指向 SRAM，复位向量为 Thumb 地址且落在镜像自身
+/// `[load_address, load_address + len)` 范围内，否则说明加载地址与链接地址不符。
+pub fn check_image_loadable(image: &[u8], load_address: u32) -> Result<()> {
+    let params = RunRamParams::from_bin(image.to_vec(), load_address);
+    params.check_ram()?;
+    let (stack, reset) = params.vector_table()?;
+    if !RAM_RANGE.contains(&stack) || reset & 1 == 0 {
+        return Err(Error::invalid_input(
+            "image does not start with a vector table in SRAM",
+        ));
+    }
+    let image_range = load_address as u64..load_address as u64 + image.len() as u64;
+    if !image_range.contains(&((reset & !1) as u64)) {
+        return Err(Error::invalid_input(format!(
+            "reset vector 0x{reset:08X} lies outside the image loaded at 0x{load_address:08X}..0x{:08X}",
+            image_range.end
+        )));
+    }
+    Ok(())
+}
+
+/// 读取芯片对应的内嵌校准镜像
+pub fn load_factory_cali_image(chip: &ChipType) -> Result<Vec<u8>> {
+    let name = factory_cali_image_name(chip)?;
+    let image = crate::load_embedded_stub(name)?;
+    check_image_loadable(&image, FACTORY_CALI_LOAD_ADDRESS)
+        .map_err(|e| Error::invalid_input(format!("{name} cannot be loaded on {chip:?}: {e}")))?;
+    Ok(image)
+}
+
+/// 把校准镜像写入 RAM 并运行，等到 msh 提示符后返回启动日志
+pub fn start_factory_cali<T: SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    chip: &ChipType,
+) -> Result<Vec<u8>> {
+    let image = load_factory_cali_image(chip)?;
+    run_ram::load_and_run(
+        tool,
+        &RunRamParams::from_bin(image, FACTORY_CALI_LOAD_ADDRESS),
+    )?;
+    let mut io = for_tool(tool);
+    io.wait_for_prompt_capture(b"msh >", Duration::from_millis(200), 5)
+}
+
+/// 一个校准步骤及其参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaliStep {
+    /// 晶振校准：参考时钟输入引脚（PB 编号）、目标精度（ppm）、超时（秒）
+    Crystal { pin: u8, ppm: u32, timeout_s: u32 },
+    /// 电池电压校准：参考电压、允许偏差、判定容差（mV），分压电阻（kΩ）
+    Battery {
+        reference_mv: u32,
+        range_mv: u32,
+        tolerance_mv: u32,
+        upper_kohm: u32,
+        lower_kohm: u32,
+    },
+}
+
+impl CaliStep {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CaliStep::Crystal { .. } => "crystal",
+            CaliStep::Battery { .. } => "battery",
+        }
+    }
+
+    /// 发送给校准镜像的 msh 命令
+    pub fn shell_command(&self) -> String {
+        match self {
+            CaliStep::Crystal {
+                pin,
+                ppm,
+                timeout_s,
+            } => format!("crystal_cali {pin} {ppm} {timeout_s}"),
+            CaliStep::Battery {
+                reference_mv,
+                range_mv,
+                tolerance_mv,
+                upper_kohm,
+                lower_kohm,
+            } => format!(
+                "battery_cali {reference_mv} {range_mv} {tolerance_mv} {upper_kohm} {lower_kohm}"
+            ),
+        }
+    }
+
+    /// 等待命令结束的时间；晶振校准额外留出 10 秒余量
+    pub fn timeout(&self) -> Duration {
+        match self {
+            CaliStep::Crystal { timeout_s, .. } => Duration::from_secs(*timeout_s as u64 + 10),
+            CaliStep::Battery { .. } => Duration::from_secs(10),
+        }
+    }
+}
+
+/// 单个校准步骤的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaliOutcome {
+    pub step: CaliStep,
+    pub passed: bool,
+    /// 校准值：晶振为 cbank 配置，电池为 `cali_value` 校准系数
+    pub value: Option<i32>,
+    /// 电池校准后判定时测得的电压（mV）
+    pub measured_mv: Option<i32>,
+}
+
+fn parse_number(text: &str) -> Option<i32> {
+    let text = text.trim_end_matches([',', '!']);
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok().map(|v| v as i32),
+        None => text.parse::<i32>().ok(),
+    }
+}
+
+/// 取 `key` 之后的第一个数字
+fn number_after(line: &str, key: &str) -> Option<i32> {
+    let start = line.find(key)? + key.len();
+    parse_number(line[start..].split_whitespace().next()?)
+}
+
+/// 解析一个校准命令从回显到提示符之间的输出；没有结果时返回 `None`
+///
+/// 参数错误（`para error, eg: ...`）返回错误。
+pub fn parse_step_output(step: &CaliStep, output: &str) -> Result<Option<CaliOutcome>> {
+    let mut passed: Option<bool> = None;
+    let mut value = None;
+    let mut measured_mv = None;
+
+    for line in output.lines().map(str::trim) {
+        if line.starts_with("para error") {
+            return Err(Error::invalid_input(format!(
+                "{} calibration rejected its parameters: {line}",
+                step.name()
+            )));
+        }
+        match step {
+            CaliStep::Crystal { .. } => {
+                if line.starts_with("crystal_cali end") {
+                    value = number_after(line, "value").or(value);
+                    passed.get_or_insert(true);
+                } else if line.starts_with("crystal_cali error") {
+                    passed = Some(false);
+                } else if let Some(rest) = line.strip_prefix("CRYSTAL_CALI_PASS") {
+                    value = rest
+                        .split_whitespace()
+                        .next()
+                        .and_then(parse_number)
+                        .or(value);
+                    passed.get_or_insert(true);
+                } else if let Some(rest) = line.strip_prefix("CRYSTAL_CALI_FAIL") {
+                    value = rest
+                        .split_whitespace()
+                        .next()
+                        .and_then(parse_number)
+                        .or(value);
+                    passed = Some(false);
+                }
+            }
+            CaliStep::Battery {
+                reference_mv,
+                tolerance_mv,
+                ..
+            } => {
+                if line.starts_with("first measure:") {
+                    value = number_after(line, "cali_value");
+                } else if line.starts_with("battery_cali judge:") {
+                    measured_mv = number_after(line, "volt");
+                    passed = measured_mv.map(|measured| {
+                        (measured as i64 - *reference_mv as i64).abs() <= *tolerance_mv as i64
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(passed.map(|passed| CaliOutcome {
+        step: step.clone(),
+        passed,
+        value,
+        measured_mv,
+    }))
+}
+
+/// 在已运行的校准镜像上执行一个步骤，返回结果和到提示符为止收到的输出
+pub fn run_step<T: SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    step: &CaliStep,
+) -> Result<(CaliOutcome, String)> {
+    let timeout = step.timeout();
+    let mut io = for_tool(tool);
+    // 丢弃之前残留的提示符，避免把它当成本命令的结束
+    io.clear(ClearBuffer::Input)?;
+    io.write_all(format!("{}\r\n", step.shell_command()).as_bytes())?;
+    io.flush()?;
+
+    let output = io.wait_for_pattern(b"msh >", timeout, step.name())?;
+    let log = String::from_utf8_lossy(&output).into_owned();
+    match parse_step_output(step, &log)? {
+        Some(outcome) => Ok((outcome, log)),
+        None => Err(Error::protocol(format!(
+            "{} calibration returned to the prompt without a result",
+            step.name()
+        ))),
+    }
+}
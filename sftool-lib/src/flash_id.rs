@@ -0,0 +1,123 @@
+//! 从 stub 启动日志中解析 flash JEDEC ID
+//!
+//! RAM stub 初始化 flash 时会打印探测结果，例如：
+//!
+//! ```text
+//! get_user_flash_cfg: isnand_0 type_1 id_c84018
+//!  addr:0x12000000 base:0x12000000 size:0x1000000 sector:0x1000 page:0x100 id:0xc84018
+//! ```
+//!
+//! 第一行给出介质类型、驱动类型和三字节 JEDEC ID，第二行给出容量。
+//! 下载 stub 时等待提示符期间收到的输出保存在 [`crate::SifliToolBase::stub_log`] 中。
+
+use crate::flash_parts::{self, FlashPart};
+use crate::stub_config::{FlashConfig, FlashMedia};
+
+/// 探测到的 flash 信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashId {
+    pub media: FlashMedia,
+    /// stub 驱动表中的类型号，对应 [`FlashConfig::driver_index`]
+    pub driver_index: u8,
+    pub manufacturer_id: u8,
+    pub device_type: u8,
+    pub density_id: u8,
+    /// 容量（字节），stub 没有打印且无法从 density 推算时为 `None`
+    pub capacity_bytes: Option<u32>,
+}
+
+impl FlashId {
+    /// 在内置型号表中查找对应的型号
+    pub fn part(&self) -> Option<&'static FlashPart> {
+        flash_parts::find_by_jedec(
+            self.media,
+            self.manufacturer_id,
+            self.device_type,
+            self.density_id,
+        )
+    }
+
+    /// 按 JEDEC 惯例由 density 推算 NOR 容量（`1 << density`）
+    pub fn jedec_capacity(&self) -> Option<u32> {
+        match (self.media, self.density_id) {
+            (FlashMedia::Nor, density @ 0x10..=0x1F) => Some(1u32 << density),
+            _ => None,
+        }
+    }
+
+    /// 转换为 stub 配置中的 flash 条目；容量未知时返回 `None`
+    pub fn to_flash_config(&self) -> Option<FlashConfig> {
+        Some(FlashConfig {
+            media: self.media,
+            driver_index: self.driver_index,
+            manufacturer_id: self.manufacturer_id,
+            device_type: self.device_type,
+            density_id: self.density_id,
+            flags: 0,
+            capacity_bytes: self.capacity_bytes?,
+        })
+    }
+}
+
+fn field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line.find(key)? + key.len();
+    line[start..].split_whitespace().next()
+}
+
+fn hex_field(line: &str, key: &str) -> Option<u32> {
+    let value = field(line, key)?;
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    u32::from_str_radix(value, 16).ok()
+}
+
+/// 解析 `get_user_flash_cfg: isnand_%d type_%d id_%02x%02x%02x` 行
+fn parse_cfg_line(line: &str) -> Option<FlashId> {
+    let media = match field(line, "isnand_")? {
+        "0" => FlashMedia::Nor,
+        "1" => FlashMedia::Nand,
+        _ => return None,
+    };
+    let driver_index = field(line, "type_")?.parse::<u8>().ok()?;
+    let id = field(line, "id_")?;
+    if id.len() != 6 {
+        return None;
+    }
+    let id = u32::from_str_radix(id, 16).ok()?;
+    Some(FlashId {
+        media,
+        driver_index,
+        manufacturer_id: (id >> 16) as u8,
+        device_type: (id >> 8) as u8,
+        density_id: id as u8,
+        capacity_bytes: None,
+    })
+}
+
+/// 解析 stub 启动日志，按出现顺序返回每个 flash 的信息
+pub fn parse_stub_log(log: &str) -> Vec<FlashId> {
+    let mut flashes: Vec<FlashId> = Vec::new();
+    let mut sizes: Vec<u32> = Vec::new();
+    for line in log.lines() {
+        if line.contains("get_user_flash_cfg:") {
+            flashes.extend(parse_cfg_line(line));
+        } else if line.contains(" sector:0x")
+            && let Some(size) = hex_field(line, "size:")
+        {
+            sizes.push(size);
+        }
+    }
+
+    // 容量行与探测行一一对应时才采用打印的容量，否则查型号表或按 JEDEC density 推算
+    let use_sizes = sizes.len() == flashes.len();
+    for (index, flash) in flashes.iter_mut().enumerate() {
+        flash.capacity_bytes = if use_sizes {
+            Some(sizes[index])
+        } else {
+            flash
+                .part()
+                .map(|part| part.capacity_bytes)
+                .or_else(|| flash.jedec_capacity())
+        };
+    }
+    flashes
+}
@@ -0,0 +1,100 @@
+//! 常见 NOR/NAND flash 型号表
+//!
+//! stub 配置中的 flash 条目可以用型号代替原始的 JEDEC ID 与容量，
+//! 例如 `"part": "W25N01GV"`；[`crate::flash_id`] 也用它把探测到的 ID 还原为型号。
+
+use crate::stub_config::FlashMedia;
+
+/// 一个 flash 型号的 JEDEC ID 与容量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlashPart {
+    pub name: &'static str,
+    pub media: FlashMedia,
+    pub manufacturer_id: u8,
+    pub device_type: u8,
+    pub density_id: u8,
+    pub capacity_bytes: u32,
+}
+
+const MIB: u32 = 1024 * 1024;
+
+const fn nor(name: &'static str, id: [u8; 3], capacity_mib: u32) -> FlashPart {
+    FlashPart {
+        name,
+        media: FlashMedia::Nor,
+        manufacturer_id: id[0],
+        device_type: id[1],
+        density_id: id[2],
+        capacity_bytes: capacity_mib * MIB,
+    }
+}
+
+const fn nand(name: &'static str, id: [u8; 3], capacity_mib: u32) -> FlashPart {
+    FlashPart {
+        name,
+        media: FlashMedia::Nand,
+        manufacturer_id: id[0],
+        device_type: id[1],
+        density_id: id[2],
+        capacity_bytes: capacity_mib * MIB,
+    }
+}
+
+/// 内置型号表，按厂商分组
+pub const FLASH_PARTS: &[FlashPart] = &[
+    // GigaDevice
+    nor("GD25Q16E", [0xC8, 0x40, 0x15], 2),
+    nor("GD25Q32E", [0xC8, 0x40, 0x16], 4),
+    nor("GD25Q64E", [0xC8, 0x40, 0x17], 8),
+    nor("GD25Q128E", [0xC8, 0x40, 0x18], 16),
+    nor("GD25Q256E", [0xC8, 0x40, 0x19], 32),
+    nor("GD25LQ64E", [0xC8, 0x60, 0x17], 8),
+    nor("GD25LQ128E", [0xC8, 0x60, 0x18], 16),
+    // Winbond
+    nor("W25Q16JV", [0xEF, 0x40, 0x15], 2),
+    nor("W25Q32JV", [0xEF, 0x40, 0x16], 4),
+    nor("W25Q64JV", [0xEF, 0x40, 0x17], 8),
+    nor("W25Q128JV", [0xEF, 0x40, 0x18], 16),
+    nor("W25Q256JV", [0xEF, 0x40, 0x19], 32),
+    nor("W25Q64JW", [0xEF, 0x60, 0x17], 8),
+    nor("W25Q128JW", [0xEF, 0x60, 0x18], 16),
+    nand("W25N512GV", [0xEF, 0xAA, 0x20], 64),
+    nand("W25N01GV", [0xEF, 0xAA, 0x21], 128),
+    nand("W25N02KV", [0xEF, 0xAA, 0x22], 256),
+    // Macronix
+    nor("MX25L6433F", [0xC2, 0x20, 0x17], 8),
+    nor("MX25L12835F", [0xC2, 0x20, 0x18], 16),
+    nor("MX25U6432F", [0xC2, 0x25, 0x37], 8),
+    nor("MX25U12832F", [0xC2, 0x25, 0x38], 16),
+    // XMC
+    nor("XM25QH64C", [0x20, 0x40, 0x17], 8),
+    nor("XM25QH128C", [0x20, 0x40, 0x18], 16),
+    // Puya
+    nor("PY25Q64HA", [0x85, 0x20, 0x17], 8),
+    nor("PY25Q128HA", [0x85, 0x20, 0x18], 16),
+    // Zbit
+    nor("ZB25VQ64", [0x5E, 0x40, 0x17], 8),
+    nor("ZB25VQ128", [0x5E, 0x40, 0x18], 16),
+];
+
+/// 按型号查找，不区分大小写
+pub fn find_part(name: &str) -> Option<&'static FlashPart> {
+    FLASH_PARTS
+        .iter()
+        .find(|part| part.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// 按 JEDEC ID 查找
+pub fn find_by_jedec(
+    media: FlashMedia,
+    manufacturer_id: u8,
+    device_type: u8,
+    density_id: u8,
+) -> Option<&'static FlashPart> {
+    FLASH_PARTS.iter().find(|part| {
+        part.media == media
+            && part.manufacturer_id == manufacturer_id
+            && part.device_type == device_type
+            && part.density_id == density_id
+    })
+}
@@ -1,9 +1,26 @@
+pub mod dfu_image;
+pub mod dfu_write;
+pub mod diff;
+pub mod efuse;
 pub mod erase_flash;
+pub mod factory_cali;
+pub mod flash_id;
+pub mod flash_parts;
+pub mod policy;
 mod ram_stub;
 pub mod read_flash;
+pub mod reg;
 pub mod reset;
+pub mod rtt;
+pub mod run_ram;
+pub mod secure_boot;
+#[cfg(feature = "simulator")]
+pub mod simulator;
 pub mod speed;
 pub mod stub_config;
+pub mod svd;
+pub mod symbolize;
+pub mod transcript;
 pub mod utils;
 pub mod write_flash;
 
@@ -27,8 +44,14 @@ pub use crate::erase_flash::EraseFlashTrait;
 pub use crate::read_flash::ReadFlashTrait;
 pub use crate::write_flash::WriteFlashTrait;
 pub use error::{Error, Result};
+pub use ram_stub::{EmbeddedStub, list_embedded_stubs, load_embedded_stub};
 
+use crate::common::sifli_debug::DebugChip;
+use crate::dfu_write::DfuDownload;
+use crate::flash_id::FlashId;
+use crate::policy::{RetryPolicy, TimeoutPolicy};
 use crate::progress::{ProgressHelper, ProgressSinkArc, no_op_progress_sink};
+use crate::transcript::TranscriptMode;
 use serialport::SerialPort;
 use std::sync::{
     Arc,
@@ -144,6 +167,16 @@ pub struct SifliToolBase {
     pub cancel_token: CancelToken,
     /// 外部 stub 文件路径，如果指定则优先使用外部文件而非内嵌文件
     pub external_stub_path: Option<String>,
+    /// 外部签名公钥文件路径（DER），仅 SF32LB55/58 使用，未指定时使用内嵌公钥
+    pub sig_pub_key_path: Option<String>,
+    /// 串口录制/回放模式，默认直接使用真实串口
+    pub transcript: Option<TranscriptMode>,
+    /// 各类操作的超时时间
+    pub timeouts: TimeoutPolicy,
+    /// 幂等命令的重试策略
+    pub retry: RetryPolicy,
+    /// stub 启动到出现提示符之间的输出，包含 flash 探测信息
+    pub stub_log: Vec<u8>,
 }
 
 impl SifliToolBase {
@@ -169,6 +202,11 @@ impl SifliToolBase {
             progress_helper,
             cancel_token: CancelToken::new(),
             external_stub_path: None,
+            sig_pub_key_path: None,
+            transcript: None,
+            timeouts: TimeoutPolicy::default(),
+            retry: RetryPolicy::default(),
+            stub_log: Vec::new(),
         }
     }
 
@@ -194,6 +232,11 @@ impl SifliToolBase {
             progress_helper,
             cancel_token: CancelToken::new(),
             external_stub_path: None,
+            sig_pub_key_path: None,
+            transcript: None,
+            timeouts: TimeoutPolicy::default(),
+            retry: RetryPolicy::default(),
+            stub_log: Vec::new(),
         }
     }
 
@@ -221,6 +264,11 @@ impl SifliToolBase {
             progress_helper,
             cancel_token: CancelToken::new(),
             external_stub_path,
+            sig_pub_key_path: None,
+            transcript: None,
+            timeouts: TimeoutPolicy::default(),
+            retry: RetryPolicy::default(),
+            stub_log: Vec::new(),
         }
     }
 
@@ -248,6 +296,11 @@ impl SifliToolBase {
             progress_helper,
             cancel_token,
             external_stub_path,
+            sig_pub_key_path: None,
+            transcript: None,
+            timeouts: TimeoutPolicy::default(),
+            retry: RetryPolicy::default(),
+            stub_log: Vec::new(),
         }
     }
 
@@ -270,6 +323,32 @@ pub struct WriteFlashFile {
     pub crc32: u32,
 }
 
+pub struct VerifyParams {
+    pub files: Vec<WriteFlashFile>,
+}
+
+/// 单个文件段的校验结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyResult {
+    pub address: u32,
+    pub len: u32,
+    pub crc32: u32,
+    pub matched: bool,
+}
+
+/// 通过 ROM DFU 下载的镜像
+pub struct DfuWriteParams {
+    pub files: Vec<DfuWriteFile>,
+}
+
+#[derive(Debug)]
+pub struct DfuWriteFile {
+    /// 目标存储器编号，对应 `dfu_recv` 数据包中的 flash_id
+    pub flash_id: u8,
+    /// 已签名的 DFU 镜像
+    pub data: Vec<u8>,
+}
+
 pub struct ReadFlashParams {
     pub files: Vec<ReadFlashFile>,
 }
@@ -313,7 +392,42 @@ pub trait SifliToolTrait: Send + Sync {
         self.base().check_cancelled()
     }
 
+    /// 解析 stub 启动日志中的 flash 探测信息
+    fn flash_ids(&self) -> Result<Vec<FlashId>> {
+        let log = String::from_utf8_lossy(&self.base().stub_log);
+        let flashes = flash_id::parse_stub_log(&log);
+        if !flashes.is_empty() {
+            return Ok(flashes);
+        }
+        // SD 卡没有 JEDEC ID，stub 只打印容量
+        if common::ram_command::is_sd_memory(&self.base().memory_type) {
+            return Err(Error::invalid_input(
+                "SD memory has no JEDEC flash ID; use a NOR or NAND stub",
+            ));
+        }
+        if log.trim().is_empty() {
+            return Err(Error::protocol(
+                "no stub boot log was captured while starting the stub",
+            ));
+        }
+        Err(Error::protocol(
+            "the stub boot log has no get_user_flash_cfg line",
+        ))
+    }
+
+    /// UART 调试通道，不支持的芯片返回 `None`
+    fn debug_chip(&mut self) -> Option<&mut dyn DebugChip> {
+        None
+    }
+
+    /// ROM 的 DFU 下载流程，不支持的芯片返回 `None`
+    fn dfu_download(&mut self) -> Option<&mut dyn DfuDownload> {
+        None
+    }
+
     fn set_speed(&mut self, baud: u32) -> Result<()>;
+    /// 按顺序尝试候选波特率，返回通过校验的速率
+    fn negotiate_speed(&mut self, candidates: &[u32]) -> Result<u32>;
     fn soft_reset(&mut self) -> Result<()>;
 }
 
@@ -321,13 +435,18 @@ pub trait SifliTool:
     SifliToolTrait + WriteFlashTrait + ReadFlashTrait + EraseFlashTrait + Send + Sync
 {
     /// 工厂函数，根据芯片类型创建对应的 SifliTool 实现
-    fn create_tool(base_param: SifliToolBase) -> Box<dyn SifliTool>
+    fn create_tool(base_param: SifliToolBase) -> Result<Box<dyn SifliTool>>
     where
         Self: Sized;
 }
 
 /// 工厂函数，根据芯片类型创建对应的 SifliTool 实现
-pub fn create_sifli_tool(chip_type: ChipType, base_param: SifliToolBase) -> Box<dyn SifliTool> {
+///
+/// 串口打开失败或 stub 下载失败时返回错误
+pub fn create_sifli_tool(
+    chip_type: ChipType,
+    base_param: SifliToolBase,
+) -> Result<Box<dyn SifliTool>> {
     match chip_type {
         ChipType::SF32LB52 => sf32lb52::SF32LB52Tool::create_tool(base_param),
         ChipType::SF32LB55 => sf32lb55::SF32LB55Tool::create_tool(base_param),
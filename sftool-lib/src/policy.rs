@@ -0,0 +1,151 @@
+//! 超时与重试策略
+//!
+//! 所有等待设备应答的超时都集中在 [`TimeoutPolicy`] 中，幂等命令（`burn_verify`、
+//! `burn_read`）在超时或校验失败后按 [`RetryPolicy`] 自动重试。
+
+use crate::common::ram_command::{Command, is_sd_memory};
+use crate::common::sifli_debug::DEFUALT_RECV_TIMEOUT;
+use crate::{Error, Result};
+use std::time::Duration;
+
+/// 区域擦除超时按扇区估算时使用的扇区大小
+const ERASE_SECTOR_SIZE: u32 = 4 * 1024;
+
+/// 各类操作的超时时间
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeoutPolicy {
+    /// RAM 命令的应答超时
+    pub command: Duration,
+    /// 整片擦除超时
+    pub erase: Duration,
+    /// 区域擦除时每个 4KB 扇区允许的时间
+    pub erase_sector: Duration,
+    /// 读取 flash 时每个数据块和应答行的超时
+    pub read: Duration,
+    /// 调试帧的应答超时
+    pub debug: Duration,
+    /// SD 卡等慢速存储的超时倍数
+    pub sd_multiplier: u32,
+}
+
+impl Default for TimeoutPolicy {
+    fn default() -> Self {
+        Self {
+            command: Duration::from_millis(4000),
+            erase: Duration::from_millis(30_000),
+            erase_sector: Duration::from_millis(800),
+            read: Duration::from_millis(10_000),
+            debug: DEFUALT_RECV_TIMEOUT,
+            sd_multiplier: 3,
+        }
+    }
+}
+
+impl TimeoutPolicy {
+    /// 可以通过 [`TimeoutPolicy::set`] 修改的字段名
+    pub const KEYS: [&'static str; 6] = [
+        "command",
+        "erase",
+        "erase_sector",
+        "read",
+        "debug",
+        "sd_multiplier",
+    ];
+
+    /// 按名称设置一项超时（毫秒），`sd_multiplier` 为倍数
+    pub fn set(&mut self, key: &str, value: u64) -> Result<()> {
+        let millis = Duration::from_millis(value);
+        match key {
+            "command" => self.command = millis,
+            "erase" => self.erase = millis,
+            "erase_sector" => self.erase_sector = millis,
+            "read" => self.read = millis,
+            "debug" => self.debug = millis,
+            "sd_multiplier" => {
+                self.sd_multiplier = u32::try_from(value).map_err(|_| {
+                    Error::invalid_input(format!("sd_multiplier {} is too large", value))
+                })?
+            }
+            _ => {
+                return Err(Error::invalid_input(format!(
+                    "unknown timeout '{}', expected one of: {}",
+                    key,
+                    Self::KEYS.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 解析 `key=value` 形式的设置并应用
+    pub fn apply(&mut self, spec: &str) -> Result<()> {
+        let Some((key, value)) = spec.split_once('=') else {
+            return Err(Error::invalid_input(format!(
+                "invalid timeout '{}', expected KEY=MILLISECONDS",
+                spec
+            )));
+        };
+        let value = value.trim().parse::<u64>().map_err(|e| {
+            Error::invalid_input(format!("invalid timeout value '{}': {}", value, e))
+        })?;
+        self.set(key.trim(), value)
+    }
+
+    /// 对慢速存储放大超时
+    pub fn scaled(&self, timeout: Duration, memory_type: &str) -> Duration {
+        if is_sd_memory(memory_type) {
+            timeout * self.sd_multiplier
+        } else {
+            timeout
+        }
+    }
+
+    /// RAM 命令的应答超时
+    pub fn command_timeout(&self, cmd: &Command, memory_type: &str) -> Duration {
+        let timeout = match cmd {
+            Command::EraseAll { .. } => self.erase,
+            _ => self.command,
+        };
+        self.scaled(timeout, memory_type)
+    }
+
+    /// 按扇区数估算区域擦除的超时
+    pub fn erase_region_timeout(&self, len: u32, memory_type: &str) -> Duration {
+        let sectors = len / ERASE_SECTOR_SIZE + 1;
+        self.scaled(self.erase_sector * sectors, memory_type)
+    }
+
+    /// 读取 flash 的超时
+    pub fn read_timeout(&self, memory_type: &str) -> Duration {
+        self.scaled(self.read, memory_type)
+    }
+}
+
+/// 幂等命令的重试策略
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 首次失败后的最多重试次数，0 表示不重试
+    pub retries: u32,
+    /// 每次重试前的等待时间
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 判断一次失败是否值得重试：超时、传输校验失败和协议错误通常是链路问题，
+    /// 取消或参数错误则不应重试
+    pub fn is_retryable(error: &Error) -> bool {
+        matches!(
+            error,
+            Error::Timeout(_) | Error::CrcMismatch { .. } | Error::Protocol(_)
+        )
+    }
+}
@@ -59,6 +59,10 @@ pub enum ProgressOperation {
         address: u32,
         len: u32,
     },
+    VerifyBlank {
+        address: u32,
+        len: u32,
+    },
     CheckRedownload {
         address: u32,
         size: u64,
@@ -71,6 +75,14 @@ pub enum ProgressOperation {
         address: u32,
         size: u32,
     },
+    DfuWrite {
+        flash_id: u8,
+        size: u64,
+    },
+    LoadRam {
+        address: u32,
+        size: u64,
+    },
 }
 
 /// 进度上下文
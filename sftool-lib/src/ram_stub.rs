@@ -9,11 +9,17 @@ pub(crate) struct RamStubFile;
 pub static CHIP_FILE_NAME: phf::Map<&'static str, &'static str> = phf_map! {
     "sf32lb52_nor" => "ram_patch_52X.bin",
     "sf32lb52_nand" => "ram_patch_52X_NAND.bin",
+    "sf32lb52_nand_nobbm" => "ram_patch_52X_NAND_NOBBM.bin",
+    "sf32lb52_nand_6m" => "ram_patch_52X_NAND_6M.bin",
+    "sf32lb52_nand_8m" => "ram_patch_52X_NAND_8M.bin",
     "sf32lb52_sd" => "ram_patch_52X_SD.bin",
     "sf32lb55_nor" => "ram_patch_55X.bin",
     "sf32lb55_sd" => "ram_patch_55X_SD.bin",
     "sf32lb56_nor" => "ram_patch_56X.bin",
     "sf32lb56_nand" => "ram_patch_56X_NAND.bin",
+    "sf32lb56_nand_nobbm" => "ram_patch_56X_NAND_NOBBM.bin",
+    "sf32lb56_nand_6m" => "ram_patch_56X_NAND_6M.bin",
+    "sf32lb56_nand_8m" => "ram_patch_56X_NAND_8M.bin",
     "sf32lb56_sd" => "ram_patch_56X_SD.bin",
     "sf32lb57_nor" => "ram_patch_57X_NOR.bin",
     "sf32lb57_nand" => "ram_patch_57X_NAND.bin",
@@ -22,7 +28,10 @@ pub static CHIP_FILE_NAME: phf::Map<&'static str, &'static str> = phf_map! {
     "sf32lb58_nor_type1" => "ram_patch_58X_TYPE1.bin",
     "sf32lb58_nand" => "ram_patch_58X_NAND.bin",
     "sf32lb58_nand_type1" => "ram_patch_58X_NAND_TYPE1.bin",
+    "sf32lb58_nand_nobbm" => "ram_patch_58X_NAND_NOBBM.bin",
     "sf32lb58_nand_nobbm_type1" => "ram_patch_58X_NAND_NOBBM_TYPE1.bin",
+    "sf32lb58_nand_6m" => "ram_patch_58X_NAND_6M.bin",
+    "sf32lb58_nand_8m" => "ram_patch_58X_NAND_8M.bin",
     "sf32lb58_sd" => "ram_patch_58X_SD.bin",
     "sf32lb58_sd_type1" => "ram_patch_58x_SD_Type1.bin",
 };
@@ -100,3 +109,54 @@ pub fn load_stub_file(
         data: Cow::Owned(stub.data.to_vec()),
     })
 }
+
+/// 内嵌 stub 文件的信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedStub {
+    /// 对应的芯片与内存类型键，如 "sf32lb52_nor"；未被任何键引用的文件为 `None`
+    pub key: Option<&'static str>,
+    pub file_name: String,
+    pub size: usize,
+    /// 标准 CRC-32（与 zlib/`crc32` 命令一致）
+    pub crc32: u32,
+    /// 是否包含可写入的驱动配置块
+    pub has_driver_config: bool,
+}
+
+/// 列出所有内嵌的 stub 文件，有键的按键排序在前，其余按文件名排序
+pub fn list_embedded_stubs() -> Vec<EmbeddedStub> {
+    const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+    let mut stubs: Vec<EmbeddedStub> = RamStubFile::iter()
+        .filter(|name| name.ends_with(".bin"))
+        .filter_map(|name| {
+            let file = RamStubFile::get(&name)?;
+            let key = CHIP_FILE_NAME
+                .entries()
+                .find(|(_, file_name)| **file_name == name)
+                .map(|(key, _)| *key);
+            Some(EmbeddedStub {
+                key,
+                file_name: name.to_string(),
+                size: file.data.len(),
+                crc32: CRC.checksum(&file.data),
+                has_driver_config: crate::stub_config::find_stub_config_offset(&file.data)
+                    .is_some(),
+            })
+        })
+        .collect();
+    stubs.sort_by(|a, b| {
+        (a.key.is_none(), a.key, &a.file_name).cmp(&(b.key.is_none(), b.key, &b.file_name))
+    });
+    stubs
+}
+
+/// 按键（如 "sf32lb52_nand"）或文件名读取内嵌 stub
+pub fn load_embedded_stub(name: &str) -> crate::Result<Vec<u8>> {
+    let key = name.to_lowercase();
+    let file_name = CHIP_FILE_NAME.get(key.as_str()).copied().unwrap_or(name);
+    RamStubFile::get(file_name)
+        .filter(|_| file_name.ends_with(".bin"))
+        .map(|file| file.data.into_owned())
+        .ok_or_else(|| crate::Error::invalid_input(format!("no embedded stub named '{}'", name)))
+}
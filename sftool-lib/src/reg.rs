@@ -0,0 +1,61 @@
+//! 外设寄存器读写
+//!
+//! 通过 UART 调试通道直接访问寄存器，不暂停内核也不复位芯片。
+//! 寄存器名称到地址的转换见 [`crate::svd`]。
+
+use crate::common::sifli_debug::{SifliDebug, SifliUartCommand};
+use crate::{Error, Result, SifliToolTrait};
+
+/// 读取一个 32 位寄存器
+pub fn read_register<T: SifliToolTrait + ?Sized>(tool: &mut T, address: u32) -> Result<u32> {
+    let chip = tool.debug_chip().ok_or_else(unsupported)?;
+    chip.debug_connect()?;
+    with_debug(chip, |chip| chip.debug_read_word32(address))
+}
+
+/// 读-改-写一个 32 位寄存器：`mask` 中为 1 的位替换为 `value` 中对应的位，返回写入的值。
+/// `mask` 为全 1 时直接写入，不先读取
+pub fn modify_register<T: SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    address: u32,
+    mask: u32,
+    value: u32,
+) -> Result<u32> {
+    let chip = tool.debug_chip().ok_or_else(unsupported)?;
+    chip.debug_connect()?;
+    with_debug(chip, |chip| modify(chip, address, mask, value))
+}
+
+fn unsupported() -> Error {
+    Error::UnsupportedChip(
+        "register access is only supported on SF32LB52, SF32LB56 and SF32LB57".to_string(),
+    )
+}
+
+/// 在已进入调试模式的芯片上执行读-改-写
+fn modify<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    address: u32,
+    mask: u32,
+    value: u32,
+) -> Result<u32> {
+    let new_value = if mask == u32::MAX {
+        value
+    } else {
+        let current = tool.debug_read_word32(address)?;
+        (current & !mask) | (value & mask)
+    };
+    tool.debug_write_word32(address, new_value)?;
+    Ok(new_value)
+}
+
+/// 连接调试通道执行一次访问，结束后退出调试模式
+fn with_debug<T, R>(tool: &mut T, access: impl FnOnce(&mut T) -> Result<R>) -> Result<R>
+where
+    T: SifliDebug + SifliToolTrait + ?Sized,
+{
+    let result = access(tool);
+    // 退出失败时不覆盖访问本身的错误
+    let _ = tool.debug_command(SifliUartCommand::Exit);
+    result
+}
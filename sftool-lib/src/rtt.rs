@@ -0,0 +1,281 @@
+//! SEGGER RTT 日志查看
+//!
+//! 通过 UART 调试通道直接读写目标 RAM 中的 `_SEGGER_RTT` 控制块，不暂停内核：
+//!
+//! | 偏移 | 长度 | 内容 |
+//! |------|------|------|
+//! | 0    | 16   | 标识 `"SEGGER RTT"`，其余补 0 |
+//! | 16   | 4    | 上行（目标 → 主机）缓冲区数量 |
+//! | 20   | 4    | 下行（主机 → 目标）缓冲区数量 |
+//! | 24   | 24*N | 缓冲区描述符：名称指针、缓冲区指针、大小、WrOff、RdOff、Flags |
+//!
+//! 上行缓冲区由主机读出 `RdOff..WrOff` 后回写 `RdOff`；下行缓冲区由主机写入数据后回写 `WrOff`。
+//! 控制块可以通过 ELF 中的 `_SEGGER_RTT` 符号定位，也可以在一段 RAM 中搜索标识。
+
+use crate::common::serial_io::sleep_with_cancel;
+use crate::common::sifli_debug::{SifliDebug, SifliUartCommand};
+use crate::{ChipType, Error, Result, SifliToolTrait};
+use goblin::elf::Elf;
+use std::ops::Range;
+use std::time::Duration;
+
+/// 控制块标识
+pub const RTT_ID: &[u8; 16] = b"SEGGER RTT\0\0\0\0\0\0";
+/// 控制块符号名
+pub const RTT_SYMBOL: &str = "_SEGGER_RTT";
+/// 控制块头长度（标识和两个缓冲区数量）
+pub const RTT_HEADER_SIZE: u32 = 24;
+/// 缓冲区描述符长度
+pub const RTT_DESCRIPTOR_SIZE: u32 = 24;
+/// 每个方向允许的最大缓冲区数量，超出视为控制块损坏
+pub const RTT_MAX_BUFFERS: u32 = 16;
+
+const DESC_BUFFER: usize = 4;
+const DESC_SIZE: usize = 8;
+const DESC_WR_OFF: u32 = 12;
+const DESC_RD_OFF: u32 = 16;
+/// 搜索控制块时每次读取的字节数
+const SCAN_CHUNK: u32 = 1024;
+
+/// 控制块位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RttLocation {
+    /// 已知地址（例如 ELF 中的 `_SEGGER_RTT` 符号）
+    Address(u32),
+    /// 在给定 RAM 范围内搜索标识
+    Scan(Range<u32>),
+}
+
+#[derive(Debug, Clone)]
+pub struct RttParams {
+    pub location: RttLocation,
+    /// 两次轮询之间的间隔
+    pub poll_interval: Duration,
+}
+
+/// 接收上行数据并提供下行数据
+pub trait RttHandler {
+    /// 找到控制块后调用一次
+    fn on_attach(&mut self, _address: u32, _up_channels: usize, _down_channels: usize) {}
+    /// 上行通道收到的数据
+    fn on_up(&mut self, channel: usize, data: &[u8]) -> Result<()>;
+    /// 需要写入下行通道的新数据；放不下的部分会在之后的轮询中继续写入
+    fn poll_down(&mut self, _channel: usize) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// 连接调试通道并持续轮询 RTT 缓冲区，直到出错或被取消
+pub fn run<T: SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    params: &RttParams,
+    handler: &mut dyn RttHandler,
+) -> Result<()> {
+    let chip = tool.debug_chip().ok_or_else(|| {
+        Error::UnsupportedChip(
+            "rtt is only supported on SF32LB52, SF32LB56 and SF32LB57".to_string(),
+        )
+    })?;
+    chip.debug_connect()?;
+    // 不暂停内核，轮询只会因出错或取消结束
+    let result = poll(chip, params, handler);
+    // 取消后仍会退出调试模式；退出失败时不覆盖原错误
+    let _ = chip.debug_command(SifliUartCommand::Exit);
+    result
+}
+
+/// 返回芯片默认搜索控制块的 RAM 范围
+pub fn default_scan_range(chip: &ChipType) -> Option<Range<u32>> {
+    match chip {
+        ChipType::SF32LB52 | ChipType::SF32LB57 => Some(0x2000_0000..0x2008_0000),
+        ChipType::SF32LB56 => Some(0x2000_0000..0x200C_8000),
+        _ => None,
+    }
+}
+
+/// 从 ELF 镜像中查找 `_SEGGER_RTT` 符号的地址
+pub fn find_rtt_symbol(data: &[u8]) -> Result<Option<u32>> {
+    let elf = Elf::parse(data)?;
+    Ok(elf
+        .syms
+        .iter()
+        .find(|sym| elf.strtab.get_at(sym.st_name) == Some(RTT_SYMBOL))
+        .map(|sym| sym.st_value as u32))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Descriptor {
+    address: u32,
+    buffer: u32,
+    size: u32,
+    wr_off: u32,
+    rd_off: u32,
+}
+
+impl Descriptor {
+    fn parse(address: u32, raw: &[u8]) -> Self {
+        let word = |offset: usize| u32::from_le_bytes(raw[offset..offset + 4].try_into().unwrap());
+        Self {
+            address,
+            buffer: word(DESC_BUFFER),
+            size: word(DESC_SIZE),
+            wr_off: word(DESC_WR_OFF as usize),
+            rd_off: word(DESC_RD_OFF as usize),
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.size != 0 && (self.wr_off >= self.size || self.rd_off >= self.size) {
+            return Err(Error::protocol(format!(
+                "RTT buffer descriptor at 0x{:08X} is corrupt (size {}, WrOff {}, RdOff {})",
+                self.address, self.size, self.wr_off, self.rd_off
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 在已进入调试模式的芯片上定位控制块并持续轮询
+fn poll<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    params: &RttParams,
+    handler: &mut dyn RttHandler,
+) -> Result<()> {
+    let address = match &params.location {
+        RttLocation::Address(address) => {
+            let id = tool.debug_read_memory(*address, RTT_ID.len())?;
+            if id != RTT_ID {
+                return Err(Error::protocol(format!(
+                    "no RTT control block at 0x{address:08X} (has the firmware initialized RTT yet?)"
+                )));
+            }
+            *address
+        }
+        RttLocation::Scan(range) => scan(tool, range.clone())?,
+    };
+
+    let counts = tool.debug_read_memory(address + RTT_ID.len() as u32, 8)?;
+    let up_count = u32::from_le_bytes(counts[0..4].try_into().unwrap());
+    let down_count = u32::from_le_bytes(counts[4..8].try_into().unwrap());
+    if up_count > RTT_MAX_BUFFERS || down_count > RTT_MAX_BUFFERS {
+        return Err(Error::protocol(format!(
+            "RTT control block at 0x{address:08X} reports {up_count} up and {down_count} down buffers"
+        )));
+    }
+    tracing::info!(
+        "RTT control block at 0x{:08X}: {} up, {} down buffers",
+        address,
+        up_count,
+        down_count
+    );
+    handler.on_attach(address, up_count as usize, down_count as usize);
+
+    let descriptors = address + RTT_HEADER_SIZE;
+    let descriptors_len = (up_count + down_count) * RTT_DESCRIPTOR_SIZE;
+    let mut pending: Vec<Vec<u8>> = vec![Vec::new(); down_count as usize];
+    loop {
+        tool.check_cancelled()?;
+        let raw = tool.debug_read_memory(descriptors, descriptors_len as usize)?;
+        let mut parsed = raw
+            .chunks_exact(RTT_DESCRIPTOR_SIZE as usize)
+            .enumerate()
+            .map(|(index, raw)| {
+                Descriptor::parse(descriptors + index as u32 * RTT_DESCRIPTOR_SIZE, raw)
+            });
+        let up: Vec<Descriptor> = parsed.by_ref().take(up_count as usize).collect();
+        let down: Vec<Descriptor> = parsed.collect();
+
+        for (channel, desc) in down.iter().enumerate() {
+            if let Some(data) = handler.poll_down(channel) {
+                pending[channel].extend_from_slice(&data);
+            }
+            if !pending[channel].is_empty() {
+                desc.check()?;
+                let written = write_down(tool, desc, &pending[channel])?;
+                pending[channel].drain(..written);
+            }
+        }
+
+        for (channel, desc) in up.iter().enumerate() {
+            desc.check()?;
+            let data = read_up(tool, desc)?;
+            if !data.is_empty() {
+                handler.on_up(channel, &data)?;
+            }
+        }
+
+        sleep_with_cancel(&tool.base().cancel_token, params.poll_interval)?;
+    }
+}
+
+fn scan<T: SifliDebug + SifliToolTrait + ?Sized>(tool: &mut T, range: Range<u32>) -> Result<u32> {
+    let overlap = RTT_ID.len() as u32 - 1;
+    let mut address = range.start;
+    while address < range.end {
+        tool.check_cancelled()?;
+        // 每块多读 15 字节，避免标识跨块时漏掉
+        let len = (range.end - address).min(SCAN_CHUNK + overlap);
+        let data = tool.debug_read_memory(address, len as usize)?;
+        if let Some(offset) = data
+            .windows(RTT_ID.len())
+            .position(|window| window == RTT_ID)
+        {
+            return Ok(address + offset as u32);
+        }
+        address += SCAN_CHUNK;
+    }
+    Err(Error::protocol(format!(
+        "no RTT control block found in 0x{:08X}..0x{:08X}",
+        range.start, range.end
+    )))
+}
+
+fn read_up<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    desc: &Descriptor,
+) -> Result<Vec<u8>> {
+    if desc.wr_off == desc.rd_off {
+        return Ok(Vec::new());
+    }
+    let data = if desc.wr_off > desc.rd_off {
+        tool.debug_read_memory(
+            desc.buffer + desc.rd_off,
+            (desc.wr_off - desc.rd_off) as usize,
+        )?
+    } else {
+        let mut data = tool.debug_read_memory(
+            desc.buffer + desc.rd_off,
+            (desc.size - desc.rd_off) as usize,
+        )?;
+        data.extend(tool.debug_read_memory(desc.buffer, desc.wr_off as usize)?);
+        data
+    };
+    // 先回写 RdOff 再交给调用方，数据即视为已取走
+    tool.debug_write_word32(desc.address + DESC_RD_OFF, desc.wr_off)?;
+    Ok(data)
+}
+
+fn write_down<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    desc: &Descriptor,
+    data: &[u8],
+) -> Result<usize> {
+    if desc.size == 0 {
+        return Ok(0);
+    }
+    // 保留一个字节区分满和空
+    let free = (desc.rd_off + desc.size - desc.wr_off - 1) % desc.size;
+    let len = (free as usize).min(data.len());
+    if len == 0 {
+        return Ok(0);
+    }
+
+    let first = len.min((desc.size - desc.wr_off) as usize);
+    tool.debug_write_memory(desc.buffer + desc.wr_off, &data[..first])?;
+    if first < len {
+        tool.debug_write_memory(desc.buffer, &data[first..len])?;
+    }
+    let wr_off = (desc.wr_off + len as u32) % desc.size;
+    tool.debug_write_word32(desc.address + DESC_WR_OFF, wr_off)?;
+    Ok(len)
+}
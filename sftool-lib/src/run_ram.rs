@@ -0,0 +1,255 @@
+//! 在 RAM 中加载并运行程序
+//!
+//! 流程与 SF32LB52/56/57 下载 RAM stub 相同：复位并暂停内核，通过调试通道把各段写入 RAM，
+//! 设置 SP、PC 后恢复运行并退出调试模式，不会改动 flash。
+//!
+//! SP 取自向量表的第 0 个字，PC 默认取向量表中的复位向量（第 1 个字），ELF 文件则默认使用其入口地址。
+//! ELF 文件按 `__Vectors` 符号或 `.isr_vector` 段定位向量表，都没有时与二进制文件一样
+//! 取地址最低的段的开头。所有段都必须位于 SRAM 内。
+
+use crate::common::serial_io::sleep_with_cancel;
+use crate::common::sifli_debug::{SifliDebug, SifliUartCommand};
+use crate::progress::{ProgressOperation, ProgressStatus};
+use crate::{Error, Result, SifliToolTrait};
+use goblin::elf::Elf;
+use goblin::elf::program_header::PT_LOAD;
+use std::ops::Range;
+use std::time::Duration;
+
+/// 可以直接写入并运行程序的 SRAM 地址范围
+pub const RAM_RANGE: Range<u32> = 0x2000_0000..0x2100_0000;
+/// 向量表的符号名
+pub const VECTOR_SYMBOL: &str = "__Vectors";
+/// 向量表所在的段名
+pub const VECTOR_SECTION: &str = ".isr_vector";
+
+/// 写入 RAM 的一段数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamSegment {
+    pub address: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunRamParams {
+    pub segments: Vec<RamSegment>,
+    /// 入口地址，为空时使用向量表中的复位向量
+    pub entry: Option<u32>,
+    /// 向量表地址，为空时取地址最低的段的开头
+    pub vector_table: Option<u32>,
+}
+
+impl RunRamParams {
+    /// 使用 ELF 中所有 PT_LOAD 段，入口为 ELF 入口地址
+    pub fn from_elf(data: &[u8]) -> Result<Self> {
+        let elf = Elf::parse(data)?;
+        let mut segments = Vec::new();
+        for ph in elf.program_headers.iter() {
+            if ph.p_type != PT_LOAD || ph.p_filesz == 0 {
+                continue;
+            }
+            let range = ph.file_range();
+            let bytes = data.get(range).ok_or_else(|| {
+                Error::invalid_input(format!(
+                    "ELF segment at 0x{:08X} extends past the end of the file",
+                    ph.p_paddr
+                ))
+            })?;
+            segments.push(RamSegment {
+                address: ph.p_paddr as u32,
+                data: bytes.to_vec(),
+            });
+        }
+        if segments.is_empty() {
+            return Err(Error::invalid_input("ELF file has no loadable segments"));
+        }
+
+        let vector_table = elf
+            .syms
+            .iter()
+            .find(|sym| elf.strtab.get_at(sym.st_name) == Some(VECTOR_SYMBOL))
+            .map(|sym| sym.st_value as u32)
+            .or_else(|| {
+                elf.section_headers
+                    .iter()
+                    .find(|sh| elf.shdr_strtab.get_at(sh.sh_name) == Some(VECTOR_SECTION))
+                    .map(|sh| sh.sh_addr as u32)
+            });
+        let params = Self {
+            segments,
+            entry: (elf.entry != 0).then_some(elf.entry as u32),
+            vector_table,
+        };
+        params.check_ram()?;
+        Ok(params)
+    }
+
+    /// 把二进制文件整体加载到指定地址，入口取其向量表
+    pub fn from_bin(data: Vec<u8>, address: u32) -> Self {
+        Self {
+            segments: vec![RamSegment { address, data }],
+            entry: None,
+            vector_table: None,
+        }
+    }
+
+    /// 检查所有段都位于 [`RAM_RANGE`] 内，写入 flash 等地址的段无法通过调试通道加载
+    pub fn check_ram(&self) -> Result<()> {
+        for segment in &self.segments {
+            let end = segment.address as u64 + segment.data.len() as u64;
+            if !RAM_RANGE.contains(&segment.address) || end > RAM_RANGE.end as u64 {
+                return Err(Error::invalid_input(format!(
+                    "segment at 0x{:08X} ({} bytes) is outside RAM 0x{:08X}..0x{:08X}",
+                    segment.address,
+                    segment.data.len(),
+                    RAM_RANGE.start,
+                    RAM_RANGE.end
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 向量表中的初始 SP 和复位向量
+    pub fn vector_table(&self) -> Result<(u32, u32)> {
+        let address = match self.vector_table {
+            Some(address) => address,
+            None => self
+                .segments
+                .iter()
+                .map(|segment| segment.address)
+                .min()
+                .ok_or_else(|| Error::invalid_input("nothing to load"))?,
+        };
+        let table = self
+            .segments
+            .iter()
+            .find_map(|segment| {
+                let offset = address.checked_sub(segment.address)? as usize;
+                segment.data.get(offset..offset.checked_add(8)?)
+            })
+            .ok_or_else(|| {
+                Error::invalid_input(format!(
+                    "no vector table at 0x{address:08X}: no segment holds 8 bytes there"
+                ))
+            })?;
+        let word =
+            |offset: usize| u32::from_le_bytes(table[offset..offset + 4].try_into().unwrap());
+        Ok((word(0), word(4)))
+    }
+
+    /// 实际使用的 SP 和 PC
+    pub fn sp_pc(&self) -> Result<(u32, u32)> {
+        let (sp, reset) = self.vector_table()?;
+        Ok((sp, self.entry.unwrap_or(reset)))
+    }
+}
+
+/// 复位芯片，把程序写入 RAM 并运行，结束后退出调试模式
+pub fn load_and_run<T: SifliToolTrait + ?Sized>(tool: &mut T, params: &RunRamParams) -> Result<()> {
+    let chip = tool.debug_chip().ok_or_else(|| {
+        Error::UnsupportedChip(
+            "run_ram is only supported on SF32LB52, SF32LB56 and SF32LB57".to_string(),
+        )
+    })?;
+    // 先检查段地址和向量表，避免复位之后才发现无法运行
+    params.check_ram()?;
+    params.sp_pc()?;
+    chip.debug_connect()?;
+    chip.prepare_reset()?;
+    reset_and_halt(chip)?;
+    load_segments(chip, params)?;
+    chip.debug_command(SifliUartCommand::Exit)?;
+    Ok(())
+}
+
+/// 复位并在复位向量处暂停内核，随后重新进入调试模式
+pub(crate) fn reset_and_halt<T: SifliDebug + SifliToolTrait + ?Sized>(tool: &mut T) -> Result<()> {
+    use probe_rs::MemoryMappedRegister;
+    use probe_rs::architecture::arm::core::armv7m::{Aircr, Demcr};
+
+    // 1.1. reset_catch_set
+    let demcr = tool.debug_read_word32(Demcr::get_mmio_address() as u32)?;
+    let mut demcr = Demcr(demcr);
+    demcr.set_vc_corereset(true);
+    tool.debug_write_word32(Demcr::get_mmio_address() as u32, demcr.into())?;
+
+    // 1.2. reset_system
+    let mut aircr = Aircr(0);
+    aircr.vectkey();
+    aircr.set_sysresetreq(true);
+    let _ = tool.debug_write_word32(Aircr::get_mmio_address() as u32, aircr.into()); // MCU已经重启，不一定能收到正确回复
+    sleep_with_cancel(&tool.base().cancel_token, Duration::from_millis(10))?;
+
+    // 1.3. Re-enter debug mode
+    tool.debug_command(SifliUartCommand::Enter)?;
+
+    // 1.4. halt
+    tool.debug_halt()?;
+
+    // 1.5. reset_catch_clear
+    let demcr = tool.debug_read_word32(Demcr::get_mmio_address() as u32)?;
+    let mut demcr = Demcr(demcr);
+    demcr.set_vc_corereset(false);
+    tool.debug_write_word32(Demcr::get_mmio_address() as u32, demcr.into())?;
+
+    sleep_with_cancel(&tool.base().cancel_token, Duration::from_millis(100))
+}
+
+/// 按 compat 设置分包写入 RAM，每写完一包推进一次进度
+pub(crate) fn write_ram<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    address: u32,
+    data: &[u8],
+    mut on_chunk: impl FnMut(usize),
+) -> Result<()> {
+    let packet_size = if tool.base().compat { 256 } else { 64 * 1024 };
+
+    let mut addr = address;
+    for chunk in data.chunks(packet_size) {
+        tool.debug_write_memory(addr, chunk)?;
+        addr += chunk.len() as u32;
+        on_chunk(chunk.len());
+    }
+    Ok(())
+}
+
+/// 设置 SP、PC 并恢复运行
+pub(crate) fn start<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    sp: u32,
+    pc: u32,
+) -> Result<()> {
+    use probe_rs::architecture::arm::core::registers::cortex_m::{PC, SP};
+
+    tool.debug_write_core_reg(PC.id.0, pc)?;
+    tool.debug_write_core_reg(SP.id.0, sp)?;
+    tool.debug_run()
+}
+
+/// 在已暂停的芯片上写入全部段并运行，带写入进度
+fn load_segments<T: SifliDebug + SifliToolTrait + ?Sized>(
+    tool: &mut T,
+    params: &RunRamParams,
+) -> Result<()> {
+    let (sp, pc) = params.sp_pc()?;
+
+    let progress = tool.progress();
+    for segment in &params.segments {
+        tool.check_cancelled()?;
+        let bar = progress.create_bar(
+            segment.data.len() as u64,
+            ProgressOperation::LoadRam {
+                address: segment.address,
+                size: segment.data.len() as u64,
+            },
+        );
+        write_ram(tool, segment.address, &segment.data, |len| {
+            bar.inc(len as u64)
+        })?;
+        bar.finish(ProgressStatus::Success);
+    }
+
+    tracing::info!("Starting RAM program: SP=0x{:08X}, PC=0x{:08X}", sp, pc);
+    start(tool, sp, pc)
+}
@@ -0,0 +1,144 @@
+//! 安全启动签名公钥
+//!
+//! SF32LB55/58 在下载 stub 前需要先通过 DFU 配置命令发送签名公钥
+//! （DER 编码的 RSA-2048 SubjectPublicKeyInfo），ROM 用它校验随后下载的 stub 签名。
+//! 开启了安全启动并烧写了自有密钥的产品需要使用自己的公钥和对应签名的 stub。
+
+use crate::ram_stub::{RamStubFile, SIG_PUB_FILE};
+use crate::{ChipType, Error, Result};
+
+/// ROM 支持的 RSA 模数长度（位）
+pub const SIG_PUB_KEY_BITS: usize = 2048;
+
+/// rsaEncryption (1.2.840.113549.1.1.1)
+const RSA_ENCRYPTION_OID: [u8; 9] = [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+
+/// 返回芯片默认使用的内嵌公钥文件名；不需要公钥的芯片返回 `None`
+///
+/// 目前只随附 58X 的公钥，SF32LB55 沿用同一个文件；它能加载内嵌的 55X stub，
+/// 自行签名的 stub 需要通过 `--sig-key` 提供对应的公钥。
+pub fn default_sig_pub_key_name(chip: &ChipType) -> Option<&'static str> {
+    match chip {
+        ChipType::SF32LB55 | ChipType::SF32LB58 => Some(SIG_PUB_FILE),
+        ChipType::SF32LB52 | ChipType::SF32LB56 | ChipType::SF32LB57 => None,
+    }
+}
+
+/// 加载签名公钥：优先使用外部文件，否则使用芯片默认的内嵌公钥；返回前会做格式校验
+pub fn load_sig_pub_key(chip: &ChipType, external_path: Option<&str>) -> Result<Vec<u8>> {
+    let data = match external_path {
+        Some(path) => {
+            tracing::info!("Loading signature public key from: {}", path);
+            std::fs::read(path).map_err(|e| {
+                Error::invalid_input(format!("failed to read signature public key '{path}': {e}"))
+            })?
+        }
+        None => {
+            let name = default_sig_pub_key_name(chip).ok_or_else(|| {
+                Error::UnsupportedChip(format!("{chip:?} does not use a signature public key"))
+            })?;
+            tracing::debug!("Loading embedded signature public key: {}", name);
+            RamStubFile::get(name)
+                .ok_or_else(|| {
+                    Error::invalid_input(format!("embedded signature public key {name} not found"))
+                })?
+                .data
+                .into_owned()
+        }
+    };
+    validate_sig_pub_key(&data)?;
+    Ok(data)
+}
+
+/// 读取一个 DER TLV，返回 (tag, 内容)，并把 `pos` 移到其后
+fn read_tlv<'a>(data: &'a [u8], pos: &mut usize) -> Result<(u8, &'a [u8])> {
+    let truncated = || Error::invalid_input("signature public key: truncated DER data");
+    let tag = *data.get(*pos).ok_or_else(truncated)?;
+    let first = *data.get(*pos + 1).ok_or_else(truncated)?;
+    *pos += 2;
+    let len = if first & 0x80 == 0 {
+        first as usize
+    } else {
+        let count = (first & 0x7F) as usize;
+        if count == 0 || count > 2 {
+            return Err(Error::invalid_input(
+                "signature public key: unsupported DER length encoding",
+            ));
+        }
+        let bytes = data.get(*pos..*pos + count).ok_or_else(truncated)?;
+        *pos += count;
+        bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize)
+    };
+    let content = data.get(*pos..*pos + len).ok_or_else(truncated)?;
+    *pos += len;
+    Ok((tag, content))
+}
+
+fn expect_tlv<'a>(data: &'a [u8], pos: &mut usize, tag: u8, what: &str) -> Result<&'a [u8]> {
+    let (found, content) = read_tlv(data, pos)?;
+    if found != tag {
+        return Err(Error::invalid_input(format!(
+            "signature public key: expected {what} (tag 0x{tag:02X}), found tag 0x{found:02X}"
+        )));
+    }
+    Ok(content)
+}
+
+/// 校验公钥为 DER 编码的 RSA-2048 SubjectPublicKeyInfo
+pub fn validate_sig_pub_key(der: &[u8]) -> Result<()> {
+    let mut pos = 0;
+    let spki = expect_tlv(der, &mut pos, 0x30, "SubjectPublicKeyInfo SEQUENCE")?;
+    if pos != der.len() {
+        return Err(Error::invalid_input(format!(
+            "signature public key: {} trailing byte(s) after DER data",
+            der.len() - pos
+        )));
+    }
+
+    let mut pos = 0;
+    let algorithm = expect_tlv(spki, &mut pos, 0x30, "AlgorithmIdentifier SEQUENCE")?;
+    let bit_string = expect_tlv(spki, &mut pos, 0x03, "public key BIT STRING")?;
+
+    let mut alg_pos = 0;
+    let oid = expect_tlv(algorithm, &mut alg_pos, 0x06, "algorithm OID")?;
+    if oid != RSA_ENCRYPTION_OID {
+        return Err(Error::invalid_input(
+            "signature public key: not an RSA key (expected rsaEncryption OID)",
+        ));
+    }
+
+    let (&unused_bits, rsa_key) = bit_string
+        .split_first()
+        .ok_or_else(|| Error::invalid_input("signature public key: empty BIT STRING"))?;
+    if unused_bits != 0 {
+        return Err(Error::invalid_input(
+            "signature public key: malformed BIT STRING",
+        ));
+    }
+
+    let mut pos = 0;
+    let rsa_key = expect_tlv(rsa_key, &mut pos, 0x30, "RSAPublicKey SEQUENCE")?;
+    let mut pos = 0;
+    let modulus = expect_tlv(rsa_key, &mut pos, 0x02, "modulus INTEGER")?;
+    let exponent = expect_tlv(rsa_key, &mut pos, 0x02, "exponent INTEGER")?;
+    if exponent.is_empty() {
+        return Err(Error::invalid_input(
+            "signature public key: empty public exponent",
+        ));
+    }
+
+    let modulus = match modulus.iter().position(|b| *b != 0) {
+        Some(start) => &modulus[start..],
+        None => &[],
+    };
+    let bits = match modulus.first() {
+        Some(first) => modulus.len() * 8 - first.leading_zeros() as usize,
+        None => 0,
+    };
+    if bits != SIG_PUB_KEY_BITS {
+        return Err(Error::invalid_input(format!(
+            "signature public key: RSA modulus is {bits} bits, expected {SIG_PUB_KEY_BITS}"
+        )));
+    }
+    Ok(())
+}
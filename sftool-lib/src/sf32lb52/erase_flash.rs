@@ -1,4 +1,5 @@
 use super::SF32LB52Tool;
+use crate::common::erase_flash::EraseOps;
 use crate::erase_flash::EraseFlashTrait;
 use crate::{EraseFlashParams, EraseRegionParams, Result};
 
@@ -14,4 +15,11 @@ impl EraseFlashTrait for SF32LB52Tool {
         }
         Ok(())
     }
+
+    fn verify_blank(&mut self, params: &EraseRegionParams) -> Result<()> {
+        for region in params.regions.iter() {
+            EraseOps::verify_blank(self, region.address, region.size)?;
+        }
+        Ok(())
+    }
 }
@@ -8,8 +8,9 @@ pub mod sifli_debug;
 pub mod speed;
 pub mod write_flash;
 
+use crate::common::port::open_port;
 use crate::common::serial_io::{for_tool, sleep_with_cancel};
-use crate::common::sifli_debug::SifliDebug;
+use crate::common::sifli_debug::{DebugChip, SifliDebug};
 use crate::progress::{
     EraseFlashStyle, EraseRegionStyle, ProgressOperation, ProgressStatus, StubStage,
 };
@@ -42,10 +43,11 @@ impl SF32LB52Tool {
         // 发送擦除所有命令
         let _ = self.command(Command::EraseAll { address });
 
+        let timeout = self.base.timeouts.erase;
         let mut io = for_tool(self);
         io.wait_for_pattern(
             b"OK",
-            Duration::from_millis(30_000),
+            timeout,
             &format!("erasing flash at 0x{:08X}", address),
         )?;
 
@@ -56,36 +58,12 @@ impl SF32LB52Tool {
 
     /// 执行区域擦除的内部方法
     pub fn internal_erase_region(&mut self, address: u32, len: u32) -> Result<()> {
-        use ram_command::{Command, RamCommand};
-
-        let progress = self.progress();
-        let spinner = progress.create_spinner(ProgressOperation::EraseRegion {
-            address,
-            len,
-            style: EraseRegionStyle::LegacyFlashStartDecimalLength,
-        });
-
-        // 发送擦除区域命令
-        let _ = self.command(Command::Erase { address, len });
-
-        let timeout_ms = (len as u128 / (4 * 1024) + 1) * 800; // 我们假设每擦除1个sector（4KB）最长时间不超过800ms
-        tracing::info!(
-            "Erase region at 0x{:08X} with length 0x{:08X}, timeout: {} ms",
+        crate::common::erase_flash::EraseOps::erase_region_with_style(
+            self,
             address,
             len,
-            timeout_ms
-        );
-
-        let mut io = for_tool(self);
-        io.wait_for_pattern(
-            b"OK",
-            Duration::from_millis(timeout_ms as u64),
-            &format!("erasing region at 0x{:08X}", address),
-        )?;
-
-        spinner.finish(ProgressStatus::Success);
-
-        Ok(())
+            EraseRegionStyle::LegacyFlashStartDecimalLength,
+        )
     }
 
     fn attempt_connect(&mut self) -> Result<()> {
@@ -137,11 +115,7 @@ impl SF32LB52Tool {
     }
 
     fn download_stub_impl(&mut self) -> Result<()> {
-        use crate::common::sifli_debug::SifliUartCommand;
         use crate::ram_stub::load_stub_file;
-        use probe_rs::MemoryMappedRegister;
-        use probe_rs::architecture::arm::core::armv7m::{Aircr, Demcr};
-        use probe_rs::architecture::arm::core::registers::cortex_m::{PC, SP};
 
         let progress = self.progress();
         let spinner = progress.create_spinner(ProgressOperation::DownloadStub {
@@ -149,38 +123,8 @@ impl SF32LB52Tool {
         });
 
         // 1. reset and halt
-        //    1.1. reset_catch_set
-        let demcr = self.debug_read_word32(Demcr::get_mmio_address() as u32)?;
-        let mut demcr = Demcr(demcr);
-        demcr.set_vc_corereset(true);
-        self.debug_write_word32(Demcr::get_mmio_address() as u32, demcr.into())?;
-
-        // 1.2. reset_system
-        let mut aircr = Aircr(0);
-        aircr.vectkey();
-        aircr.set_sysresetreq(true);
-        let _ = self.debug_write_word32(Aircr::get_mmio_address() as u32, aircr.into()); // MCU已经重启，不一定能收到正确回复
-        sleep_with_cancel(
-            &self.base.cancel_token,
-            std::time::Duration::from_millis(10),
-        )?;
-
-        // 1.3. Re-enter debug mode
-        self.debug_command(SifliUartCommand::Enter)?;
+        crate::run_ram::reset_and_halt(self)?;
 
-        // 1.4. halt
-        self.debug_halt()?;
-
-        // 1.5. reset_catch_clear
-        let demcr = self.debug_read_word32(Demcr::get_mmio_address() as u32)?;
-        let mut demcr = Demcr(demcr);
-        demcr.set_vc_corereset(false);
-        self.debug_write_word32(Demcr::get_mmio_address() as u32, demcr.into())?;
-
-        sleep_with_cancel(
-            &self.base.cancel_token,
-            std::time::Duration::from_millis(100),
-        )?;
         // 2. Download stub - 支持外部 stub 文件
         let chip_memory_key = format!("sf32lb52_{}", self.base.memory_type);
         let stub = match load_stub_file(self.base.external_stub_path.as_deref(), &chip_memory_key) {
@@ -191,16 +135,7 @@ impl SF32LB52Tool {
             }
         };
 
-        let packet_size = if self.base.compat { 256 } else { 64 * 1024 };
-
-        let mut addr = 0x2005_A000;
-        let mut data = &stub.data[..];
-        while !data.is_empty() {
-            let chunk = &data[..std::cmp::min(data.len(), packet_size)];
-            self.debug_write_memory(addr, chunk)?;
-            addr += chunk.len() as u32;
-            data = &data[chunk.len()..];
-        }
+        crate::run_ram::write_ram(self, 0x2005_A000, &stub.data, |_| {})?;
 
         // 2.1.1 Set RTC->BKP0R to 0xA640
         // RTC->BKP0R address is 0x500cb000 + 0x30
@@ -227,11 +162,7 @@ impl SF32LB52Tool {
                 .try_into()
                 .expect("slice with exactly 4 bytes"),
         );
-        self.debug_write_core_reg(PC.id.0, pc)?;
-        self.debug_write_core_reg(SP.id.0, sp)?;
-
-        // 3.2. run
-        self.debug_run()?;
+        crate::run_ram::start(self, sp, pc)?;
 
         spinner.finish(ProgressStatus::Success);
 
@@ -240,19 +171,16 @@ impl SF32LB52Tool {
 }
 
 impl SifliTool for SF32LB52Tool {
-    fn create_tool(base: SifliToolBase) -> Box<dyn SifliTool> {
-        let mut port = serialport::new(&base.port_name, 1000000)
-            .timeout(Duration::from_secs(5))
-            .open()
-            .unwrap();
-        port.write_request_to_send(false).unwrap();
+    fn create_tool(base: SifliToolBase) -> Result<Box<dyn SifliTool>> {
+        let mut port = open_port(&base)?;
+        port.write_request_to_send(false)?;
         std::thread::sleep(Duration::from_millis(100));
 
         let mut tool = Box::new(Self { base, port });
         if tool.base.before.should_download_stub() {
-            tool.download_stub().expect("Failed to download stub");
+            tool.download_stub()?;
         }
-        tool
+        Ok(tool)
     }
 }
 
@@ -265,11 +193,20 @@ impl SifliToolTrait for SF32LB52Tool {
         &self.base
     }
 
+    fn debug_chip(&mut self) -> Option<&mut dyn DebugChip> {
+        Some(self)
+    }
+
     fn set_speed(&mut self, baud: u32) -> Result<()> {
         use crate::speed::SpeedTrait;
         SpeedTrait::set_speed(self, baud)
     }
 
+    fn negotiate_speed(&mut self, candidates: &[u32]) -> Result<u32> {
+        use crate::speed::SpeedTrait;
+        SpeedTrait::negotiate_speed(self, candidates)
+    }
+
     fn soft_reset(&mut self) -> Result<()> {
         use crate::reset::Reset;
         Reset::soft_reset(self)
@@ -2,8 +2,10 @@ use super::SF32LB52Tool;
 use crate::Result;
 use crate::common::serial_io::is_cancelled_io_error;
 use crate::common::sifli_debug::{
-    ChipFrameFormat, RecvError, START_WORD, SifliUartCommand, SifliUartResponse, common_debug,
+    ChipFrameFormat, DebugChip, RecvError, START_WORD, SifliUartCommand, SifliUartResponse,
+    common_debug,
 };
+use crate::efuse::EfuseLayout;
 use std::io::{BufReader, Read};
 
 // Re-export for the module
@@ -103,6 +105,10 @@ impl crate::common::sifli_debug::SifliDebug for SF32LB52Tool {
         common_debug::debug_read_word32_impl::<SF32LB52Tool, SF32LB52FrameFormat>(self, addr)
     }
 
+    fn debug_read_memory(&mut self, addr: u32, len: usize) -> Result<Vec<u8>> {
+        common_debug::debug_read_memory_impl::<SF32LB52Tool, SF32LB52FrameFormat>(self, addr, len)
+    }
+
     fn debug_write_word32(&mut self, addr: u32, data: u32) -> Result<()> {
         common_debug::debug_write_word32_impl::<SF32LB52Tool, SF32LB52FrameFormat>(self, addr, data)
     }
@@ -129,3 +135,13 @@ impl crate::common::sifli_debug::SifliDebug for SF32LB52Tool {
         common_debug::debug_halt_impl::<SF32LB52Tool, SF32LB52FrameFormat>(self)
     }
 }
+
+impl DebugChip for SF32LB52Tool {
+    fn debug_connect(&mut self) -> Result<()> {
+        self.attempt_connect()
+    }
+
+    fn efuse_layout(&self) -> Option<EfuseLayout> {
+        Some(EfuseLayout::SF32LB52)
+    }
+}
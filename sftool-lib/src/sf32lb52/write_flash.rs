@@ -1,7 +1,7 @@
 use super::SF32LB52Tool;
 use crate::common::write_flash::FlashWriter;
 use crate::write_flash::WriteFlashTrait;
-use crate::{Result, WriteFlashParams};
+use crate::{Result, VerifyParams, VerifyResult, WriteFlashParams};
 
 impl WriteFlashTrait for SF32LB52Tool {
     fn write_flash(&mut self, params: &WriteFlashParams) -> Result<()> {
@@ -20,4 +20,8 @@ impl WriteFlashTrait for SF32LB52Tool {
         }
         Ok(())
     }
+
+    fn verify_flash(&mut self, params: &VerifyParams) -> Result<Vec<VerifyResult>> {
+        FlashWriter::verify_files(self, &params.files)
+    }
 }
@@ -10,13 +10,23 @@ impl RamCommand for SF32LB55Tool {
     fn command(&mut self, cmd: Command) -> Result<Response> {
         let cmd_string = self.format_command(&cmd);
         let memory_type = self.base.memory_type.clone();
+        let timeouts = self.base.timeouts.clone();
+        let retry = self.base.retry.clone();
         let mut io = for_tool(self);
-        RamOps::send_command_and_wait_response(&mut io, cmd, &cmd_string, memory_type.as_str())
+        RamOps::send_command_and_wait_response(
+            &mut io,
+            cmd,
+            &cmd_string,
+            memory_type.as_str(),
+            &timeouts,
+            &retry,
+        )
     }
 
     fn send_data(&mut self, data: &[u8]) -> Result<Response> {
         let config = CommandConfig {
             compat_mode: self.base.compat,
+            response_timeout: self.base.timeouts.command,
             ..Default::default()
         };
         let mut io = for_tool(self);
@@ -27,6 +37,14 @@ impl RamCommand for SF32LB55Tool {
 impl DownloadStub for SF32LB55Tool {
     fn download_stub(&mut self) -> Result<()> {
         // Use SifliTool trait methods
-        self.download_stub_impl()
+        self.download_stub_impl()?;
+
+        // DFU 流程本身不等待提示符；在这里等待 stub 进入 shell 并保存启动日志
+        let stub_log = {
+            let mut io = for_tool(self);
+            RamOps::capture_stub_log(&mut io, b"msh >", 200, 5)?
+        };
+        self.base.stub_log = stub_log;
+        Ok(())
     }
 }
@@ -1,9 +1,25 @@
 use super::SF32LB55Tool;
-use crate::common::speed::SpeedOps;
+use crate::common::speed::{ProbeAddresses, SpeedOps};
 use crate::{Result, speed::SpeedTrait};
 
+/// 波特率协商时按存储类型选用的 burn_verify 地址
+///
+/// SF32LB55 没有 NAND stub，`nand` 不会被用到；随附的 SD stub 经过加密，SD 窗口无法从 stub 中核实，
+/// 沿用 0x6800_0000。burn_verify 对未映射的地址返回 Fail，同样视为链路可用
+const PROBE: ProbeAddresses = ProbeAddresses {
+    nor: 0x1000_0000,
+    nand: 0x1000_0000,
+    sd: 0x6800_0000,
+};
+
 impl SpeedTrait for SF32LB55Tool {
     fn set_speed(&mut self, speed: u32) -> Result<()> {
+        // DFU 下载的 stub 可能尚未进入 shell，先同步提示符
+        SpeedOps::sync_prompt(self)?;
         SpeedOps::set_speed(self, speed)
     }
+
+    fn negotiate_speed(&mut self, candidates: &[u32]) -> Result<u32> {
+        SpeedOps::negotiate_speed(self, candidates, PROBE)
+    }
 }
@@ -11,13 +11,23 @@ impl RamCommand for SF32LB56Tool {
     fn command(&mut self, cmd: Command) -> Result<Response> {
         let cmd_string = self.format_command(&cmd);
         let memory_type = self.base.memory_type.clone();
+        let timeouts = self.base.timeouts.clone();
+        let retry = self.base.retry.clone();
         let mut io = for_tool(self);
-        RamOps::send_command_and_wait_response(&mut io, cmd, &cmd_string, memory_type.as_str())
+        RamOps::send_command_and_wait_response(
+            &mut io,
+            cmd,
+            &cmd_string,
+            memory_type.as_str(),
+            &timeouts,
+            &retry,
+        )
     }
 
     fn send_data(&mut self, data: &[u8]) -> Result<Response> {
         let config = CommandConfig {
             compat_mode: self.base.compat,
+            response_timeout: self.base.timeouts.command,
             ..Default::default()
         };
         let mut io = for_tool(self);
@@ -43,21 +53,25 @@ impl DownloadStub for SF32LB56Tool {
 
         // 根据memory_type选择不同的等待条件
         let is_sd = is_sd_memory(&self.base.memory_type);
-        let mut io = for_tool(self);
-        if is_sd {
-            // SD卡模式：等待 "sd0 OPEN success"，超时5秒
-            RamOps::wait_for_shell_prompt(
-                &mut io,
-                b"sd0 OPEN success",
-                1000, // 1秒间隔
-                5,    // 最多重试5次 (总计5秒)
-            )
-        } else {
-            // 非SD模式：等待shell提示符 "msh >"
-            RamOps::wait_for_shell_prompt(
-                &mut io, b"msh >", 200, // 200ms间隔
-                5,   // 最多重试5次
-            )
-        }
+        let stub_log = {
+            let mut io = for_tool(self);
+            if is_sd {
+                // SD卡模式：等待 "sd0 OPEN success"，超时5秒
+                RamOps::capture_stub_log(
+                    &mut io,
+                    b"sd0 OPEN success",
+                    1000, // 1秒间隔
+                    5,    // 最多重试5次 (总计5秒)
+                )?
+            } else {
+                // 非SD模式：等待shell提示符 "msh >"
+                RamOps::capture_stub_log(
+                    &mut io, b"msh >", 200, // 200ms间隔
+                    5,   // 最多重试5次
+                )?
+            }
+        };
+        self.base.stub_log = stub_log;
+        Ok(())
     }
 }
@@ -1,4 +1,5 @@
 use super::SF32LB57Tool;
+use crate::common::erase_flash::EraseOps;
 use crate::erase_flash::EraseFlashTrait;
 use crate::{EraseFlashParams, EraseRegionParams, Result};
 
@@ -13,4 +14,11 @@ impl EraseFlashTrait for SF32LB57Tool {
         }
         Ok(())
     }
+
+    fn verify_blank(&mut self, params: &EraseRegionParams) -> Result<()> {
+        for region in params.regions.iter() {
+            EraseOps::verify_blank(self, region.address, region.size)?;
+        }
+        Ok(())
+    }
 }
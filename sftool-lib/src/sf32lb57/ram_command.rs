@@ -10,13 +10,23 @@ impl RamCommand for SF32LB57Tool {
     fn command(&mut self, cmd: Command) -> Result<Response> {
         let cmd_string = self.format_command(&cmd);
         let memory_type = self.base.memory_type.clone();
+        let timeouts = self.base.timeouts.clone();
+        let retry = self.base.retry.clone();
         let mut io = for_tool(self);
-        RamOps::send_command_and_wait_response(&mut io, cmd, &cmd_string, memory_type.as_str())
+        RamOps::send_command_and_wait_response(
+            &mut io,
+            cmd,
+            &cmd_string,
+            memory_type.as_str(),
+            &timeouts,
+            &retry,
+        )
     }
 
     fn send_data(&mut self, data: &[u8]) -> Result<Response> {
         let config = CommandConfig {
             compat_mode: self.base.compat,
+            response_timeout: self.base.timeouts.command,
             ..Default::default()
         };
         let mut io = for_tool(self);
@@ -49,11 +59,15 @@ impl DownloadStub for SF32LB57Tool {
         self.debug_command(SifliUartCommand::Exit)?;
 
         let is_sd = is_sd_memory(&self.base.memory_type);
-        let mut io = for_tool(self);
-        if is_sd {
-            RamOps::wait_for_shell_prompt(&mut io, b"sd0 OPEN success", 5000, 1)
-        } else {
-            RamOps::wait_for_shell_prompt(&mut io, b"msh >", 200, 5)
-        }
+        let stub_log = {
+            let mut io = for_tool(self);
+            if is_sd {
+                RamOps::capture_stub_log(&mut io, b"sd0 OPEN success", 5000, 1)?
+            } else {
+                RamOps::capture_stub_log(&mut io, b"msh >", 200, 5)?
+            }
+        };
+        self.base.stub_log = stub_log;
+        Ok(())
     }
 }
@@ -2,8 +2,10 @@ use super::SF32LB57Tool;
 use crate::Result;
 use crate::common::serial_io::is_cancelled_io_error;
 use crate::common::sifli_debug::{
-    ChipFrameFormat, RecvError, START_WORD, SifliUartCommand, SifliUartResponse, common_debug,
+    ChipFrameFormat, DebugChip, RecvError, START_WORD, SifliUartCommand, SifliUartResponse,
+    common_debug,
 };
+use crate::efuse::EfuseLayout;
 use std::io::{BufReader, Read};
 
 pub use crate::common::sifli_debug::SifliDebug;
@@ -94,6 +96,10 @@ impl crate::common::sifli_debug::SifliDebug for SF32LB57Tool {
         common_debug::debug_read_word32_impl::<SF32LB57Tool, SF32LB57FrameFormat>(self, addr)
     }
 
+    fn debug_read_memory(&mut self, addr: u32, len: usize) -> Result<Vec<u8>> {
+        common_debug::debug_read_memory_impl::<SF32LB57Tool, SF32LB57FrameFormat>(self, addr, len)
+    }
+
     fn debug_write_word32(&mut self, addr: u32, data: u32) -> Result<()> {
         common_debug::debug_write_word32_impl::<SF32LB57Tool, SF32LB57FrameFormat>(self, addr, data)
     }
@@ -120,3 +126,13 @@ impl crate::common::sifli_debug::SifliDebug for SF32LB57Tool {
         common_debug::debug_halt_impl::<SF32LB57Tool, SF32LB57FrameFormat>(self)
     }
 }
+
+impl DebugChip for SF32LB57Tool {
+    fn debug_connect(&mut self) -> Result<()> {
+        self.attempt_connect()
+    }
+
+    fn efuse_layout(&self) -> Option<EfuseLayout> {
+        Some(EfuseLayout::SF32LB57)
+    }
+}
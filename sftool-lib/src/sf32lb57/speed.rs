@@ -1,9 +1,22 @@
 use super::SF32LB57Tool;
-use crate::common::speed::SpeedOps;
+use crate::common::speed::{ProbeAddresses, SpeedOps};
 use crate::{Result, speed::SpeedTrait};
 
+/// 波特率协商时按存储类型选用的 burn_verify 地址
+///
+/// NAND 和 SD 都映射在 0x6400_0000 窗口（ram_patch_57X_NAND/SD.bin）
+const PROBE: ProbeAddresses = ProbeAddresses {
+    nor: 0x1000_0000,
+    nand: 0x6400_0000,
+    sd: 0x6400_0000,
+};
+
 impl SpeedTrait for SF32LB57Tool {
     fn set_speed(&mut self, speed: u32) -> Result<()> {
         SpeedOps::set_speed(self, speed)
     }
+
+    fn negotiate_speed(&mut self, candidates: &[u32]) -> Result<u32> {
+        SpeedOps::negotiate_speed(self, candidates, PROBE)
+    }
 }
@@ -15,4 +15,11 @@ impl EraseFlashTrait for SF32LB58Tool {
         }
         Ok(())
     }
+
+    fn verify_blank(&mut self, params: &EraseRegionParams) -> Result<()> {
+        for region in params.regions.iter() {
+            EraseOps::verify_blank(self, region.address, region.size)?;
+        }
+        Ok(())
+    }
 }
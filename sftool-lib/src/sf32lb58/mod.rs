@@ -7,10 +7,13 @@ pub mod reset;
 pub mod speed;
 pub mod write_flash;
 
+use crate::common::port::open_port;
 use crate::common::serial_io::{for_tool, sleep_with_cancel};
-use crate::progress::{ProgressOperation, ProgressStatus, StubStage};
+use crate::dfu_image::DfuImage;
+use crate::dfu_write::DfuDownload;
+use crate::progress::{ProgressHandle, ProgressOperation, ProgressStatus, StubStage};
 use crate::sf32lb58::ram_command::DownloadStub;
-use crate::{Result, SifliTool, SifliToolBase, SifliToolTrait};
+use crate::{ChipType, Result, SifliTool, SifliToolBase, SifliToolTrait};
 use serialport::SerialPort;
 use std::time::Duration;
 
@@ -41,11 +44,6 @@ enum DfuConfigType {
 }
 
 impl SF32LB58Tool {
-    // DFU协议常量
-    const BLOCK_SIZE: usize = 512;
-    const HDR_SIZE: usize = 32 + 296;
-    const CHUNK_OVERHEAD: usize = 32 + 4;
-
     /// 发送DFU命令的通用方法
     fn send_dfu_command(&mut self, data_len: usize, delay_ms: Option<u64>) -> Result<()> {
         let cmd = format!("dfu_recv {}\r", data_len);
@@ -87,7 +85,7 @@ impl SF32LB58Tool {
     }
 
     fn download_stub_impl(&mut self) -> Result<()> {
-        use crate::ram_stub::{self, SIG_PUB_FILE, load_stub_file};
+        use crate::ram_stub::load_stub_file;
 
         tracing::info!("Starting SF32LB58 stub download process");
         {
@@ -100,31 +98,28 @@ impl SF32LB58Tool {
             stage: StubStage::Start,
         });
 
-        // 1. 下载签名公钥文件 (58X_sig_pub.der)
-        tracing::debug!("Loading signature public key file: {}", SIG_PUB_FILE);
-        let sig_pub_data = ram_stub::RamStubFile::get(SIG_PUB_FILE).ok_or_else(|| {
-            tracing::error!("Signature public key file not found: {}", SIG_PUB_FILE);
-            std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "58X_sig_pub.der file not found",
-            )
-        })?;
+        // 1. 加载并校验 RAM stub 镜像 - 支持外部 stub 文件，损坏的镜像在发送任何数据前报错
+        let chip_memory_key = format!("sf32lb58_{}", self.base.memory_type);
+        let stub = load_stub_file(self.base.external_stub_path.as_deref(), &chip_memory_key)?;
+        let image = DfuImage::parse(&stub.data)?;
+
+        // 2. 下载签名公钥文件，未指定外部公钥时使用内嵌的 58X_sig_pub.der
+        let sig_pub_data = crate::secure_boot::load_sig_pub_key(
+            &ChipType::SF32LB58,
+            self.base.sig_pub_key_path.as_deref(),
+        )?;
 
         spinner.set_operation(ProgressOperation::DownloadStub {
             stage: StubStage::SignatureKey,
         });
-        self.download_boot_patch_sigkey(&sig_pub_data.data)?;
-
-        // 2. 下载RAM stub文件 - 支持外部 stub 文件
-        let chip_memory_key = format!("sf32lb58_{}", self.base.memory_type);
-        let stub = load_stub_file(self.base.external_stub_path.as_deref(), &chip_memory_key)?;
+        self.download_boot_patch_sigkey(&sig_pub_data)?;
 
         spinner.set_operation(ProgressOperation::DownloadStub {
             stage: StubStage::RamStub,
         });
 
-        // 发送下载镜像命令（flashid = 9 对应RAM stub）
-        self.download_image(&stub.data, 9)?;
+        // 3. 发送下载镜像命令（flashid = 9 对应RAM stub）
+        self.download_image(&image, 9, None)?;
 
         spinner.finish(ProgressStatus::Success);
 
@@ -155,36 +150,15 @@ impl SF32LB58Tool {
         Ok(())
     }
 
-    /// 下载镜像文件
-    fn download_image(&mut self, data: &[u8], flash_id: u8) -> Result<()> {
-        tracing::info!(
-            "Starting image download: flash_id={}, size={} bytes",
-            flash_id,
-            data.len()
-        );
-
-        // 1. 发送镜像头部
-        self.download_image_header(data, flash_id)?;
-
-        // 2. 发送镜像主体
-        self.download_image_body(data, flash_id)?;
-
-        // 3. 发送结束标志
-        self.download_image_end(flash_id)?;
-
-        tracing::info!("Image download completed successfully");
-        Ok(())
-    }
-
     /// 下载镜像头部
-    fn download_image_header(&mut self, data: &[u8], flash_id: u8) -> Result<()> {
+    fn download_image_header(&mut self, image: &DfuImage, flash_id: u8) -> Result<()> {
         tracing::debug!("Downloading image header...");
 
         let header = [DfuCommandType::ImageHeader as u8, flash_id];
-        let total_len = 2 + Self::HDR_SIZE;
+        let total_len = 2 + image.header().len();
 
         self.send_dfu_command(total_len, Some(10))?;
-        self.send_dfu_data(&header, &data[0..Self::HDR_SIZE], None)?;
+        self.send_dfu_data(&header, image.header(), None)?;
 
         tracing::debug!("Waiting for image header response...");
         self.wait_for_ok_response(3000)?;
@@ -194,33 +168,32 @@ impl SF32LB58Tool {
     }
 
     /// 下载镜像主体
-    fn download_image_body(&mut self, data: &[u8], flash_id: u8) -> Result<()> {
+    fn download_image_body(
+        &mut self,
+        image: &DfuImage,
+        flash_id: u8,
+        progress: Option<&ProgressHandle>,
+    ) -> Result<()> {
         tracing::debug!("Downloading image body...");
 
         let body_header = [DfuCommandType::ImageBody as u8, flash_id];
-        let mut offset = Self::HDR_SIZE;
         let mut chunk_count = 0;
 
-        while offset < data.len() {
+        for chunk in image.chunks() {
             self.base.check_cancelled()?;
-            let remaining = data.len() - offset;
-            let chunk_size = std::cmp::min(remaining, Self::CHUNK_OVERHEAD + Self::BLOCK_SIZE);
 
-            tracing::trace!(
-                "Sending chunk {}: offset={}, size={}",
-                chunk_count,
-                offset,
-                chunk_size
-            );
+            tracing::trace!("Sending chunk {}: size={}", chunk_count, chunk.len());
 
-            let total_len = 2 + chunk_size;
+            let total_len = 2 + chunk.len();
             self.send_dfu_command(total_len, Some(10))?;
-            self.send_dfu_data(&body_header, &data[offset..offset + chunk_size], None)?;
+            self.send_dfu_data(&body_header, chunk, None)?;
 
             tracing::trace!("Waiting for chunk {} response...", chunk_count);
             self.wait_for_ok_response(3000)?;
+            if let Some(progress) = progress {
+                progress.inc(chunk.len() as u64);
+            }
 
-            offset += chunk_size;
             chunk_count += 1;
         }
 
@@ -267,20 +240,48 @@ impl SF32LB58Tool {
     }
 }
 
+impl DfuDownload for SF32LB58Tool {
+    fn download_image(
+        &mut self,
+        image: &DfuImage,
+        flash_id: u8,
+        progress: Option<&ProgressHandle>,
+    ) -> Result<()> {
+        tracing::info!(
+            "Starting image download: flash_id={}, size={} bytes, {} chunks",
+            flash_id,
+            image.data().len(),
+            image.chunk_count()
+        );
+
+        // 1. 发送镜像头部
+        self.download_image_header(image, flash_id)?;
+        if let Some(progress) = progress {
+            progress.inc(image.header().len() as u64);
+        }
+
+        // 2. 发送镜像主体
+        self.download_image_body(image, flash_id, progress)?;
+
+        // 3. 发送结束标志
+        self.download_image_end(flash_id)?;
+
+        tracing::info!("Image download completed successfully");
+        Ok(())
+    }
+}
+
 impl SifliTool for SF32LB58Tool {
-    fn create_tool(base: SifliToolBase) -> Box<dyn SifliTool> {
-        let mut port = serialport::new(&base.port_name, 1000000)
-            .timeout(Duration::from_secs(5))
-            .open()
-            .unwrap();
-        port.write_request_to_send(false).unwrap();
+    fn create_tool(base: SifliToolBase) -> Result<Box<dyn SifliTool>> {
+        let mut port = open_port(&base)?;
+        port.write_request_to_send(false)?;
         std::thread::sleep(Duration::from_millis(100));
 
         let mut tool = Box::new(Self { base, port });
         if tool.base.before.should_download_stub() {
-            tool.download_stub().expect("Failed to download stub");
+            tool.download_stub()?;
         }
-        tool
+        Ok(tool)
     }
 }
 
@@ -293,8 +294,18 @@ impl SifliToolTrait for SF32LB58Tool {
         &self.base
     }
 
-    fn set_speed(&mut self, _baud: u32) -> Result<()> {
-        todo!("SF32LB58Tool::set_speed not implemented yet")
+    fn dfu_download(&mut self) -> Option<&mut dyn DfuDownload> {
+        Some(self)
+    }
+
+    fn set_speed(&mut self, baud: u32) -> Result<()> {
+        use crate::speed::SpeedTrait;
+        SpeedTrait::set_speed(self, baud)
+    }
+
+    fn negotiate_speed(&mut self, candidates: &[u32]) -> Result<u32> {
+        use crate::speed::SpeedTrait;
+        SpeedTrait::negotiate_speed(self, candidates)
     }
 
     fn soft_reset(&mut self) -> Result<()> {
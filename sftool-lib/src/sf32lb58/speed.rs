@@ -1,9 +1,25 @@
 use super::SF32LB58Tool;
-use crate::common::speed::SpeedOps;
+use crate::common::speed::{ProbeAddresses, SpeedOps};
 use crate::{Result, speed::SpeedTrait};
 
+/// 波特率协商时按存储类型选用的 burn_verify 地址
+///
+/// NAND 映射在 0x6800_0000（未加密的 ram_patch_58X_NAND_*_ori.bin）；加密的 SD stub
+/// 无法核实，按同一窗口处理。burn_verify 对未映射的地址返回 Fail，同样视为链路可用
+const PROBE: ProbeAddresses = ProbeAddresses {
+    nor: 0x1200_0000,
+    nand: 0x6800_0000,
+    sd: 0x6800_0000,
+};
+
 impl SpeedTrait for SF32LB58Tool {
     fn set_speed(&mut self, speed: u32) -> Result<()> {
+        // DFU 下载的 stub 可能尚未进入 shell，先同步提示符
+        SpeedOps::sync_prompt(self)?;
         SpeedOps::set_speed(self, speed)
     }
+
+    fn negotiate_speed(&mut self, candidates: &[u32]) -> Result<u32> {
+        SpeedOps::negotiate_speed(self, candidates, PROBE)
+    }
 }
@@ -0,0 +1,561 @@
+use super::SimulatorMode;
+use super::memory::SparseMemory;
+use crate::common::port::DEFAULT_BAUD;
+use crate::common::sifli_debug::{ChipFrameFormat, START_WORD};
+use crate::utils::Utils;
+use std::collections::{HashMap, VecDeque};
+
+const ENTER_PAYLOAD: [u8; 8] = [0x41, 0x54, 0x53, 0x46, 0x33, 0x32, 0x05, 0x21];
+const EXIT_PAYLOAD: [u8; 8] = [0x41, 0x54, 0x53, 0x46, 0x33, 0x32, 0x18, 0x21];
+
+const DHCSR: u32 = 0xE000_EDF0;
+const DCRSR: u32 = 0xE000_EDF4;
+const AIRCR: u32 = 0xE000_ED0C;
+const PC_REGSEL: u32 = 15;
+
+/// stub 在 burn_erase_write 中每接收这么多数据回复一次 RX_WAIT
+const RX_WAIT_BLOCK: u32 = 128 * 1024;
+const DFU_STUB_FLASH_ID: u8 = 9;
+
+const PROMPT: &[u8] = b"msh >";
+
+/// 调试帧的编解码参数，从 [`ChipFrameFormat`] 推导
+#[derive(Clone, Copy)]
+pub(crate) struct FrameCodec {
+    create_header: fn(u16) -> Vec<u8>,
+    header_len: usize,
+    big_endian: bool,
+}
+
+impl FrameCodec {
+    pub fn of<F: ChipFrameFormat>() -> Self {
+        Self {
+            create_header: F::create_header,
+            header_len: F::create_header(0).len(),
+            // 通过解码一个已知的字来判断芯片使用的字节序
+            big_endian: F::decode_response_data(&[0, 0, 0, 1]) == 1,
+        }
+    }
+
+    fn u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    fn encode_u32(&self, value: u32) -> [u8; 4] {
+        if self.big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        }
+    }
+}
+
+/// 芯片进入 RAM stub 的方式
+#[derive(Clone, Copy)]
+pub(crate) enum BootProtocol {
+    /// 通过 UART 调试帧写入内存并运行 (SF32LB52/56/57)
+    Debug(FrameCodec),
+    /// 通过 ROM 的 dfu_recv 命令下载 (SF32LB55/58)
+    Dfu,
+}
+
+enum PendingData {
+    EraseWrite {
+        address: u32,
+        len: u32,
+        received: u32,
+    },
+    Write {
+        address: u32,
+        len: u32,
+        received: u32,
+    },
+    Dfu {
+        len: usize,
+        packet: Vec<u8>,
+    },
+}
+
+pub(crate) struct Device {
+    protocol: BootProtocol,
+    pub mode: SimulatorMode,
+    pub memory: SparseMemory,
+    pub baud_rate: u32,
+    pub host_baud_rate: u32,
+    pub ignore_baud: bool,
+    /// 串口链路能承载的最高波特率，超过后收发的数据全部丢失
+    pub max_baud_rate: Option<u32>,
+    pub sd_banner: bool,
+    pub boot_log: Vec<u8>,
+    /// 非 burn_* 命令的预设输出，按命令名匹配
+    pub command_output: HashMap<String, Vec<u8>>,
+    pub dfu_image: Vec<u8>,
+    pub sig_pub_key: Vec<u8>,
+    /// 最近一次通过 DCRSR 写入的 PC
+    pub pc: Option<u32>,
+    output: VecDeque<u8>,
+    input: Vec<u8>,
+    line: Vec<u8>,
+    last_cr: bool,
+    pending: Option<PendingData>,
+    pc_written: bool,
+    running: bool,
+    dfu_header_seen: bool,
+}
+
+impl Device {
+    pub fn new(protocol: BootProtocol) -> Self {
+        Self {
+            protocol,
+            mode: SimulatorMode::Rom,
+            memory: SparseMemory::default(),
+            baud_rate: DEFAULT_BAUD,
+            host_baud_rate: DEFAULT_BAUD,
+            ignore_baud: false,
+            max_baud_rate: None,
+            sd_banner: false,
+            boot_log: Vec::new(),
+            command_output: HashMap::new(),
+            dfu_image: Vec::new(),
+            sig_pub_key: Vec::new(),
+            pc: None,
+            output: VecDeque::new(),
+            input: Vec::new(),
+            line: Vec::new(),
+            last_cr: false,
+            pending: None,
+            pc_written: false,
+            running: false,
+            dfu_header_seen: false,
+        }
+    }
+
+    /// 主机与设备波特率不一致时，双方都只能收到乱码，这里直接丢弃
+    fn in_sync(&self) -> bool {
+        let link_ok = self
+            .max_baud_rate
+            .is_none_or(|max_baud_rate| self.baud_rate <= max_baud_rate);
+        link_ok && (self.ignore_baud || self.host_baud_rate == self.baud_rate)
+    }
+
+    /// 复位芯片，回到 ROM 状态（flash 内容保留）
+    pub fn reset(&mut self) {
+        self.mode = SimulatorMode::Rom;
+        self.baud_rate = DEFAULT_BAUD;
+        self.input.clear();
+        self.line.clear();
+        self.last_cr = false;
+        self.pending = None;
+        self.pc_written = false;
+        self.running = false;
+        self.dfu_header_seen = false;
+    }
+
+    pub fn pending_output(&self) -> usize {
+        self.output.len()
+    }
+
+    pub fn take_output(&mut self, buf: &mut [u8]) -> usize {
+        let count = buf.len().min(self.output.len());
+        for slot in buf.iter_mut().take(count) {
+            *slot = self.output.pop_front().unwrap();
+        }
+        count
+    }
+
+    pub fn clear_output(&mut self) {
+        self.output.clear();
+    }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        if self.in_sync() {
+            self.output.extend(bytes.iter().copied());
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        if !self.in_sync() {
+            return;
+        }
+        self.input.extend_from_slice(bytes);
+
+        let input = std::mem::take(&mut self.input);
+        let mut pos = 0usize;
+        while pos < input.len() {
+            // burn_speed 之后主机仍以旧波特率发送的数据全部丢失
+            if !self.in_sync() {
+                return;
+            }
+            if self.pending.is_some() {
+                pos += self.consume_data(&input[pos..]);
+                continue;
+            }
+
+            if let BootProtocol::Debug(codec) = self.protocol
+                && input[pos] == START_WORD[0]
+            {
+                let rest = &input[pos..];
+                if rest.len() < START_WORD.len() {
+                    break;
+                }
+                if rest[1] == START_WORD[1] {
+                    match self.try_frame(codec, rest) {
+                        Some(consumed) => {
+                            pos += consumed;
+                            continue;
+                        }
+                        // 帧尚未接收完整
+                        None => break,
+                    }
+                }
+            }
+
+            let byte = input[pos];
+            pos += 1;
+            self.handle_char(byte);
+        }
+        // 保留不完整的调试帧，等待后续数据
+        self.input.extend_from_slice(&input[pos..]);
+    }
+
+    fn handle_char(&mut self, byte: u8) {
+        match byte {
+            b'\r' | b'\n' => {
+                let skip = byte == b'\n' && self.last_cr;
+                self.last_cr = byte == b'\r';
+                if skip {
+                    return;
+                }
+                let line = String::from_utf8_lossy(&std::mem::take(&mut self.line)).into_owned();
+                self.handle_line(line.trim());
+            }
+            _ => {
+                self.last_cr = false;
+                self.line.push(byte);
+            }
+        }
+    }
+
+    fn handle_line(&mut self, line: &str) {
+        match (self.mode, self.protocol) {
+            (SimulatorMode::Shell, _) => self.handle_shell_line(line),
+            (SimulatorMode::Rom, BootProtocol::Dfu) => self.handle_rom_line(line),
+            // 调试模式和无 stub 的 ROM 不响应文本命令
+            _ => {}
+        }
+    }
+
+    fn try_frame(&mut self, codec: FrameCodec, buf: &[u8]) -> Option<usize> {
+        if buf.len() < codec.header_len {
+            return None;
+        }
+        let mut len = codec.u16(&buf[2..4]) as usize;
+        // 64KB 的 MEMWrite 包超出 u16 长度字段，按其中的字数计算帧长
+        let body = &buf[codec.header_len..];
+        if body.len() >= 8 && body[..2] == [0x40, 0x77] {
+            len = 8 + 4 * codec.u16(&body[6..8]) as usize;
+        }
+        let total = codec.header_len + len;
+        if buf.len() < total {
+            return None;
+        }
+        let payload = buf[codec.header_len..total].to_vec();
+        self.handle_debug_command(codec, &payload);
+        Some(total)
+    }
+
+    fn send_frame(&mut self, codec: FrameCodec, payload: &[u8]) {
+        let mut frame = (codec.create_header)(payload.len() as u16);
+        frame.extend_from_slice(payload);
+        self.emit(&frame);
+    }
+
+    fn handle_debug_command(&mut self, codec: FrameCodec, payload: &[u8]) {
+        if payload == ENTER_PAYLOAD {
+            self.mode = SimulatorMode::Debug;
+            self.send_frame(codec, &[0xD1]);
+            return;
+        }
+        if payload == EXIT_PAYLOAD {
+            if self.mode == SimulatorMode::Debug {
+                if self.pc_written && self.running {
+                    self.start_stub();
+                } else {
+                    self.mode = SimulatorMode::Rom;
+                }
+            }
+            return;
+        }
+        if self.mode != SimulatorMode::Debug || payload.len() < 8 || payload[0] != 0x40 {
+            return;
+        }
+
+        let address = codec.u32(&payload[2..6]);
+        let count = codec.u16(&payload[6..8]) as usize;
+        match payload[1] {
+            0x72 => {
+                let mut response = vec![0xD2];
+                for i in 0..count {
+                    let word = self.memory.read(address.wrapping_add(4 * i as u32), 4);
+                    let value = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                    response.extend_from_slice(&codec.encode_u32(value));
+                }
+                // 结尾的校验字节，主机端会直接丢弃
+                response.push(0x00);
+                self.send_frame(codec, &response);
+            }
+            0x77 => {
+                let data = &payload[8..];
+                for (i, chunk) in data.chunks_exact(4).take(count).enumerate() {
+                    let value = codec.u32(chunk);
+                    let word_address = address.wrapping_add(4 * i as u32);
+                    self.memory.write(word_address, &value.to_le_bytes());
+                    self.handle_register_write(word_address, value);
+                }
+                self.send_frame(codec, &[0xD3]);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_register_write(&mut self, address: u32, value: u32) {
+        // SF32LB56 会把 0xE000_0000 段映射到 0xF000_0000
+        let is = |register: u32| {
+            address == register || address == ((register & 0x0FFF_FFFF) | 0xF000_0000)
+        };
+
+        if is(DHCSR) && value >> 16 == 0xA05F {
+            let halt = value & 0b10 != 0;
+            let debug_enabled = value & 0b1 != 0;
+            self.running = debug_enabled && !halt;
+        } else if is(DCRSR) && value & (1 << 16) != 0 && value & 0x7F == PC_REGSEL {
+            // 写入的值已经放在紧随 DCRSR 之后的 DCRDR 中
+            let dcrdr = self.memory.read(address.wrapping_add(4), 4);
+            self.pc = Some(u32::from_le_bytes([dcrdr[0], dcrdr[1], dcrdr[2], dcrdr[3]]));
+            self.pc_written = true;
+        } else if is(AIRCR) && value >> 16 == 0x05FA && value & 0b100 != 0 {
+            self.pc_written = false;
+            self.running = false;
+        }
+    }
+
+    fn start_stub(&mut self) {
+        self.mode = SimulatorMode::Shell;
+        self.line.clear();
+        self.emit(b"\r\n \\ | /\r\n- SiFli Corporation\r\n / | \\\r\n");
+        let boot_log = self.boot_log.clone();
+        self.emit(&boot_log);
+        if self.sd_banner {
+            self.emit(b"sd0 OPEN success\r\n");
+        }
+        self.emit(PROMPT);
+    }
+
+    fn handle_rom_line(&mut self, line: &str) {
+        let mut args = line.split_whitespace();
+        if args.next() != Some("dfu_recv") {
+            return;
+        }
+        match args.next().and_then(|len| len.parse::<usize>().ok()) {
+            Some(len) if len > 0 => {
+                self.pending = Some(PendingData::Dfu {
+                    len,
+                    packet: Vec::with_capacity(len),
+                });
+            }
+            _ => self.emit(b"Fail\r\n"),
+        }
+    }
+
+    fn handle_dfu_packet(&mut self, packet: &[u8]) {
+        let ok = match packet {
+            // Config: BootPatchSig
+            [3, 10, key @ ..] => {
+                self.sig_pub_key = key.to_vec();
+                true
+            }
+            [1, _, header @ ..] => {
+                self.dfu_image = header.to_vec();
+                self.dfu_header_seen = true;
+                true
+            }
+            [2, _, body @ ..] if self.dfu_header_seen => {
+                self.dfu_image.extend_from_slice(body);
+                true
+            }
+            [4, _] if self.dfu_header_seen => true,
+            _ => false,
+        };
+
+        if !ok {
+            self.emit(b"Fail\r\n");
+            return;
+        }
+        self.emit(b"OK\r\n");
+        if packet[0] == 4 && packet[1] == DFU_STUB_FLASH_ID {
+            self.dfu_header_seen = false;
+            self.start_stub();
+        }
+    }
+
+    fn handle_shell_line(&mut self, line: &str) {
+        if line.is_empty() {
+            self.emit(b"\r\n");
+            self.emit(PROMPT);
+            return;
+        }
+
+        self.emit(line.as_bytes());
+        self.emit(b"\r\n");
+
+        let mut parts = line.split_whitespace();
+        let command = parts.next().unwrap_or_default();
+        let args: Option<Vec<u32>> = parts
+            .map(|arg| Utils::str_to_u32(arg).ok())
+            .collect::<Option<Vec<u32>>>();
+        let args = args.unwrap_or_default();
+        let arg = |index: usize| args.get(index).copied();
+
+        match (command, arg(0), arg(1), arg(2)) {
+            ("burn_erase_all" | "burn_erase_all_factory", Some(address), _, _) => {
+                // 擦除地址所在的整个存储器窗口
+                self.memory.erase(address & 0xFF00_0000, 0x0100_0000);
+                self.emit(b"OK\r\n");
+            }
+            ("burn_erase", Some(address), Some(len), _) => {
+                self.memory.erase(address, len);
+                self.emit(b"OK\r\n");
+            }
+            ("burn_erase_write", Some(address), Some(len), _) => {
+                self.memory.erase(address, len);
+                self.emit(b"RX_WAIT\r\n");
+                self.pending = Some(PendingData::EraseWrite {
+                    address,
+                    len,
+                    received: 0,
+                });
+                // 长度为 0 时没有后续数据，立即完成
+                self.consume_data(&[]);
+                return;
+            }
+            ("burn_write", Some(address), Some(len), _) => {
+                self.pending = Some(PendingData::Write {
+                    address,
+                    len,
+                    received: 0,
+                });
+                // 长度为 0 时没有后续数据，立即完成
+                self.consume_data(&[]);
+                return;
+            }
+            ("burn_verify", Some(address), Some(len), Some(crc)) => {
+                let data = self.memory.read(address, len as usize);
+                if Utils::calculate_crc32(&data) == crc {
+                    self.emit(b"OK\r\n");
+                } else {
+                    self.emit(b"Fail\r\n");
+                }
+            }
+            ("burn_read", Some(address), Some(len), _) => {
+                let data = self.memory.read(address, len as usize);
+                self.emit(b"start_trans\r\n");
+                self.emit(&data);
+                let trailer = format!("\r\ncrc:0x{:08X}\r\nOK\r\n", Utils::calculate_crc32(&data));
+                self.emit(trailer.as_bytes());
+            }
+            ("burn_speed", Some(baud), _, _) => {
+                // 新波特率立即生效，主机切换后发送回车即可看到提示符
+                self.baud_rate = baud;
+                return;
+            }
+            ("burn_reset", _, _, _) => {
+                self.emit(b"OK\r\n");
+                self.reset();
+                return;
+            }
+            (
+                "burn_erase_all"
+                | "burn_erase_all_factory"
+                | "burn_erase"
+                | "burn_erase_write"
+                | "burn_write"
+                | "burn_verify"
+                | "burn_read"
+                | "burn_speed",
+                _,
+                _,
+                _,
+            ) => self.emit(b"Fail\r\n"),
+            _ => match self.command_output.get(command).cloned() {
+                Some(output) => self.emit(&output),
+                None => {
+                    let message = format!("{}: command not found.\r\n", command);
+                    self.emit(message.as_bytes());
+                }
+            },
+        }
+        self.emit(PROMPT);
+    }
+
+    /// 消费 burn_write / dfu_recv 之后的原始数据，返回消费的字节数
+    fn consume_data(&mut self, data: &[u8]) -> usize {
+        let Some(pending) = self.pending.as_mut() else {
+            return 0;
+        };
+
+        match pending {
+            PendingData::EraseWrite {
+                address,
+                len,
+                received,
+            }
+            | PendingData::Write {
+                address,
+                len,
+                received,
+            } => {
+                let count = data.len().min((*len - *received) as usize);
+                let chunk = &data[..count];
+                self.memory.program(address.wrapping_add(*received), chunk);
+                let before = *received;
+                *received += count as u32;
+                let after = *received;
+
+                let done = after == *len;
+                let erase_write = matches!(pending, PendingData::EraseWrite { .. });
+                if done {
+                    self.pending = None;
+                    self.emit(b"OK\r\n");
+                    self.emit(PROMPT);
+                } else if erase_write && before / RX_WAIT_BLOCK != after / RX_WAIT_BLOCK {
+                    self.emit(b"RX_WAIT\r\n");
+                }
+                count
+            }
+            PendingData::Dfu { len, packet } => {
+                let count = data.len().min(*len - packet.len());
+                packet.extend_from_slice(&data[..count]);
+                if packet.len() == *len {
+                    let packet = std::mem::take(packet);
+                    self.pending = None;
+                    self.handle_dfu_packet(&packet);
+                }
+                count
+            }
+        }
+    }
+}
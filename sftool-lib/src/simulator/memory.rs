@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+const PAGE_SIZE: u32 = 4096;
+
+/// 稀疏存储器模型，未写入的区域读出为 0xFF（与擦除后的 flash 一致）
+#[derive(Default)]
+pub(crate) struct SparseMemory {
+    pages: HashMap<u32, Box<[u8]>>,
+}
+
+impl SparseMemory {
+    pub fn read(&self, address: u32, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| {
+                let addr = address.wrapping_add(i as u32);
+                self.pages
+                    .get(&(addr / PAGE_SIZE))
+                    .map_or(0xFF, |page| page[(addr % PAGE_SIZE) as usize])
+            })
+            .collect()
+    }
+
+    /// 直接覆盖写入（RAM 语义）
+    pub fn write(&mut self, address: u32, data: &[u8]) {
+        for (i, byte) in data.iter().enumerate() {
+            *self.byte_mut(address.wrapping_add(i as u32)) = *byte;
+        }
+    }
+
+    /// 编程写入（flash 语义，只能把 1 变成 0）
+    pub fn program(&mut self, address: u32, data: &[u8]) {
+        for (i, byte) in data.iter().enumerate() {
+            *self.byte_mut(address.wrapping_add(i as u32)) &= *byte;
+        }
+    }
+
+    /// 按扇区对齐擦除 `[address, address + len)` 覆盖到的范围
+    pub fn erase(&mut self, address: u32, len: u32) {
+        if len == 0 {
+            return;
+        }
+        let first = address / PAGE_SIZE;
+        let last = address.saturating_add(len - 1) / PAGE_SIZE;
+        self.pages.retain(|page, _| *page < first || *page > last);
+    }
+
+    fn byte_mut(&mut self, address: u32) -> &mut u8 {
+        let page = self
+            .pages
+            .entry(address / PAGE_SIZE)
+            .or_insert_with(|| vec![0xFF; PAGE_SIZE as usize].into_boxed_slice());
+        &mut page[(address % PAGE_SIZE) as usize]
+    }
+}
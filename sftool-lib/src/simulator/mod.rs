@@ -0,0 +1,364 @@
+//! Software device simulator.
+//!
+//! [`Simulator`] models a SiFli chip well enough to run the complete flashing
+//! pipeline without a board: the UART debug frames used by SF32LB52/56/57
+//! (Enter/Exit/MEMRead/MEMWrite), the `dfu_recv` ROM flow used by
+//! SF32LB55/58, and the `msh >` RAM stub shell with its `burn_*` commands
+//! operating on a sparse memory image.
+//!
+//! The simulator is exposed as an in-process [`SerialPort`] via
+//! [`Simulator::port`] and, on Unix, as a pseudo terminal via
+//! [`Simulator::open_pty`] for host programs that talk to a device path.
+
+mod device;
+mod memory;
+
+use crate::ChipType;
+use crate::common::ram_command::is_sd_memory;
+use device::{BootProtocol, Device, FrameCodec};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::io::{self, ErrorKind, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// What the simulated chip is currently running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulatorMode {
+    /// Boot ROM, waiting for a debug connection or a DFU download.
+    Rom,
+    /// Halted in UART debug mode.
+    Debug,
+    /// RAM stub running, accepting `burn_*` shell commands.
+    Shell,
+}
+
+/// A simulated SiFli target shared by all of its ports.
+#[derive(Clone)]
+pub struct Simulator {
+    device: Arc<Mutex<Device>>,
+}
+
+impl Simulator {
+    /// Create a simulator for `chip` with a NOR flash stub.
+    pub fn new(chip: ChipType) -> Self {
+        Self::with_memory(chip, "nor")
+    }
+
+    /// Create a simulator for `chip`; SD memory types print the
+    /// `sd0 OPEN success` banner the host waits for.
+    pub fn with_memory(chip: ChipType, memory_type: &str) -> Self {
+        let protocol = match chip {
+            ChipType::SF32LB52 => BootProtocol::Debug(FrameCodec::of::<
+                crate::sf32lb52::sifli_debug::SF32LB52FrameFormat,
+            >()),
+            ChipType::SF32LB56 => {
+                BootProtocol::Debug(FrameCodec::of::<crate::sf32lb56::SF32LB56FrameFormat>())
+            }
+            ChipType::SF32LB57 => BootProtocol::Debug(FrameCodec::of::<
+                crate::sf32lb57::sifli_debug::SF32LB57FrameFormat,
+            >()),
+            ChipType::SF32LB55 | ChipType::SF32LB58 => BootProtocol::Dfu,
+        };
+        let mut device = Device::new(protocol);
+        device.sd_banner = is_sd_memory(memory_type);
+        Self {
+            device: Arc::new(Mutex::new(device)),
+        }
+    }
+
+    /// Open a new in-process serial port connected to the simulator.
+    pub fn port(&self) -> Box<dyn SerialPort> {
+        Box::new(SimulatorPort {
+            device: self.device.clone(),
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// Current execution state of the simulated chip.
+    pub fn mode(&self) -> SimulatorMode {
+        self.device.lock().unwrap().mode
+    }
+
+    /// Baud rate the simulated chip is currently using.
+    pub fn baud_rate(&self) -> u32 {
+        self.device.lock().unwrap().baud_rate
+    }
+
+    /// Limit the rate the simulated USB-UART link can carry, like a cheap
+    /// adapter; above it all traffic in both directions is lost.
+    pub fn set_max_baud_rate(&self, max_baud_rate: Option<u32>) {
+        self.device.lock().unwrap().max_baud_rate = max_baud_rate;
+    }
+
+    /// Extra output the simulated stub prints before its first prompt, such
+    /// as the flash detection log.
+    pub fn set_boot_log(&self, log: &str) {
+        self.device.lock().unwrap().boot_log = log.as_bytes().to_vec();
+    }
+
+    /// Make the simulated shell print `output` when it receives `command`,
+    /// e.g. the result line of a calibration command.
+    pub fn set_command_output(&self, command: &str, output: &str) {
+        self.device
+            .lock()
+            .unwrap()
+            .command_output
+            .insert(command.to_string(), output.as_bytes().to_vec());
+    }
+
+    /// Read from the simulated memory; unwritten bytes read as 0xFF.
+    pub fn read_memory(&self, address: u32, len: usize) -> Vec<u8> {
+        self.device.lock().unwrap().memory.read(address, len)
+    }
+
+    /// Preload the simulated memory, e.g. with existing flash contents.
+    pub fn write_memory(&self, address: u32, data: &[u8]) {
+        self.device.lock().unwrap().memory.write(address, data);
+    }
+
+    /// Program counter last written through the debug registers, i.e. where
+    /// the core resumes after a stub or RAM program download.
+    pub fn pc(&self) -> Option<u32> {
+        self.device.lock().unwrap().pc
+    }
+
+    /// Image received through the most recent DFU download (header + body).
+    pub fn dfu_image(&self) -> Vec<u8> {
+        self.device.lock().unwrap().dfu_image.clone()
+    }
+
+    /// Signature public key received through the most recent DFU config command.
+    pub fn sig_pub_key(&self) -> Vec<u8> {
+        self.device.lock().unwrap().sig_pub_key.clone()
+    }
+
+    /// Expose the simulator on a new pseudo terminal.
+    ///
+    /// Baud rate and RTS changes are not visible through a pty, so the
+    /// simulated chip accepts data at any rate while the pty is in use.
+    /// Ptys also reject modem control ioctls; clients should open the path
+    /// with `preserve_dtr_on_open()` and not toggle RTS/DTR.
+    #[cfg(unix)]
+    pub fn open_pty(&self) -> crate::Result<SimulatorPty> {
+        SimulatorPty::open(self.device.clone())
+    }
+}
+
+/// In-process serial port connected to a [`Simulator`].
+pub struct SimulatorPort {
+    device: Arc<Mutex<Device>>,
+    timeout: Duration,
+}
+
+impl Read for SimulatorPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        {
+            let mut device = self.device.lock().unwrap();
+            if device.pending_output() > 0 {
+                return Ok(device.take_output(buf));
+            }
+        }
+        // 模拟器同步处理输入，没有数据时短暂等待即可
+        std::thread::sleep(self.timeout.min(Duration::from_millis(1)));
+        Err(io::Error::new(
+            ErrorKind::TimedOut,
+            "no data from simulator",
+        ))
+    }
+}
+
+impl Write for SimulatorPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.device.lock().unwrap().feed(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for SimulatorPort {
+    fn name(&self) -> Option<String> {
+        Some("simulator".to_string())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.device.lock().unwrap().host_baud_rate)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.device.lock().unwrap().host_baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        // RTS 接在芯片复位脚上
+        if level {
+            self.device.lock().unwrap().reset();
+        }
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.device.lock().unwrap().pending_output() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        if matches!(buffer_to_clear, ClearBuffer::Input | ClearBuffer::All) {
+            self.device.lock().unwrap().clear_output();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(Self {
+            device: self.device.clone(),
+            timeout: self.timeout,
+        }))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`Simulator`] bridged to a pseudo terminal.
+///
+/// The bridge thread stops when this value is dropped.
+#[cfg(unix)]
+pub struct SimulatorPty {
+    path: String,
+    stop: Arc<std::sync::atomic::AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+    // 保持从设备端打开，避免客户端断开时主设备读到 EIO
+    _slave: serialport::TTYPort,
+}
+
+#[cfg(unix)]
+impl SimulatorPty {
+    fn open(device: Arc<Mutex<Device>>) -> crate::Result<Self> {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let (mut master, slave) = serialport::TTYPort::pair()?;
+        let path = slave
+            .name()
+            .ok_or_else(|| crate::Error::invalid_input("pty has no device path"))?;
+        master.set_timeout(Duration::from_millis(10))?;
+        device.lock().unwrap().ignore_baud = true;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            while !thread_stop.load(Ordering::SeqCst) {
+                match master.read(&mut buffer) {
+                    Ok(count) if count > 0 => device.lock().unwrap().feed(&buffer[..count]),
+                    Ok(_) => {}
+                    Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => {}
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) => {
+                        tracing::warn!("Simulator pty read failed: {}", e);
+                        break;
+                    }
+                }
+
+                let count = device.lock().unwrap().take_output(&mut buffer);
+                if count > 0 && master.write_all(&buffer[..count]).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            path,
+            stop,
+            thread: Some(thread),
+            _slave: slave,
+        })
+    }
+
+    /// Device path of the pty, usable as `--port`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SimulatorPty {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -1,5 +1,27 @@
 use crate::Result;
 
+/// `--baud auto` 依次尝试的波特率，从高到低
+pub const AUTO_BAUD_LADDER: [u32; 5] = [6_000_000, 3_000_000, 2_000_000, 1_500_000, 1_000_000];
+
+/// 生成自动协商的候选速率；`preferred`（例如上次成功的速率）排在最前，
+/// 失败后再从高到低尝试完整的速率表，避免一次偶发失败把缓存永久压低
+pub fn auto_baud_candidates(preferred: Option<u32>) -> Vec<u32> {
+    match preferred {
+        Some(preferred) => std::iter::once(preferred)
+            .chain(
+                AUTO_BAUD_LADDER
+                    .iter()
+                    .copied()
+                    .filter(|&baud| baud != preferred),
+            )
+            .collect(),
+        None => AUTO_BAUD_LADDER.to_vec(),
+    }
+}
+
 pub trait SpeedTrait {
     fn set_speed(&mut self, speed: u32) -> Result<()>;
+
+    /// 按顺序尝试 `candidates`，返回最终使用的波特率
+    fn negotiate_speed(&mut self, candidates: &[u32]) -> Result<u32>;
 }
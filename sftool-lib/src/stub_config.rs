@@ -4,7 +4,7 @@
 //! fixed-size T_EXT_DRIVER_CFG block inside a file. It does not handle
 //! encrypted ram_patch images (imgtool) or any CLI parsing concerns.
 
-use crate::{Error, Result};
+use crate::{ChipType, Error, Result};
 use std::path::Path;
 
 const MAGIC_FLAG: u32 = 0xABCDDBCA;
@@ -103,6 +103,160 @@ pub struct Sd0Config {
     pub init_sequence: Sd0InitSequence,
 }
 
+/// Pins and peripherals a chip exposes to the stub driver config.
+///
+/// Pin counts cover the largest package of each series; a count of zero
+/// means the chip has no such port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChipStubRules {
+    pub pa_pins: u8,
+    pub pb_pins: u8,
+    pub pbr_pins: u8,
+    /// PMIC channels the stub can drive; empty if the chip has no external PMIC.
+    pub pmic_channels: &'static [PmicChannel],
+    /// SD0 pinmux options; empty if the chip has no SDIO.
+    pub sd0_pinmux: &'static [Sd0Pinmux],
+}
+
+const ALL_PMIC_CHANNELS: &[PmicChannel] = &[
+    PmicChannel::LvSw1001,
+    PmicChannel::LvSw1002,
+    PmicChannel::LvSw1003,
+    PmicChannel::LvSw1004,
+    PmicChannel::LvSw1005,
+    PmicChannel::HvSw1501,
+    PmicChannel::HvSw1502,
+    PmicChannel::Ldo33,
+    PmicChannel::Ldo30,
+    PmicChannel::Ldo28,
+];
+
+const ALL_SD0_PINMUX: &[Sd0Pinmux] = &[Sd0Pinmux::ClkPa34OrPa09, Sd0Pinmux::ClkPa60OrPa39];
+
+/// SF32LB52 only has PA00-PA44, so the PA60 option does not exist.
+const SF32LB52_SD0_PINMUX: &[Sd0Pinmux] = &[Sd0Pinmux::ClkPa34OrPa09];
+
+/// Return the stub config rules for a chip.
+pub fn chip_stub_rules(chip: &ChipType) -> ChipStubRules {
+    match chip {
+        // PA00-PA44 plus the PBR0-PBR3 backup pins; power comes from the on-chip PMU.
+        ChipType::SF32LB52 => ChipStubRules {
+            pa_pins: 45,
+            pb_pins: 0,
+            pbr_pins: 4,
+            pmic_channels: &[],
+            sd0_pinmux: SF32LB52_SD0_PINMUX,
+        },
+        ChipType::SF32LB55 => ChipStubRules {
+            pa_pins: 80,
+            pb_pins: 64,
+            pbr_pins: 0,
+            pmic_channels: &[],
+            sd0_pinmux: ALL_SD0_PINMUX,
+        },
+        ChipType::SF32LB56 | ChipType::SF32LB57 | ChipType::SF32LB58 => ChipStubRules {
+            pa_pins: 80,
+            pb_pins: 64,
+            pbr_pins: 6,
+            pmic_channels: ALL_PMIC_CHANNELS,
+            sd0_pinmux: ALL_SD0_PINMUX,
+        },
+    }
+}
+
+/// Check that every pin, PMIC channel and SD0 option in the config exists on the chip.
+///
+/// Errors name the offending entry, e.g. `pins[2]` or `pmic.channels[0]`.
+pub fn validate_stub_config(config: &StubConfig, chip: &ChipType) -> Result<()> {
+    let rules = chip_stub_rules(chip);
+
+    for (index, pin) in config.pins.iter().enumerate() {
+        check_pin(
+            &rules,
+            chip,
+            &format!("pins[{index}]"),
+            pin.port,
+            pin.number,
+        )?;
+    }
+
+    if let Some(pmic) = &config.pmic
+        && !pmic.disabled
+    {
+        if rules.pmic_channels.is_empty() {
+            return Err(Error::invalid_input(format!(
+                "pmic: {chip:?} has no external PMIC, remove the entry or set disabled"
+            )));
+        }
+        check_pin(&rules, chip, "pmic.scl", pmic.scl_port, pmic.scl_pin)?;
+        check_pin(&rules, chip, "pmic.sda", pmic.sda_port, pmic.sda_pin)?;
+        if pmic.scl_port == pmic.sda_port && pmic.scl_pin == pmic.sda_pin {
+            return Err(Error::invalid_input(format!(
+                "pmic: scl and sda both use {}",
+                pin_name(pmic.scl_port, pmic.scl_pin)
+            )));
+        }
+        for (index, channel) in pmic.channels.iter().enumerate() {
+            if !rules.pmic_channels.contains(channel) {
+                return Err(Error::invalid_input(format!(
+                    "pmic.channels[{index}]: {} is not available on {chip:?}",
+                    channel.name()
+                )));
+            }
+        }
+    }
+
+    if let Some(sd0) = &config.sd0 {
+        if rules.sd0_pinmux.is_empty() {
+            return Err(Error::invalid_input(format!(
+                "sd0: {chip:?} has no SDIO controller"
+            )));
+        }
+        if !rules.sd0_pinmux.contains(&sd0.pinmux) {
+            return Err(Error::invalid_input(format!(
+                "sd0.pinmux: {} is not available on {chip:?}",
+                sd0.pinmux.name()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_pin(
+    rules: &ChipStubRules,
+    chip: &ChipType,
+    label: &str,
+    port: PinPort,
+    number: u8,
+) -> Result<()> {
+    let count = match port {
+        PinPort::Pa => rules.pa_pins,
+        PinPort::Pb => rules.pb_pins,
+        PinPort::Pbr => rules.pbr_pins,
+    };
+    if count == 0 {
+        return Err(Error::invalid_input(format!(
+            "{label}: {chip:?} has no {} port",
+            port.name()
+        )));
+    }
+    if number >= count {
+        return Err(Error::invalid_input(format!(
+            "{label}: {} does not exist on {chip:?} ({}0-{}{})",
+            pin_name(port, number),
+            port.name(),
+            port.name(),
+            count - 1
+        )));
+    }
+    Ok(())
+}
+
+fn pin_name(port: PinPort, number: u8) -> String {
+    format!("{}{number}", port.name())
+}
+
 /// Scan for the first valid driver config block and return its offset.
 pub fn find_stub_config_offset(data: &[u8]) -> Option<usize> {
     if data.len() < DRIVER_CONFIG_SIZE {
@@ -444,6 +598,15 @@ impl PinPort {
             PinPort::Pbr => 2,
         }
     }
+
+    // Name used in config files and error messages.
+    fn name(self) -> &'static str {
+        match self {
+            PinPort::Pa => "PA",
+            PinPort::Pb => "PB",
+            PinPort::Pbr => "PBR",
+        }
+    }
 }
 
 impl From<PinPort> for u8 {
@@ -543,6 +706,22 @@ impl PmicChannel {
             PmicChannel::Ldo28 => 9,
         }
     }
+
+    // Name used in config files and error messages.
+    fn name(self) -> &'static str {
+        match self {
+            PmicChannel::LvSw1001 => "1v8_lvsw100_1",
+            PmicChannel::LvSw1002 => "1v8_lvsw100_2",
+            PmicChannel::LvSw1003 => "1v8_lvsw100_3",
+            PmicChannel::LvSw1004 => "1v8_lvsw100_4",
+            PmicChannel::LvSw1005 => "1v8_lvsw100_5",
+            PmicChannel::HvSw1501 => "vbat_hvsw150_1",
+            PmicChannel::HvSw1502 => "vbat_hvsw150_2",
+            PmicChannel::Ldo33 => "ldo33",
+            PmicChannel::Ldo30 => "ldo30",
+            PmicChannel::Ldo28 => "ldo28",
+        }
+    }
 }
 
 impl TryFrom<u8> for PmicChannel {
@@ -574,6 +753,14 @@ impl Sd0Pinmux {
             Sd0Pinmux::ClkPa60OrPa39 => 1,
         }
     }
+
+    // Name used in config files and error messages.
+    fn name(self) -> &'static str {
+        match self {
+            Sd0Pinmux::ClkPa34OrPa09 => "clk_pa34_or_pa09",
+            Sd0Pinmux::ClkPa60OrPa39 => "clk_pa60_or_pa39",
+        }
+    }
 }
 
 impl From<Sd0Pinmux> for u8 {
@@ -0,0 +1,547 @@
+//! CMSIS-SVD 寄存器描述
+//!
+//! 只解析按名称访问寄存器所需的部分：外设基地址、寄存器偏移和位宽、位域位置。
+//! 支持外设和寄存器的 `derivedFrom`、位域的三种写法（`bitOffset`/`bitWidth`、`lsb`/`msb`、`bitRange`）
+//! 以及寄存器的 `size`/`resetValue` 继承。
+//!
+//! `dim` 数组（外设、cluster、寄存器和位域）按 `dimIndex` 展开成独立的条目，名称中的 `%s`
+//! 和 `[%s]` 都替换为下标，例如 `CH[%s]` 展开为 `CH0`、`CH1`……；cluster 中的寄存器
+//! 展开为 `CLUSTER_REG` 形式的名称。cluster 和位域上的 `derivedFrom` 不支持，解析时报错。
+//!
+//! 名称格式为 `PERIPH.REG[.FIELD]`，匹配时不区分大小写。
+
+use crate::{Error, Result};
+use roxmltree::Node;
+use std::path::Path;
+
+/// 解析后的 SVD 设备
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub name: String,
+    pub peripherals: Vec<Peripheral>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peripheral {
+    pub name: String,
+    pub base_address: u32,
+    pub registers: Vec<Register>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    pub name: String,
+    pub address_offset: u32,
+    /// 位宽
+    pub size: u32,
+    pub reset_value: Option<u32>,
+    pub description: Option<String>,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub bit_offset: u32,
+    pub bit_width: u32,
+    pub description: Option<String>,
+}
+
+impl Field {
+    /// 位域掩码（已移位）
+    pub fn mask(&self) -> u32 {
+        let bits = if self.bit_width >= 32 {
+            u32::MAX
+        } else {
+            (1 << self.bit_width) - 1
+        };
+        bits << self.bit_offset
+    }
+
+    /// 从寄存器值中取出位域
+    pub fn extract(&self, value: u32) -> u32 {
+        (value & self.mask()) >> self.bit_offset
+    }
+
+    /// 把位域值放到寄存器中对应的位置，超出位宽时报错
+    pub fn insert(&self, value: u32, field_value: u32) -> Result<u32> {
+        if field_value > self.mask() >> self.bit_offset {
+            return Err(Error::invalid_input(format!(
+                "value 0x{:X} does not fit in the {}-bit field {}",
+                field_value, self.bit_width, self.name
+            )));
+        }
+        Ok((value & !self.mask()) | (field_value << self.bit_offset))
+    }
+
+    /// `[msb:lsb]` 形式的位范围
+    pub fn bit_range(&self) -> String {
+        format!(
+            "[{}:{}]",
+            self.bit_offset + self.bit_width - 1,
+            self.bit_offset
+        )
+    }
+}
+
+/// 按名称解析出的寄存器（及可选的位域）
+#[derive(Debug, Clone, Copy)]
+pub struct RegisterRef<'a> {
+    pub peripheral: &'a Peripheral,
+    pub register: &'a Register,
+    pub field: Option<&'a Field>,
+}
+
+impl RegisterRef<'_> {
+    /// 寄存器的绝对地址
+    pub fn address(&self) -> u32 {
+        self.peripheral
+            .base_address
+            .wrapping_add(self.register.address_offset)
+    }
+
+    /// `PERIPH.REG[.FIELD]` 形式的名称
+    pub fn name(&self) -> String {
+        match self.field {
+            Some(field) => format!(
+                "{}.{}.{}",
+                self.peripheral.name, self.register.name, field.name
+            ),
+            None => format!("{}.{}", self.peripheral.name, self.register.name),
+        }
+    }
+}
+
+impl Device {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+
+    /// 解析 SVD 文本
+    pub fn parse(text: &str) -> Result<Self> {
+        let doc = roxmltree::Document::parse(text)
+            .map_err(|e| Error::invalid_input(format!("invalid SVD file: {e}")))?;
+        let root = doc.root_element();
+        if !root.has_tag_name("device") {
+            return Err(Error::invalid_input(
+                "invalid SVD file: root element is not <device>",
+            ));
+        }
+
+        let defaults = Defaults::default().inherit(root)?;
+        let nodes: Vec<Node> = child(root, "peripherals")
+            .map(|peripherals| elements(peripherals, "peripheral").collect())
+            .unwrap_or_default();
+
+        let mut peripherals = Vec::with_capacity(nodes.len());
+        for node in &nodes {
+            let name = required_text(*node, "name")?;
+            let base_address = parse_number(&required_text(*node, "baseAddress")?)
+                .map_err(|e| svd_error(&name, e))?;
+
+            // derivedFrom 的外设沿用原外设的寄存器，只替换基地址
+            let source = match node.attribute("derivedFrom") {
+                Some(from) => *nodes
+                    .iter()
+                    .find(|other| text_of(**other, "name").as_deref() == Some(from))
+                    .ok_or_else(|| {
+                        Error::invalid_input(format!(
+                            "invalid SVD file: {name} is derived from unknown peripheral {from}"
+                        ))
+                    })?,
+                None => *node,
+            };
+            let registers = if child(*node, "registers").is_some() {
+                parse_registers(*node, defaults.inherit(*node)?)?
+            } else {
+                parse_registers(source, defaults.inherit(source)?)?
+            };
+            for (name, increment) in dim_elements(*node, &name)? {
+                peripherals.push(Peripheral {
+                    name,
+                    base_address: base_address.wrapping_add(increment),
+                    registers: registers.clone(),
+                });
+            }
+        }
+
+        Ok(Self {
+            name: text_of(root, "name").unwrap_or_default(),
+            peripherals,
+        })
+    }
+
+    pub fn peripheral(&self, name: &str) -> Option<&Peripheral> {
+        self.peripherals
+            .iter()
+            .find(|peripheral| peripheral.name.eq_ignore_ascii_case(name))
+    }
+
+    /// 解析 `PERIPH.REG[.FIELD]`
+    pub fn resolve(&self, path: &str) -> Result<RegisterRef<'_>> {
+        let parts: Vec<&str> = path.split('.').collect();
+        if !(2..=3).contains(&parts.len()) || parts.iter().any(|part| part.is_empty()) {
+            return Err(Error::invalid_input(format!(
+                "invalid register name '{path}', expected PERIPH.REG[.FIELD]"
+            )));
+        }
+
+        let peripheral = self.peripheral(parts[0]).ok_or_else(|| {
+            Error::invalid_input(format!("no peripheral named {} in the SVD file", parts[0]))
+        })?;
+        let register = peripheral
+            .registers
+            .iter()
+            .find(|register| register.name.eq_ignore_ascii_case(parts[1]))
+            .ok_or_else(|| {
+                Error::invalid_input(format!(
+                    "peripheral {} has no register named {}",
+                    peripheral.name, parts[1]
+                ))
+            })?;
+        let field = match parts.get(2) {
+            Some(name) => Some(
+                register
+                    .fields
+                    .iter()
+                    .find(|field| field.name.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| {
+                        Error::invalid_input(format!(
+                            "register {}.{} has no field named {}",
+                            peripheral.name, register.name, name
+                        ))
+                    })?,
+            ),
+            None => None,
+        };
+        Ok(RegisterRef {
+            peripheral,
+            register,
+            field,
+        })
+    }
+}
+
+/// 可从 device/peripheral 逐级继承的寄存器属性
+#[derive(Debug, Clone, Copy)]
+struct Defaults {
+    size: u32,
+    reset_value: Option<u32>,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self {
+            size: 32,
+            reset_value: None,
+        }
+    }
+}
+
+impl Defaults {
+    fn inherit(self, node: Node) -> Result<Self> {
+        let name = text_of(node, "name").unwrap_or_default();
+        let number = |tag: &str| -> Result<Option<u32>> {
+            text_of(node, tag)
+                .map(|text| parse_number(&text).map_err(|e| svd_error(&name, e)))
+                .transpose()
+        };
+        Ok(Self {
+            size: number("size")?.unwrap_or(self.size),
+            reset_value: number("resetValue")?.or(self.reset_value),
+        })
+    }
+}
+
+fn parse_registers(peripheral: Node, defaults: Defaults) -> Result<Vec<Register>> {
+    let Some(registers) = child(peripheral, "registers") else {
+        return Ok(Vec::new());
+    };
+
+    let mut result = Vec::new();
+    collect_registers(registers, defaults, "", 0, &mut result)?;
+    Ok(result)
+}
+
+/// 收集 `<registers>` 或 `<cluster>` 下的寄存器，`prefix` 和 `offset` 来自外层的 cluster
+fn collect_registers(
+    parent: Node,
+    defaults: Defaults,
+    prefix: &str,
+    offset: u32,
+    result: &mut Vec<Register>,
+) -> Result<()> {
+    for node in parent.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "register" => {
+                let register = parse_register(node, defaults, 0)?;
+                for (name, increment) in dim_elements(node, &register.name)? {
+                    result.push(Register {
+                        name: format!("{prefix}{name}"),
+                        address_offset: offset
+                            .wrapping_add(register.address_offset)
+                            .wrapping_add(increment),
+                        ..register.clone()
+                    });
+                }
+            }
+            "cluster" => {
+                let name = required_text(node, "name")?;
+                if let Some(from) = node.attribute("derivedFrom") {
+                    return Err(svd_error(
+                        &name,
+                        format!("cluster derivedFrom ({from}) is not supported"),
+                    ));
+                }
+                let cluster_offset = parse_number(&required_text(node, "addressOffset")?)
+                    .map_err(|e| svd_error(&name, e))?;
+                let defaults = defaults.inherit(node)?;
+                for (name, increment) in dim_elements(node, &name)? {
+                    collect_registers(
+                        node,
+                        defaults,
+                        &format!("{prefix}{name}_"),
+                        offset.wrapping_add(cluster_offset).wrapping_add(increment),
+                        result,
+                    )?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 解析单个寄存器（不展开 `dim`）。`derivedFrom` 指向同一层的寄存器，
+/// 没有写出的属性和位域都从原寄存器继承
+fn parse_register(node: Node, defaults: Defaults, depth: usize) -> Result<Register> {
+    let name = required_text(node, "name")?;
+    let base = match node.attribute("derivedFrom") {
+        Some(from) => {
+            if depth >= 8 {
+                return Err(svd_error(&name, "derivedFrom chain is too deep"));
+            }
+            let source = node
+                .parent()
+                .and_then(|parent| {
+                    elements(parent, "register")
+                        .find(|other| text_of(*other, "name").as_deref() == Some(from))
+                })
+                .ok_or_else(|| svd_error(&name, format!("derived from unknown register {from}")))?;
+            Some(parse_register(source, defaults, depth + 1)?)
+        }
+        None => None,
+    };
+
+    let inherited = match &base {
+        Some(base) => Defaults {
+            size: base.size,
+            reset_value: base.reset_value,
+        },
+        None => defaults,
+    }
+    .inherit(node)?;
+    let address_offset = match (text_of(node, "addressOffset"), &base) {
+        (Some(text), _) => parse_number(&text).map_err(|e| svd_error(&name, e))?,
+        (None, Some(base)) => base.address_offset,
+        (None, None) => return Err(svd_error(&name, "register has no <addressOffset>")),
+    };
+
+    let fields = match (child(node, "fields"), base.as_ref()) {
+        (Some(fields_node), _) => parse_fields(fields_node, &name)?,
+        (None, Some(base)) => base.fields.clone(),
+        (None, None) => Vec::new(),
+    };
+    let description = text_of(node, "description")
+        .map(|text| normalize(&text))
+        .or_else(|| base.and_then(|base| base.description));
+
+    Ok(Register {
+        name,
+        address_offset,
+        size: inherited.size,
+        reset_value: inherited.reset_value,
+        description,
+        fields,
+    })
+}
+
+fn parse_fields(fields_node: Node, register: &str) -> Result<Vec<Field>> {
+    let mut fields = Vec::new();
+    for node in elements(fields_node, "field") {
+        if let Some(from) = node.attribute("derivedFrom") {
+            let name = text_of(node, "name").unwrap_or_default();
+            return Err(svd_error(
+                &format!("{register}.{name}"),
+                format!("field derivedFrom ({from}) is not supported"),
+            ));
+        }
+        let field = parse_field(node, register)?;
+        for (name, increment) in dim_elements(node, &field.name)? {
+            let bit_offset = field.bit_offset.saturating_add(increment);
+            check_bits(&format!("{register}.{name}"), bit_offset, field.bit_width)?;
+            fields.push(Field {
+                name,
+                bit_offset,
+                ..field.clone()
+            });
+        }
+    }
+    fields.sort_by_key(|field| field.bit_offset);
+    Ok(fields)
+}
+
+fn parse_field(node: Node, register: &str) -> Result<Field> {
+    let name = required_text(node, "name")?;
+    let context = format!("{register}.{name}");
+    let number = |tag: &str| {
+        text_of(node, tag)
+            .map(|text| parse_number(&text).map_err(|e| svd_error(&context, e)))
+            .transpose()
+    };
+
+    let (bit_offset, bit_width) = if let Some(offset) = number("bitOffset")? {
+        (offset, number("bitWidth")?.unwrap_or(1))
+    } else if let (Some(lsb), Some(msb)) = (number("lsb")?, number("msb")?) {
+        (lsb, msb.saturating_sub(lsb) + 1)
+    } else if let Some(range) = text_of(node, "bitRange") {
+        let (msb, lsb) = range
+            .trim()
+            .strip_prefix('[')
+            .and_then(|range| range.strip_suffix(']'))
+            .and_then(|range| range.split_once(':'))
+            .ok_or_else(|| svd_error(&context, format!("invalid bitRange '{range}'")))?;
+        let msb = parse_number(msb).map_err(|e| svd_error(&context, e))?;
+        let lsb = parse_number(lsb).map_err(|e| svd_error(&context, e))?;
+        (lsb, msb.saturating_sub(lsb) + 1)
+    } else {
+        return Err(svd_error(&context, "field has no bit position"));
+    };
+    check_bits(&context, bit_offset, bit_width)?;
+
+    Ok(Field {
+        name,
+        bit_offset,
+        bit_width,
+        description: text_of(node, "description").map(|text| normalize(&text)),
+    })
+}
+
+fn check_bits(context: &str, bit_offset: u32, bit_width: u32) -> Result<()> {
+    if bit_width == 0 || bit_offset.saturating_add(bit_width) > 32 {
+        return Err(svd_error(
+            context,
+            format!("bits {bit_offset}+{bit_width} exceed 32 bits"),
+        ));
+    }
+    Ok(())
+}
+
+/// 展开 `dim` 数组，返回每个元素的名称和相对第一个元素的偏移；没有 `dim` 时原样返回
+fn dim_elements(node: Node, name: &str) -> Result<Vec<(String, u32)>> {
+    let Some(dim) = text_of(node, "dim") else {
+        return Ok(vec![(name.to_string(), 0)]);
+    };
+    let dim = parse_number(&dim).map_err(|e| svd_error(name, e))?;
+    let increment =
+        parse_number(&required_text(node, "dimIncrement")?).map_err(|e| svd_error(name, e))?;
+    let indices = match text_of(node, "dimIndex") {
+        Some(text) => parse_dim_index(&text).map_err(|e| svd_error(name, e))?,
+        None => (0..dim).map(|index| index.to_string()).collect(),
+    };
+    if indices.len() != dim as usize {
+        return Err(svd_error(
+            name,
+            format!("dimIndex has {} entries but dim is {dim}", indices.len()),
+        ));
+    }
+    if !name.contains("%s") {
+        return Err(svd_error(name, "dim array name has no %s"));
+    }
+
+    let mut result = Vec::with_capacity(indices.len());
+    for (position, index) in (0u32..).zip(&indices) {
+        let offset = position
+            .checked_mul(increment)
+            .ok_or_else(|| svd_error(name, "dim array is too large"))?;
+        result.push((name.replace("[%s]", index).replace("%s", index), offset));
+    }
+    Ok(result)
+}
+
+/// `dimIndex`：`0-3`、`A-D` 或逗号分隔的列表
+fn parse_dim_index(text: &str) -> std::result::Result<Vec<String>, String> {
+    let invalid = || format!("invalid dimIndex '{text}'");
+    if let Some((first, last)) = text.split_once('-') {
+        let (first, last) = (first.trim(), last.trim());
+        if let (Ok(first), Ok(last)) = (first.parse::<u32>(), last.parse::<u32>()) {
+            return Ok((first..=last).map(|index| index.to_string()).collect());
+        }
+        return match (first.as_bytes(), last.as_bytes()) {
+            ([first], [last]) if first.is_ascii_uppercase() && last.is_ascii_uppercase() => {
+                Ok((*first..=*last)
+                    .map(|c| char::from(c).to_string())
+                    .collect())
+            }
+            _ => Err(invalid()),
+        };
+    }
+    let indices: Vec<String> = text
+        .split(',')
+        .map(|index| index.trim().to_string())
+        .collect();
+    if indices.iter().any(|index| index.is_empty()) {
+        return Err(invalid());
+    }
+    Ok(indices)
+}
+
+/// SVD 数字：十进制、`0x` 十六进制或 `#` 二进制
+fn parse_number(text: &str) -> std::result::Result<u32, String> {
+    let text = text.trim();
+    let parsed = if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16)
+    } else if let Some(bin) = text.strip_prefix('#') {
+        u32::from_str_radix(bin, 2)
+    } else {
+        text.parse()
+    };
+    parsed.map_err(|_| format!("invalid number '{text}'"))
+}
+
+fn svd_error(context: &str, message: impl std::fmt::Display) -> Error {
+    Error::invalid_input(format!("invalid SVD file: {context}: {message}"))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag))
+}
+
+fn elements<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    tag: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| child.has_tag_name(tag))
+}
+
+fn text_of(node: Node, tag: &str) -> Option<String> {
+    child(node, tag)
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+}
+
+fn required_text(node: Node, tag: &str) -> Result<String> {
+    text_of(node, tag).ok_or_else(|| {
+        Error::invalid_input(format!(
+            "invalid SVD file: <{}> without <{}>",
+            node.tag_name().name(),
+            tag
+        ))
+    })
+}
+
+/// 把描述中的换行和连续空白压缩成一个空格
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
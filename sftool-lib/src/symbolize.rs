@@ -0,0 +1,185 @@
+//! Address symbolization for firmware log output.
+//!
+//! Firmware crash handlers usually print backtraces as raw hex addresses.
+//! This module loads the function symbols of an ELF/AXF image and rewrites
+//! such lines so every address that falls inside an executable section is
+//! followed by `<function+offset>`.
+
+use crate::Result;
+use goblin::elf::section_header::SHF_EXECINSTR;
+use goblin::elf::sym::STT_FUNC;
+use goblin::elf::{Elf, header::EM_ARM};
+use std::ops::Range;
+use std::path::Path;
+
+/// A function symbol taken from the ELF symbol table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub address: u32,
+    pub size: u32,
+}
+
+/// Result of resolving an address against the symbol table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolHit<'a> {
+    pub name: &'a str,
+    pub offset: u32,
+}
+
+/// Function symbol table restricted to the executable sections of an image.
+#[derive(Debug, Clone, Default)]
+pub struct Symbolizer {
+    text_ranges: Vec<Range<u32>>,
+    symbols: Vec<Symbol>,
+    thumb: bool,
+}
+
+impl Symbolizer {
+    /// Build a symbolizer from explicit text ranges and symbols.
+    pub fn new(text_ranges: Vec<Range<u32>>, mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by_key(|symbol| symbol.address);
+        Self {
+            text_ranges,
+            symbols,
+            thumb: false,
+        }
+    }
+
+    /// Load the function symbols of an ELF/AXF file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Load the function symbols of an in-memory ELF image.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let elf = Elf::parse(data)?;
+        // On Cortex-M the LSB of a function address is the Thumb bit, not part of the address.
+        let thumb = elf.header.e_machine == EM_ARM;
+
+        let text_ranges: Vec<Range<u32>> = elf
+            .section_headers
+            .iter()
+            .filter(|sh| sh.sh_flags & u64::from(SHF_EXECINSTR) != 0 && sh.sh_size > 0)
+            .map(|sh| sh.sh_addr as u32..(sh.sh_addr + sh.sh_size) as u32)
+            .collect();
+
+        let symbols = elf
+            .syms
+            .iter()
+            .filter(|sym| sym.st_type() == STT_FUNC && sym.st_value != 0)
+            .filter_map(|sym| {
+                let name = elf.strtab.get_at(sym.st_name)?;
+                if name.is_empty() {
+                    return None;
+                }
+                let address = if thumb {
+                    sym.st_value as u32 & !1
+                } else {
+                    sym.st_value as u32
+                };
+                Some(Symbol {
+                    name: name.to_string(),
+                    address,
+                    size: sym.st_size as u32,
+                })
+            })
+            .collect();
+
+        let mut symbolizer = Self::new(text_ranges, symbols);
+        symbolizer.thumb = thumb;
+        Ok(symbolizer)
+    }
+
+    /// Number of function symbols loaded.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Resolve an address to the function containing it.
+    ///
+    /// Only addresses inside an executable section are resolved; anything
+    /// else (RAM pointers, register values) returns `None`.
+    pub fn lookup(&self, address: u32) -> Option<SymbolHit<'_>> {
+        let address = if self.thumb { address & !1 } else { address };
+        if !self
+            .text_ranges
+            .iter()
+            .any(|range| range.contains(&address))
+        {
+            return None;
+        }
+
+        let index = self
+            .symbols
+            .partition_point(|symbol| symbol.address <= address);
+        let symbol = self.symbols.get(index.checked_sub(1)?)?;
+        let offset = address - symbol.address;
+        if symbol.size != 0 && offset >= symbol.size {
+            return None;
+        }
+
+        Some(SymbolHit {
+            name: &symbol.name,
+            offset,
+        })
+    }
+
+    /// Append `<function+0xoffset>` after every resolvable hex address in a line.
+    pub fn annotate_line(&self, line: &str) -> String {
+        let bytes = line.as_bytes();
+        let mut output = String::with_capacity(line.len());
+        let mut copied = 0usize;
+        let mut index = 0usize;
+
+        while index + 2 <= bytes.len() {
+            let is_prefix = bytes[index] == b'0'
+                && matches!(bytes[index + 1], b'x' | b'X')
+                && (index == 0 || !bytes[index - 1].is_ascii_alphanumeric());
+            if !is_prefix {
+                index += 1;
+                continue;
+            }
+
+            let digits_start = index + 2;
+            let digits_end = bytes[digits_start..]
+                .iter()
+                .position(|b| !b.is_ascii_hexdigit())
+                .map_or(bytes.len(), |pos| digits_start + pos);
+            let digit_count = digits_end - digits_start;
+            let followed_by_word = bytes
+                .get(digits_end)
+                .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_');
+
+            if (1..=8).contains(&digit_count) && !followed_by_word {
+                let value = u32::from_str_radix(&line[digits_start..digits_end], 16)
+                    .expect("at most 8 hex digits fit in u32");
+                if let Some(hit) = self.lookup(value) {
+                    output.push_str(&line[copied..digits_end]);
+                    output.push_str(&format!(" <{}+0x{:x}>", hit.name, hit.offset));
+                    copied = digits_end;
+                }
+            }
+            index = digits_end.max(index + 1);
+        }
+
+        output.push_str(&line[copied..]);
+        output
+    }
+}
+
+/// Start of the word at the end of a partial line.
+///
+/// A line flushed before its newline may end in the first half of an address
+/// (`0x2001` of `0x20012345`); callers print only the bytes before this index
+/// and keep the rest until more data arrives.
+pub fn partial_token_start(line: &[u8]) -> usize {
+    line.iter()
+        .rposition(|b| !(b.is_ascii_alphanumeric() || *b == b'_'))
+        .map_or(0, |pos| pos + 1)
+}
@@ -0,0 +1,698 @@
+//! Serial transcript recording and replay.
+//!
+//! [`RecordingSerialPort`] wraps a real port and logs every byte read or
+//! written, together with RTS/DTR and baud rate changes, to a text file.
+//! [`ReplaySerialPort`] feeds such a file back into a `SifliTool` so field
+//! failures can be reproduced without the original hardware.
+//!
+//! Transcript format, one event per line:
+//!
+//! ```text
+//! # sftool transcript v1
+//! 0.000000 RTS 0
+//! 0.101532 TX 0d0a
+//! 0.104011 RX 6d7368203e
+//! 0.250004 BAUD 3000000
+//! ```
+//!
+//! The first column is the time in seconds since the port was opened.
+//! `TX`/`RX` carry the bytes as hex, `RTS`/`DTR` the line level and `BAUD`
+//! the new rate.
+
+use crate::{Error, Result};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const TRANSCRIPT_HEADER: &str = "# sftool transcript v1";
+
+/// How the serial port of a tool is wrapped.
+#[derive(Debug, Clone)]
+pub enum TranscriptMode {
+    /// Open the real port and record all traffic to the given file.
+    Record(PathBuf),
+    /// Do not open any port; serve traffic from the given transcript.
+    ///
+    /// The tool uses a clone of the port, so the caller can keep its own
+    /// handle and check [`ReplaySerialPort::mismatches`] afterwards.
+    Replay(ReplaySerialPort),
+}
+
+/// A single transcript entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    Tx(Vec<u8>),
+    Rx(Vec<u8>),
+    Baud(u32),
+    Rts(bool),
+    Dtr(bool),
+}
+
+/// Parse transcript text into its events; timestamps are discarded.
+pub fn parse_transcript<R: BufRead>(reader: R) -> Result<Vec<TranscriptEvent>> {
+    let mut events = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = || Error::invalid_input(format!("invalid transcript line {}", index + 1));
+        let mut fields = line.split_whitespace();
+        let _timestamp = fields.next().ok_or_else(invalid)?;
+        let kind = fields.next().ok_or_else(invalid)?;
+        let value = fields.next().unwrap_or("");
+
+        let event = match kind {
+            "TX" => TranscriptEvent::Tx(decode_hex(value).ok_or_else(invalid)?),
+            "RX" => TranscriptEvent::Rx(decode_hex(value).ok_or_else(invalid)?),
+            "BAUD" => TranscriptEvent::Baud(value.parse()?),
+            "RTS" => TranscriptEvent::Rts(parse_level(value).ok_or_else(invalid)?),
+            "DTR" => TranscriptEvent::Dtr(parse_level(value).ok_or_else(invalid)?),
+            _ => return Err(invalid()),
+        };
+        events.push(event);
+    }
+    Ok(events)
+}
+
+fn parse_level(value: &str) -> Option<bool> {
+    match value {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(out, "{:02x}", byte);
+    }
+    out
+}
+
+struct TranscriptWriter {
+    out: Box<dyn Write + Send>,
+    start: Instant,
+}
+
+impl TranscriptWriter {
+    fn log(&mut self, kind: &str, value: &str) {
+        let elapsed = self.start.elapsed();
+        // 录制失败不应影响实际的烧录流程
+        let _ = writeln!(
+            self.out,
+            "{}.{:06} {} {}",
+            elapsed.as_secs(),
+            elapsed.subsec_micros(),
+            kind,
+            value
+        );
+        let _ = self.out.flush();
+    }
+}
+
+/// Serial port wrapper that records all traffic to a transcript.
+pub struct RecordingSerialPort {
+    inner: Box<dyn SerialPort>,
+    writer: Arc<Mutex<TranscriptWriter>>,
+}
+
+impl RecordingSerialPort {
+    /// Wrap `inner`, writing the transcript to a newly created file.
+    pub fn create<P: AsRef<Path>>(inner: Box<dyn SerialPort>, path: P) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(inner, Box::new(BufWriter::new(file))))
+    }
+
+    /// Wrap `inner`, writing the transcript to an arbitrary sink.
+    pub fn new(inner: Box<dyn SerialPort>, out: Box<dyn Write + Send>) -> Self {
+        let mut writer = TranscriptWriter {
+            out,
+            start: Instant::now(),
+        };
+        let _ = writeln!(writer.out, "{}", TRANSCRIPT_HEADER);
+        Self {
+            inner,
+            writer: Arc::new(Mutex::new(writer)),
+        }
+    }
+
+    fn log(&self, kind: &str, value: &str) {
+        if let Ok(mut writer) = self.writer.lock() {
+            writer.log(kind, value);
+        }
+    }
+}
+
+impl Read for RecordingSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        if count > 0 {
+            self.log("RX", &encode_hex(&buf[..count]));
+        }
+        Ok(count)
+    }
+}
+
+impl Write for RecordingSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.inner.write(buf)?;
+        if count > 0 {
+            self.log("TX", &encode_hex(&buf[..count]));
+        }
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl SerialPort for RecordingSerialPort {
+    fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        self.inner.baud_rate()
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        self.inner.data_bits()
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        self.inner.flow_control()
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        self.inner.parity()
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        self.inner.stop_bits()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.inner.timeout()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.inner.set_baud_rate(baud_rate)?;
+        self.log("BAUD", &baud_rate.to_string());
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        self.inner.set_data_bits(data_bits)
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        self.inner.set_flow_control(flow_control)
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        self.inner.set_parity(parity)
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        self.inner.set_stop_bits(stop_bits)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.inner.write_request_to_send(level)?;
+        self.log("RTS", if level { "1" } else { "0" });
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.inner.write_data_terminal_ready(level)?;
+        self.log("DTR", if level { "1" } else { "0" });
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        self.inner.read_clear_to_send()
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        self.inner.read_data_set_ready()
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        self.inner.read_ring_indicator()
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        self.inner.read_carrier_detect()
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        self.inner.bytes_to_read()
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        self.inner.bytes_to_write()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        self.inner.clear(buffer_to_clear)
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(Self {
+            inner: self.inner.try_clone()?,
+            writer: self.writer.clone(),
+        }))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        self.inner.set_break()
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        self.inner.clear_break()
+    }
+}
+
+struct ReplayState {
+    events: VecDeque<TranscriptEvent>,
+    /// 已经"到达"但尚未被读取的接收数据
+    rx_ready: VecDeque<u8>,
+    /// 当前 TX 事件中已匹配的字节数
+    tx_offset: usize,
+    baud_rate: u32,
+    timeout: Duration,
+    mismatches: usize,
+}
+
+impl ReplayState {
+    /// Release all RX events up to the next host-side action.
+    ///
+    /// Received bytes only become readable once every TX/control event
+    /// preceding them in the transcript has been performed by the tool,
+    /// which keeps request/response ordering identical to the recording.
+    fn advance(&mut self) {
+        while let Some(TranscriptEvent::Rx(bytes)) = self.events.front() {
+            self.rx_ready.extend(bytes.iter().copied());
+            self.events.pop_front();
+        }
+    }
+
+    fn consume_write(&mut self, buf: &[u8]) {
+        let mut overrun = false;
+        for &byte in buf {
+            self.advance();
+            // 跳过工具未执行的控制事件
+            while matches!(
+                self.events.front(),
+                Some(TranscriptEvent::Baud(_) | TranscriptEvent::Rts(_) | TranscriptEvent::Dtr(_))
+            ) {
+                self.mismatches += 1;
+                self.events.pop_front();
+                self.advance();
+            }
+
+            let Some(TranscriptEvent::Tx(expected)) = self.events.front() else {
+                // 录制已结束，多出的写入整体算一次不匹配
+                overrun = true;
+                break;
+            };
+            if expected[self.tx_offset] != byte {
+                self.mismatches += 1;
+            }
+            self.tx_offset += 1;
+            if self.tx_offset == expected.len() {
+                self.tx_offset = 0;
+                self.events.pop_front();
+            }
+        }
+        if overrun {
+            self.mismatches += 1;
+        }
+        self.advance();
+    }
+
+    fn consume_control(&mut self, event: TranscriptEvent) {
+        self.advance();
+        if self.tx_offset == 0
+            && let Some(front) = self.events.front()
+            && std::mem::discriminant(front) == std::mem::discriminant(&event)
+        {
+            if *front != event {
+                self.mismatches += 1;
+            }
+            self.events.pop_front();
+            self.advance();
+        } else {
+            // 录制中此处没有同类控制事件（或录制已结束）
+            self.mismatches += 1;
+        }
+    }
+}
+
+/// Serial port that replays a recorded transcript.
+///
+/// Writes are matched against the recorded `TX` bytes; divergences are
+/// counted (see [`ReplaySerialPort::mismatches`]) rather than rejected so a
+/// replay can still run to the point where behavior differs. Writes after
+/// the transcript ends and control changes the recording did not make at
+/// that point count as mismatches too.
+///
+/// Clones share the replay position.
+#[derive(Clone)]
+pub struct ReplaySerialPort {
+    state: Arc<Mutex<ReplayState>>,
+}
+
+impl std::fmt::Debug for ReplaySerialPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("ReplaySerialPort")
+            .field("remaining_events", &state.events.len())
+            .field("mismatches", &state.mismatches)
+            .finish()
+    }
+}
+
+impl ReplaySerialPort {
+    /// Load a transcript file recorded with [`RecordingSerialPort`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        let events = parse_transcript(BufReader::new(file))?;
+        Ok(Self::from_events(events))
+    }
+
+    pub fn from_events(events: Vec<TranscriptEvent>) -> Self {
+        let mut state = ReplayState {
+            events: events.into(),
+            rx_ready: VecDeque::new(),
+            tx_offset: 0,
+            baud_rate: 1_000_000,
+            timeout: Duration::from_secs(5),
+            mismatches: 0,
+        };
+        state.advance();
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Number of host actions that did not match the transcript so far.
+    pub fn mismatches(&self) -> usize {
+        self.state.lock().unwrap().mismatches
+    }
+
+    /// Whether every recorded event has been consumed.
+    pub fn is_finished(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.events.is_empty() && state.rx_ready.is_empty()
+    }
+}
+
+impl Read for ReplaySerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timeout = {
+            let mut state = self.state.lock().unwrap();
+            state.advance();
+            if !state.rx_ready.is_empty() {
+                let count = buf.len().min(state.rx_ready.len());
+                for slot in buf.iter_mut().take(count) {
+                    *slot = state.rx_ready.pop_front().unwrap();
+                }
+                return Ok(count);
+            }
+            state.timeout
+        };
+        // 没有可读数据时短暂等待，避免调用方忙等
+        std::thread::sleep(timeout.min(Duration::from_millis(1)));
+        Err(io::Error::new(ErrorKind::TimedOut, "no data in transcript"))
+    }
+}
+
+impl Write for ReplaySerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.state.lock().unwrap().consume_write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for ReplaySerialPort {
+    fn name(&self) -> Option<String> {
+        Some("replay".to_string())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.state.lock().unwrap().baud_rate)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.state.lock().unwrap().timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.baud_rate = baud_rate;
+        state.consume_control(TranscriptEvent::Baud(baud_rate));
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.state.lock().unwrap().timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .consume_control(TranscriptEvent::Rts(level));
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .consume_control(TranscriptEvent::Dtr(level));
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let mut state = self.state.lock().unwrap();
+        state.advance();
+        Ok(state.rx_ready.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, _: ClearBuffer) -> serialport::Result<()> {
+        // 录制中只包含实际读到的数据，因此回放时不丢弃任何内容
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(Self {
+            state: self.state.clone(),
+        }))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CancelToken;
+    use crate::common::serial_io::{SerialIo, test_support::TestSerialPort};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn session(port: &mut dyn SerialPort) -> Vec<u8> {
+        port.write_request_to_send(false).unwrap();
+        port.write_all(b"burn_verify 0x12000000 0x100 0x1234\r\n")
+            .unwrap();
+        let mut io = SerialIo::new(port, CancelToken::new());
+        let response = io
+            .wait_for_pattern(b"OK", Duration::from_millis(100), "OK response")
+            .unwrap();
+        io.set_baud_rate(3_000_000).unwrap();
+        response
+    }
+
+    #[test]
+    fn recorded_session_replays_identically() {
+        let (test_port, _) = TestSerialPort::from_bytes(b"burn_verify\r\nOK\r\n");
+        let sink = SharedBuffer::default();
+        let mut recorder = RecordingSerialPort::new(Box::new(test_port), Box::new(sink.clone()));
+        let recorded = session(&mut recorder);
+
+        let text = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+        assert!(text.starts_with(TRANSCRIPT_HEADER));
+        let events = parse_transcript(text.as_bytes()).unwrap();
+        assert_eq!(events[0], TranscriptEvent::Rts(false));
+        assert_eq!(events.last(), Some(&TranscriptEvent::Baud(3_000_000)));
+
+        let mut replay = ReplaySerialPort::from_events(events);
+        let replayed = session(&mut replay);
+
+        assert_eq!(replayed, recorded);
+        assert_eq!(replay.mismatches(), 0);
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn replay_withholds_response_until_request_is_sent() {
+        let mut replay = ReplaySerialPort::from_events(vec![
+            TranscriptEvent::Tx(b"burn_erase_all\r\n".to_vec()),
+            TranscriptEvent::Rx(b"OK".to_vec()),
+        ]);
+        let mut buffer = [0u8; 8];
+
+        let error = replay.read(&mut buffer).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+
+        replay.write_all(b"burn_erase_all\r\n").unwrap();
+        let count = replay.read(&mut buffer).unwrap();
+        assert_eq!(&buffer[..count], b"OK");
+    }
+
+    #[test]
+    fn replay_counts_diverging_writes() {
+        let mut replay =
+            ReplaySerialPort::from_events(vec![TranscriptEvent::Tx(b"burn_verify".to_vec())]);
+
+        replay.write_all(b"burn_erase ").unwrap();
+
+        assert!(replay.mismatches() > 0);
+    }
+
+    #[test]
+    fn replay_counts_writes_after_the_transcript_ends() {
+        let mut replay =
+            ReplaySerialPort::from_events(vec![TranscriptEvent::Tx(b"burn_verify".to_vec())]);
+
+        replay.write_all(b"burn_verify").unwrap();
+        assert_eq!(replay.mismatches(), 0);
+        assert!(replay.is_finished());
+
+        replay.write_all(b"burn_erase_all\r\n").unwrap();
+        assert_eq!(replay.mismatches(), 1);
+    }
+
+    #[test]
+    fn replay_counts_unexpected_control_events() {
+        let mut replay = ReplaySerialPort::from_events(vec![
+            TranscriptEvent::Tx(b"burn_reset\r\n".to_vec()),
+            TranscriptEvent::Rx(b"OK".to_vec()),
+        ]);
+
+        // A reset the recording never did, while a TX is still expected
+        replay.write_request_to_send(true).unwrap();
+        assert_eq!(replay.mismatches(), 1);
+
+        replay.write_all(b"burn_reset\r\n").unwrap();
+        let mut buffer = [0u8; 2];
+        replay.read_exact(&mut buffer).unwrap();
+        assert!(replay.is_finished());
+
+        // Control events after the transcript ends count as well
+        replay.write_data_terminal_ready(false).unwrap();
+        replay.set_baud_rate(3_000_000).unwrap();
+        assert_eq!(replay.mismatches(), 3);
+    }
+}
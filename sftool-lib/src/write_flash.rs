@@ -1,5 +1,7 @@
-use crate::{Result, WriteFlashParams};
+use crate::{Result, VerifyParams, VerifyResult, WriteFlashParams};
 
 pub trait WriteFlashTrait {
     fn write_flash(&mut self, params: &WriteFlashParams) -> Result<()>;
+    /// 只校验不写入，返回每个文件段的校验结果
+    fn verify_flash(&mut self, params: &VerifyParams) -> Result<Vec<VerifyResult>>;
 }
@@ -0,0 +1,138 @@
+use sftool_lib::common::ram_command::DownloadStub;
+use sftool_lib::dfu_image::{
+    DFU_BLOCK_SIZE, DFU_CHUNK_SIZE, DFU_HEADER_SIZE, DFU_LENGTH_KEY, DFU_LENGTH_OFFSET, DfuImage,
+};
+use sftool_lib::simulator::Simulator;
+use sftool_lib::{BeforeOperation, ChipType, SifliToolBase, list_embedded_stubs};
+use std::io::Write;
+
+fn make_image(payload: usize) -> Vec<u8> {
+    let mut image = vec![0xA5; DFU_HEADER_SIZE];
+    image[DFU_LENGTH_OFFSET..DFU_LENGTH_OFFSET + 4]
+        .copy_from_slice(&(payload as u32 ^ DFU_LENGTH_KEY).to_le_bytes());
+    for (index, block) in vec![0x5A; payload].chunks(DFU_BLOCK_SIZE).enumerate() {
+        image.extend_from_slice(&[0xEE; 32]);
+        image.extend_from_slice(&((index * DFU_BLOCK_SIZE) as u32).to_le_bytes());
+        image.extend_from_slice(block);
+    }
+    image
+}
+
+#[test]
+fn embedded_dfu_stubs_parse() {
+    let stubs: Vec<_> = list_embedded_stubs()
+        .into_iter()
+        .filter(|stub| {
+            let name = stub.file_name.to_ascii_lowercase();
+            // The `_ori` files are plain binaries that no memory type loads
+            (name.starts_with("ram_patch_55x") || name.starts_with("ram_patch_58x"))
+                && !name.contains("_ori")
+        })
+        .collect();
+    assert!(!stubs.is_empty());
+    for stub in stubs {
+        let data = sftool_lib::load_embedded_stub(&stub.file_name).unwrap();
+        let image = DfuImage::parse(&data).unwrap_or_else(|e| panic!("{}: {e}", stub.file_name));
+        assert_eq!(image.header().len(), DFU_HEADER_SIZE);
+        assert!(image.declared_size_matches());
+        assert_eq!(
+            image.chunks().map(<[u8]>::len).sum::<usize>() + DFU_HEADER_SIZE,
+            data.len()
+        );
+    }
+}
+
+#[test]
+fn layout_is_reported() {
+    let data = make_image(3 * DFU_BLOCK_SIZE + 100);
+    let image = DfuImage::parse(&data).unwrap();
+    assert_eq!(image.chunk_count(), 4);
+    assert_eq!(image.last_block_size(), 100);
+    assert_eq!(image.payload_size(), 3 * DFU_BLOCK_SIZE + 100);
+}
+
+#[test]
+fn corrupt_images_are_rejected() {
+    let err = DfuImage::parse(&[0; DFU_HEADER_SIZE]).unwrap_err();
+    assert!(err.to_string().contains("header"), "{err}");
+
+    // Truncated inside the last chunk header
+    let mut data = make_image(2 * DFU_BLOCK_SIZE);
+    data.truncate(DFU_HEADER_SIZE + DFU_CHUNK_SIZE + 20);
+    let err = DfuImage::parse(&data).unwrap_err();
+    assert!(err.to_string().contains("chunk 1"), "{err}");
+
+    // A dropped byte shifts every following chunk
+    let mut data = make_image(4 * DFU_BLOCK_SIZE);
+    data.remove(DFU_HEADER_SIZE + 100);
+    let err = DfuImage::parse(&data).unwrap_err();
+    assert!(err.to_string().contains("chunk 1 has offset"), "{err}");
+
+    // Chunks dropped at a block boundary keep every offset valid; only the
+    // header notices, and its length key is not known for every signer
+    let mut data = make_image(4 * DFU_BLOCK_SIZE);
+    data.truncate(DFU_HEADER_SIZE + 3 * DFU_CHUNK_SIZE);
+    let image = DfuImage::parse(&data).unwrap();
+    assert!(!image.declared_size_matches());
+    assert_eq!(image.declared_payload_size(), 4 * DFU_BLOCK_SIZE);
+    assert_eq!(image.payload_size(), 3 * DFU_BLOCK_SIZE);
+
+    // A plain firmware binary is not a DFU image
+    let raw = sftool_lib::load_embedded_stub("sf32lb52_nor").unwrap();
+    assert!(DfuImage::parse(&raw).is_err());
+}
+
+#[test]
+fn corrupt_external_stub_fails_before_dfu() {
+    let mut data = make_image(2 * DFU_BLOCK_SIZE);
+    data.truncate(data.len() - DFU_BLOCK_SIZE - 10);
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(&data).unwrap();
+
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    let mut base = SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::DefaultReset,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    );
+    base.external_stub_path = Some(file.path().to_string_lossy().to_string());
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base,
+        port: simulator.port(),
+    };
+
+    assert!(tool.download_stub().is_err());
+    assert!(simulator.sig_pub_key().is_empty());
+    assert!(simulator.dfu_image().is_empty());
+}
+
+#[test]
+fn external_stub_with_another_length_key_is_downloaded() {
+    // Signed with a header key other than the bundled stubs' one
+    let mut data = make_image(2 * DFU_BLOCK_SIZE + 40);
+    data[DFU_LENGTH_OFFSET..DFU_LENGTH_OFFSET + 4].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+    assert!(!DfuImage::parse(&data).unwrap().declared_size_matches());
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(&data).unwrap();
+
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    let mut base = SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::DefaultReset,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    );
+    base.external_stub_path = Some(file.path().to_string_lossy().to_string());
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base,
+        port: simulator.port(),
+    };
+
+    tool.download_stub().unwrap();
+    assert_eq!(simulator.dfu_image(), data);
+}
@@ -0,0 +1,132 @@
+use sftool_lib::dfu_image::{DFU_CHUNK_SIZE, DFU_HEADER_SIZE};
+use sftool_lib::dfu_write::dfu_write;
+use sftool_lib::progress::{ProgressEvent, ProgressOperation, ProgressSink};
+use sftool_lib::simulator::{Simulator, SimulatorMode};
+use sftool_lib::{BeforeOperation, ChipType, DfuWriteFile, DfuWriteParams, Error, SifliToolBase};
+use std::sync::{Arc, Mutex};
+
+struct RecordingSink {
+    events: Mutex<Vec<ProgressEvent>>,
+}
+
+impl ProgressSink for RecordingSink {
+    fn on_event(&self, event: ProgressEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+fn make_base(sink: Arc<RecordingSink>) -> SifliToolBase {
+    SifliToolBase::new_with_progress(
+        "simulator".to_string(),
+        BeforeOperation::NoResetNoSync,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+        sink,
+    )
+}
+
+fn signed_image() -> Vec<u8> {
+    sftool_lib::load_embedded_stub("sf32lb58_nor").unwrap()
+}
+
+#[test]
+fn dfu_write_sends_image_through_rom() {
+    let sink = Arc::new(RecordingSink {
+        events: Mutex::new(Vec::new()),
+    });
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base: make_base(sink.clone()),
+        port: simulator.port(),
+    };
+
+    let image = signed_image();
+    dfu_write(
+        &mut tool,
+        &DfuWriteParams {
+            files: vec![DfuWriteFile {
+                flash_id: 0,
+                data: image.clone(),
+            }],
+        },
+    )
+    .unwrap();
+
+    // Flash id 0 is not the RAM stub, so the ROM keeps running
+    assert_eq!(simulator.mode(), SimulatorMode::Rom);
+    assert_eq!(simulator.dfu_image(), image);
+
+    let events = sink.events.lock().unwrap();
+    assert!(events.iter().any(|event| matches!(
+        event,
+        ProgressEvent::Start { ctx, .. }
+            if matches!(ctx.operation, ProgressOperation::DfuWrite { flash_id: 0, .. })
+    )));
+    let advanced: u64 = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::Advance { delta, .. } => Some(*delta),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(advanced, image.len() as u64);
+}
+
+#[test]
+fn dfu_write_checks_every_image_first() {
+    let sink = Arc::new(RecordingSink {
+        events: Mutex::new(Vec::new()),
+    });
+    let simulator = Simulator::new(ChipType::SF32LB55);
+    let mut tool = sftool_lib::sf32lb55::SF32LB55Tool {
+        base: make_base(sink),
+        port: simulator.port(),
+    };
+
+    // The first image is fine, the second one is cut off inside a chunk header
+    let mut corrupt = signed_image();
+    let last_chunk = (corrupt.len() - DFU_HEADER_SIZE) % DFU_CHUNK_SIZE;
+    corrupt.truncate(corrupt.len() - last_chunk + 20);
+    let result = dfu_write(
+        &mut tool,
+        &DfuWriteParams {
+            files: vec![
+                DfuWriteFile {
+                    flash_id: 0,
+                    data: signed_image(),
+                },
+                DfuWriteFile {
+                    flash_id: 1,
+                    data: corrupt,
+                },
+            ],
+        },
+    );
+    assert!(matches!(result, Err(Error::InvalidInput { .. })));
+    assert!(simulator.dfu_image().is_empty());
+}
+
+#[test]
+fn dfu_write_is_rejected_on_stub_only_chips() {
+    let sink = Arc::new(RecordingSink {
+        events: Mutex::new(Vec::new()),
+    });
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(sink),
+        port: simulator.port(),
+    };
+
+    let result = dfu_write(
+        &mut tool,
+        &DfuWriteParams {
+            files: vec![DfuWriteFile {
+                flash_id: 0,
+                data: signed_image(),
+            }],
+        },
+    );
+    assert!(matches!(result, Err(Error::UnsupportedChip(_))));
+}
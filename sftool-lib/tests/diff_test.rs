@@ -0,0 +1,78 @@
+use sftool_lib::diff::{DiffRange, compare};
+
+#[test]
+fn identical_data_has_no_differences() {
+    let data = vec![0x5Au8; 0x2000];
+    let report = compare(0x1202_0000, &data, &data);
+    assert!(report.is_identical());
+    assert!(report.ranges.is_empty());
+    assert!(report.sectors.is_empty());
+}
+
+#[test]
+fn differences_are_grouped_into_ranges_and_sectors() {
+    let expected = vec![0u8; 0x3000];
+    let mut actual = expected.clone();
+    actual[0x10..0x14].fill(0xFF);
+    actual[0x20] = 1;
+    actual[0x2FFF] = 2;
+
+    let report = compare(0x1202_0000, &expected, &actual);
+    assert_eq!(report.differing_bytes, 6);
+    assert_eq!(
+        report.ranges,
+        vec![
+            DiffRange {
+                address: 0x1202_0010,
+                len: 4
+            },
+            DiffRange {
+                address: 0x1202_0020,
+                len: 1
+            },
+            DiffRange {
+                address: 0x1202_2FFF,
+                len: 1
+            },
+        ]
+    );
+    assert_eq!(report.sectors, vec![0x1202_0000, 0x1202_2000]);
+}
+
+#[test]
+fn contiguous_sectors_are_merged() {
+    let expected = vec![0u8; 0x6000];
+    let mut actual = expected.clone();
+    actual[..0x3000].fill(0xFF);
+    actual[0x5000] = 1;
+
+    let report = compare(0x1202_0000, &expected, &actual);
+    assert_eq!(report.sectors.len(), 4);
+    assert_eq!(
+        report.sector_ranges(),
+        vec![
+            DiffRange {
+                address: 0x1202_0000,
+                len: 0x3000
+            },
+            DiffRange {
+                address: 0x1202_5000,
+                len: 0x1000
+            },
+        ]
+    );
+}
+
+#[test]
+fn short_read_back_counts_missing_bytes() {
+    let report = compare(0x1000, &[1, 2, 3, 4], &[1, 2]);
+    assert_eq!(report.len, 4);
+    assert_eq!(report.differing_bytes, 2);
+    assert_eq!(
+        report.ranges,
+        vec![DiffRange {
+            address: 0x1002,
+            len: 2
+        }]
+    );
+}
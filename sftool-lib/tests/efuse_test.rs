@@ -0,0 +1,152 @@
+use sftool_lib::efuse::{self, EFUSE_BANK_COUNT, EFUSE_BANK_SIZE};
+use sftool_lib::simulator::Simulator;
+use sftool_lib::{BeforeOperation, ChipType, Error, SifliToolBase};
+
+const EFUSEC_BANK0_DATA_52: u32 = 0x5000_C030;
+const LDO_REG_52: u32 = 0x500C_A094;
+
+fn make_base() -> SifliToolBase {
+    SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::NoResetNoSync,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    )
+}
+
+fn fill_banks(simulator: &Simulator, bank0_data: u32) {
+    for bank in 0..EFUSE_BANK_COUNT {
+        let data: Vec<u8> = (0..EFUSE_BANK_SIZE)
+            .map(|index| (bank * EFUSE_BANK_SIZE + index) as u8)
+            .collect();
+        simulator.write_memory(bank0_data + (bank * EFUSE_BANK_SIZE) as u32, &data);
+    }
+}
+
+#[test]
+fn efuse_read_returns_banks_and_uid() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    fill_banks(&simulator, EFUSEC_BANK0_DATA_52);
+    // Voltage field (bits 0..3) at 10, below the range the read raises it to
+    simulator.write_memory(LDO_REG_52, &0x1234_560Au32.to_le_bytes());
+
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    let data = efuse::read_efuse(&mut tool).unwrap();
+
+    let uid: Vec<u8> = (0..16).collect();
+    assert_eq!(data.uid().as_slice(), uid.as_slice());
+    assert_eq!(data.banks[3][0], 3 * EFUSE_BANK_SIZE as u8);
+    assert_eq!(data.banks[3][31], 4 * EFUSE_BANK_SIZE as u8 - 1);
+    assert_eq!(
+        simulator.read_memory(LDO_REG_52, 4),
+        0x1234_560Au32.to_le_bytes()
+    );
+}
+
+#[test]
+fn efuse_read_on_sf32lb57_uses_its_own_controller() {
+    let simulator = Simulator::new(ChipType::SF32LB57);
+    fill_banks(&simulator, 0x5000_C030);
+
+    let mut tool = sftool_lib::sf32lb57::SF32LB57Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    let data = efuse::read_efuse(&mut tool).unwrap();
+
+    let uid: Vec<u8> = (0..16).collect();
+    assert_eq!(data.uid().as_slice(), uid.as_slice());
+    assert_eq!(data.banks[2][5], 2 * EFUSE_BANK_SIZE as u8 + 5);
+}
+
+#[test]
+fn efuse_read_is_rejected_on_dfu_chips() {
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    assert!(matches!(
+        efuse::read_efuse(&mut tool),
+        Err(Error::UnsupportedChip(_))
+    ));
+}
+
+/// Pack `(value, bits)` fields LSB first, the way the stubs unpack bank 1
+fn pack_bits(fields: &[(u32, usize)]) -> [u8; EFUSE_BANK_SIZE] {
+    let mut bank = [0u8; EFUSE_BANK_SIZE];
+    let mut bit = 0;
+    for &(value, len) in fields {
+        for index in 0..len {
+            if value >> index & 1 != 0 {
+                bank[(bit + index) / 8] |= 1 << ((bit + index) % 8);
+            }
+        }
+        bit += len;
+    }
+    bank
+}
+
+#[test]
+fn sf32lb52_adc_calibration_is_decoded_from_bank1() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    fill_banks(&simulator, EFUSEC_BANK0_DATA_52);
+    let bank1 = pack_bits(&[
+        (0xA5, 32),
+        (1862, 12),
+        (10, 5),
+        (3139, 12),
+        (25, 5),
+        (2950, 12),
+        (42, 6),
+    ]);
+    simulator.write_memory(EFUSEC_BANK0_DATA_52 + EFUSE_BANK_SIZE as u32, &bank1);
+
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    let data = efuse::read_efuse(&mut tool).unwrap();
+
+    assert_eq!(
+        data.adc_calibration(),
+        Some(efuse::AdcCalibration {
+            vol10: 1862,
+            low_mv: 1000,
+            vol25: 3139,
+            high_mv: 2500,
+            vbat_reg: 2950,
+            vbat_mv: 4200,
+        })
+    );
+    assert_eq!(data.pid(), None);
+
+    // A blank bank 1 carries no calibration
+    let mut blank = data.clone();
+    blank.banks[1] = [0; EFUSE_BANK_SIZE];
+    assert_eq!(blank.adc_calibration(), None);
+}
+
+#[test]
+fn sf32lb57_pid_spans_two_bytes() {
+    let simulator = Simulator::new(ChipType::SF32LB57);
+    let mut bank0 = [0u8; EFUSE_BANK_SIZE];
+    // Bits 228..235: high nibble of byte 28, low nibble of byte 29
+    bank0[28] = 0x90;
+    bank0[29] = 0x02;
+    simulator.write_memory(0x5000_C030, &bank0);
+
+    let mut tool = sftool_lib::sf32lb57::SF32LB57Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    let data = efuse::read_efuse(&mut tool).unwrap();
+
+    assert_eq!(data.pid(), Some(0x29));
+    assert_eq!(data.adc_calibration(), None);
+}
@@ -0,0 +1,54 @@
+use sftool_lib::stub_config::read_stub_config_from_bytes;
+use sftool_lib::{ChipType, list_embedded_stubs, load_embedded_stub, load_stub_bytes};
+
+#[test]
+fn list_embedded_stubs_puts_keyed_images_first() {
+    let stubs = list_embedded_stubs();
+    let nand = stubs
+        .iter()
+        .find(|stub| stub.key == Some("sf32lb52_nand"))
+        .expect("sf32lb52_nand is embedded");
+    assert_eq!(nand.file_name, "ram_patch_52X_NAND.bin");
+    assert!(nand.has_driver_config);
+    assert!(nand.size > 0);
+
+    let first_unkeyed = stubs.iter().position(|stub| stub.key.is_none());
+    if let Some(first_unkeyed) = first_unkeyed {
+        assert!(stubs[first_unkeyed..].iter().all(|stub| stub.key.is_none()));
+    }
+    assert!(stubs.iter().all(|stub| stub.file_name.ends_with(".bin")));
+}
+
+#[test]
+fn load_embedded_stub_by_key_or_file_name() {
+    let by_key = load_embedded_stub("SF32LB52_NAND").expect("load by key");
+    let by_name = load_embedded_stub("ram_patch_52X_NAND.bin").expect("load by file name");
+    assert_eq!(by_key, by_name);
+    read_stub_config_from_bytes(&by_key).expect("embedded stub has a driver config block");
+
+    assert!(load_embedded_stub("58X_sig_pub.der").is_err());
+    assert!(load_embedded_stub("sf32lb99_nor").is_err());
+}
+
+#[test]
+fn nand_variants_map_to_embedded_stubs() {
+    let cases = [
+        (ChipType::SF32LB52, "nand_6m", "ram_patch_52X_NAND_6M.bin"),
+        (ChipType::SF32LB52, "nand_8m", "ram_patch_52X_NAND_8M.bin"),
+        (
+            ChipType::SF32LB56,
+            "nand_nobbm",
+            "ram_patch_56X_NAND_NOBBM.bin",
+        ),
+        (ChipType::SF32LB58, "NAND_8M", "ram_patch_58X_NAND_8M.bin"),
+    ];
+    for (chip, memory, file_name) in cases {
+        let data = load_stub_bytes(None, chip.clone(), memory)
+            .unwrap_or_else(|e| panic!("{chip:?} {memory}: {e}"));
+        assert_eq!(data, load_embedded_stub(file_name).unwrap(), "{memory}");
+    }
+
+    assert!(load_stub_bytes(None, ChipType::SF32LB57, "nand_6m").is_err());
+    // The unsigned `_ori` stubs cannot be sent over DFU, so no memory type maps to them
+    assert!(load_stub_bytes(None, ChipType::SF32LB58, "nor_ori").is_err());
+}
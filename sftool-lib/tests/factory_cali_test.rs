@@ -0,0 +1,183 @@
+use sftool_lib::factory_cali::*;
+use sftool_lib::sf32lb52::SF32LB52Tool;
+use sftool_lib::sf32lb56::SF32LB56Tool;
+use sftool_lib::simulator::{Simulator, SimulatorMode};
+use sftool_lib::{BeforeOperation, ChipType, SifliToolBase};
+
+const CRYSTAL: CaliStep = CaliStep::Crystal {
+    pin: 8,
+    ppm: 5,
+    timeout_s: 20,
+};
+
+const BATTERY: CaliStep = CaliStep::Battery {
+    reference_mv: 4000,
+    range_mv: 400,
+    tolerance_mv: 10,
+    upper_kohm: 1000,
+    lower_kohm: 220,
+};
+
+fn make_base() -> SifliToolBase {
+    SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::NoResetNoSync,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    )
+}
+
+#[test]
+fn calibration_images_are_loadable_on_supported_chips() {
+    for chip in [ChipType::SF32LB52, ChipType::SF32LB56] {
+        let image = load_factory_cali_image(&chip).unwrap();
+        check_image_loadable(&image, FACTORY_CALI_LOAD_ADDRESS).unwrap();
+    }
+    for chip in [ChipType::SF32LB55, ChipType::SF32LB57, ChipType::SF32LB58] {
+        assert!(load_factory_cali_image(&chip).is_err(), "{chip:?}");
+    }
+}
+
+#[test]
+fn reset_vector_outside_the_image_is_rejected() {
+    // Written where the RAM stubs go, the reset vector points below the image
+    let image = load_factory_cali_image(&ChipType::SF32LB52).unwrap();
+    assert!(check_image_loadable(&image, 0x2005_A000).is_err());
+    let image = load_factory_cali_image(&ChipType::SF32LB56).unwrap();
+    assert!(check_image_loadable(&image, 0x2006_7000).is_err());
+
+    let mut image = vec![0; 0x100];
+    image[..4].copy_from_slice(&0x2001_0000u32.to_le_bytes());
+    image[4..8].copy_from_slice(&0x2000_0081u32.to_le_bytes());
+    check_image_loadable(&image, 0x2000_0000).unwrap();
+    assert!(check_image_loadable(&image, 0x2000_0200).is_err());
+    // Reset vector just past the end
+    image[4..8].copy_from_slice(&0x2000_0101u32.to_le_bytes());
+    assert!(check_image_loadable(&image, 0x2000_0000).is_err());
+}
+
+#[test]
+fn calibration_image_runs_from_its_link_address() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    start_factory_cali(&mut tool, &ChipType::SF32LB52).unwrap();
+
+    let image = load_factory_cali_image(&ChipType::SF32LB52).unwrap();
+    assert_eq!(
+        simulator.read_memory(FACTORY_CALI_LOAD_ADDRESS, image.len()),
+        image
+    );
+    let written = FACTORY_CALI_LOAD_ADDRESS..FACTORY_CALI_LOAD_ADDRESS + image.len() as u32;
+    let pc = simulator.pc().unwrap();
+    assert!(written.contains(&(pc & !1)), "PC 0x{pc:08X}");
+    assert_eq!(pc, u32::from_le_bytes(image[4..8].try_into().unwrap()));
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+
+    let simulator = Simulator::new(ChipType::SF32LB56);
+    let mut tool = SF32LB56Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    start_factory_cali(&mut tool, &ChipType::SF32LB56).unwrap();
+
+    // The SF32LB56 debug bus sees H_RAM at +0x0A00_0000
+    let image = load_factory_cali_image(&ChipType::SF32LB56).unwrap();
+    assert_eq!(
+        simulator.read_memory(FACTORY_CALI_LOAD_ADDRESS + 0x0A00_0000, image.len()),
+        image
+    );
+    let written = FACTORY_CALI_LOAD_ADDRESS..FACTORY_CALI_LOAD_ADDRESS + image.len() as u32;
+    let pc = simulator.pc().unwrap();
+    assert!(written.contains(&(pc & !1)), "PC 0x{pc:08X}");
+}
+
+#[test]
+fn parse_crystal_output() {
+    let pass = parse_step_output(
+        &CRYSTAL,
+        "crystal_cali 8 5 20\r\ncrystal_cali start PB08 5 20 !\r\ncrystal_cali end, value 0x1f\r\nCRYSTAL_CALI_PASS 0x1f\r\n",
+    )
+    .unwrap()
+    .unwrap();
+    assert!(pass.passed);
+    assert_eq!(pass.value, Some(0x1f));
+
+    let timeout = parse_step_output(
+        &CRYSTAL,
+        "crystal_cali start PB08 5 20 !\r\ncrystal_cali error, timeout 20 s\r\n",
+    )
+    .unwrap()
+    .unwrap();
+    assert!(!timeout.passed);
+    assert_eq!(timeout.value, None);
+
+    let otp_error = parse_step_output(
+        &CRYSTAL,
+        "crystal_cali end, value 0x2a\r\nFACTORY_CFG_ID_CRYSTAL write error with -1\r\nCRYSTAL_CALI_FAIL\r\n",
+    )
+    .unwrap()
+    .unwrap();
+    assert!(!otp_error.passed);
+    assert_eq!(otp_error.value, Some(0x2a));
+
+    assert!(
+        parse_step_output(&CRYSTAL, "crystal_cali 8 5 20\r\n")
+            .unwrap()
+            .is_none()
+    );
+    assert!(parse_step_output(&CRYSTAL, "para error, eg: crystal_cali 8 5 20\r\n").is_err());
+}
+
+#[test]
+fn parse_battery_output() {
+    // Lines as printed by battery_cali in factory_cali.bin
+    let pass = parse_step_output(
+        &BATTERY,
+        "battery_cali 4000 400 10 1000 220\r\nbattery_cali start\r\n first measure: ref_value 4000 meas_value 3987 cali_value 1003\r\n battery_cali judge: volt 3992\r\n",
+    )
+    .unwrap()
+    .unwrap();
+    assert!(pass.passed);
+    assert_eq!(pass.value, Some(1003));
+    assert_eq!(pass.measured_mv, Some(3992));
+
+    // Outside reference_mv +- tolerance_mv after calibration
+    let fail = parse_step_output(
+        &BATTERY,
+        "battery_cali start\r\n first measure: ref_value 4000 meas_value 3700 cali_value 1081\r\n battery_cali judge: volt 4011\r\n",
+    )
+    .unwrap()
+    .unwrap();
+    assert!(!fail.passed);
+    assert_eq!(fail.measured_mv, Some(4011));
+
+    assert!(
+        parse_step_output(&BATTERY, "battery_cali start\r\n")
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn run_step_reads_result_from_shell() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    simulator.set_command_output(
+        "crystal_cali",
+        "crystal_cali start PB08 5 20 !\r\ncrystal_cali end, value 0x2a\r\nCRYSTAL_CALI_PASS 0x2a\r\n",
+    );
+    let mut tool = SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    start_factory_cali(&mut tool, &ChipType::SF32LB52).unwrap();
+
+    let (outcome, log) = run_step(&mut tool, &CRYSTAL).unwrap();
+    assert!(outcome.passed);
+    assert_eq!(outcome.value, Some(0x2a));
+    assert!(log.contains("crystal_cali end"));
+}
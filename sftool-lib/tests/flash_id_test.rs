@@ -0,0 +1,52 @@
+use sftool_lib::flash_id::parse_stub_log;
+use sftool_lib::stub_config::FlashMedia;
+
+#[test]
+fn parses_nor_id_and_printed_size() {
+    let log = "\r\n \\ | /\r\n- SiFli Corporation\r\n\
+        get_user_flash_cfg: isnand_0 type_1 id_c84018\r\n \
+        addr:0x12000000 base:0x12000000 size:0x1000000 sector:0x1000 page:0x100 id:0xc84018\r\n\
+        msh >";
+    let flashes = parse_stub_log(log);
+    assert_eq!(flashes.len(), 1);
+    let flash = &flashes[0];
+    assert_eq!(flash.media, FlashMedia::Nor);
+    assert_eq!(flash.driver_index, 1);
+    assert_eq!(
+        (flash.manufacturer_id, flash.device_type, flash.density_id),
+        (0xC8, 0x40, 0x18)
+    );
+    assert_eq!(flash.capacity_bytes, Some(0x0100_0000));
+
+    let config = flash.to_flash_config().unwrap();
+    assert_eq!(config.capacity_bytes, 0x0100_0000);
+    assert_eq!(config.manufacturer_id, 0xC8);
+}
+
+#[test]
+fn nor_capacity_falls_back_to_jedec_density() {
+    let flashes = parse_stub_log("get_user_flash_cfg: isnand_0 type_0 id_ef4017\r\n");
+    assert_eq!(flashes[0].capacity_bytes, Some(8 * 1024 * 1024));
+}
+
+#[test]
+fn known_nand_takes_capacity_from_part_table() {
+    let flashes = parse_stub_log("get_user_flash_cfg: isnand_1 type_2 id_efaa21\r\n");
+    assert_eq!(flashes[0].media, FlashMedia::Nand);
+    assert_eq!(flashes[0].part().unwrap().name, "W25N01GV");
+    assert_eq!(flashes[0].capacity_bytes, Some(128 * 1024 * 1024));
+}
+
+#[test]
+fn unknown_nand_without_size_has_unknown_capacity() {
+    let flashes = parse_stub_log("get_user_flash_cfg: isnand_1 type_2 id_e57100\r\n");
+    assert!(flashes[0].part().is_none());
+    assert_eq!(flashes[0].capacity_bytes, None);
+    assert!(flashes[0].to_flash_config().is_none());
+}
+
+#[test]
+fn log_without_detection_lines_is_empty() {
+    assert!(parse_stub_log("msh >").is_empty());
+    assert!(parse_stub_log("get_user_flash_cfg: isnand_0 type_1 id_zz").is_empty());
+}
@@ -0,0 +1,44 @@
+use sftool_lib::flash_parts::{FLASH_PARTS, find_by_jedec, find_part};
+use sftool_lib::stub_config::FlashMedia;
+use std::collections::HashSet;
+
+#[test]
+fn part_lookup_ignores_case() {
+    let part = find_part("w25n01gv").unwrap();
+    assert_eq!(part.name, "W25N01GV");
+    assert_eq!(part.media, FlashMedia::Nand);
+    assert_eq!(part.capacity_bytes, 128 * 1024 * 1024);
+    assert!(find_part("NOT-A-PART").is_none());
+}
+
+#[test]
+fn jedec_lookup_finds_part() {
+    let part = find_by_jedec(FlashMedia::Nor, 0xC8, 0x40, 0x18).unwrap();
+    assert_eq!(part.name, "GD25Q128E");
+}
+
+#[test]
+fn part_table_has_unique_names_and_ids() {
+    let mut names = HashSet::new();
+    let mut ids = HashSet::new();
+    for part in FLASH_PARTS {
+        assert!(names.insert(part.name), "duplicate part {}", part.name);
+        assert!(
+            ids.insert((part.manufacturer_id, part.device_type, part.density_id)),
+            "duplicate JEDEC ID for {}",
+            part.name
+        );
+    }
+}
+
+#[test]
+fn nor_capacities_match_density() {
+    for part in FLASH_PARTS
+        .iter()
+        .filter(|part| part.media == FlashMedia::Nor)
+    {
+        // Macronix 1.8V parts encode the density with a 0x20 offset
+        let density = part.density_id & 0x1F;
+        assert_eq!(part.capacity_bytes, 1 << density, "{}", part.name);
+    }
+}
@@ -0,0 +1,262 @@
+use serialport::SerialPort;
+use sftool_lib::common::net_port::{NetworkSerialPort, is_network_port};
+use sftool_lib::common::ram_command::DownloadStub;
+use sftool_lib::simulator::{Simulator, SimulatorMode};
+use sftool_lib::{BeforeOperation, ChipType, SifliToolBase, SifliToolTrait};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const DO: u8 = 253;
+const COM_PORT: u8 = 44;
+
+#[derive(Debug, PartialEq)]
+enum ServerEvent {
+    Data(Vec<u8>),
+    ComPort(u8, Vec<u8>),
+}
+
+/// Minimal RFC 2217 decoder used by the stand-in servers.
+#[derive(Default)]
+struct TelnetDecoder {
+    pending: Vec<u8>,
+}
+
+impl TelnetDecoder {
+    fn feed(&mut self, bytes: &[u8]) -> Vec<ServerEvent> {
+        self.pending.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        let mut data = Vec::new();
+        let mut i = 0;
+        while i < self.pending.len() {
+            let byte = self.pending[i];
+            if byte != IAC {
+                data.push(byte);
+                i += 1;
+                continue;
+            }
+            let Some(&command) = self.pending.get(i + 1) else {
+                break;
+            };
+            match command {
+                IAC => {
+                    data.push(IAC);
+                    i += 2;
+                }
+                SB => {
+                    let Some(end) = self.pending[i..]
+                        .windows(2)
+                        .position(|w| w == [IAC, SE])
+                        .map(|p| i + p)
+                    else {
+                        break;
+                    };
+                    let body = &self.pending[i + 2..end];
+                    if body.len() >= 2 && body[0] == COM_PORT {
+                        if !data.is_empty() {
+                            events.push(ServerEvent::Data(std::mem::take(&mut data)));
+                        }
+                        events.push(ServerEvent::ComPort(body[1], body[2..].to_vec()));
+                    }
+                    i = end + 2;
+                }
+                _ if self.pending.len() >= i + 3 => i += 3,
+                _ => break,
+            }
+        }
+        self.pending.drain(..i);
+        if !data.is_empty() {
+            events.push(ServerEvent::Data(data));
+        }
+        events
+    }
+}
+
+fn read_until(port: &mut dyn SerialPort, expected: usize) -> Vec<u8> {
+    let mut received = Vec::new();
+    let start = Instant::now();
+    while received.len() < expected && start.elapsed() < Duration::from_secs(3) {
+        let mut buffer = [0u8; 64];
+        match port.read(&mut buffer) {
+            Ok(count) => received.extend_from_slice(&buffer[..count]),
+            Err(e) if e.kind() == ErrorKind::TimedOut => {}
+            Err(e) => panic!("read failed: {}", e),
+        }
+    }
+    received
+}
+
+#[test]
+fn network_port_urls_are_detected() {
+    assert!(is_network_port("tcp://127.0.0.1:4000"));
+    assert!(is_network_port("rfc2217://lab-host:2217"));
+    assert!(!is_network_port("/dev/ttyUSB0"));
+    assert!(!is_network_port("COM3"));
+}
+
+#[test]
+fn raw_tcp_port_passes_bytes_through() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("tcp://{}", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = [0u8; 4];
+        stream.read_exact(&mut buffer).unwrap();
+        stream.write_all(&buffer).unwrap();
+        buffer
+    });
+
+    let mut port = NetworkSerialPort::open(&url, 1_000_000, Duration::from_millis(50)).unwrap();
+    port.write_request_to_send(true).unwrap();
+    port.write_all(&[0x7E, 0xFF, 0x00, 0x79]).unwrap();
+
+    assert_eq!(server.join().unwrap(), [0x7E, 0xFF, 0x00, 0x79]);
+    assert_eq!(read_until(&mut port, 4), [0x7E, 0xFF, 0x00, 0x79]);
+}
+
+#[test]
+fn rfc2217_port_sends_line_control_and_escapes_data() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("rfc2217://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        // Negotiation, a baud acknowledgement and data containing an escaped 0xFF
+        stream
+            .write_all(&[
+                IAC, DO, COM_PORT, IAC, SB, COM_PORT, 101, 0, 0x2D, 0xC6, 0xC0,
+            ])
+            .unwrap();
+        stream.write_all(&[IAC, SE, b'O', IAC, IAC, b'K']).unwrap();
+
+        let mut decoder = TelnetDecoder::default();
+        let mut buffer = [0u8; 256];
+        while let Ok(count) = stream.read(&mut buffer) {
+            if count == 0 {
+                break;
+            }
+            for event in decoder.feed(&buffer[..count]) {
+                tx.send(event).unwrap();
+            }
+        }
+    });
+
+    let mut port = NetworkSerialPort::open(&url, 1_000_000, Duration::from_millis(50)).unwrap();
+    assert_eq!(read_until(&mut port, 3), [b'O', 0xFF, b'K']);
+    assert_eq!(port.remote_baud_rate(), Some(3_000_000));
+
+    port.set_baud_rate(3_000_000).unwrap();
+    port.write_request_to_send(true).unwrap();
+    port.write_request_to_send(false).unwrap();
+    port.write_all(&[1, 0xFF, 2]).unwrap();
+    drop(port);
+
+    let events: Vec<ServerEvent> = rx.iter().collect();
+    let com_port: Vec<(u8, Vec<u8>)> = events
+        .iter()
+        .filter_map(|event| match event {
+            ServerEvent::ComPort(command, value) => Some((*command, value.clone())),
+            ServerEvent::Data(_) => None,
+        })
+        .collect();
+
+    assert_eq!(com_port[0], (1, 1_000_000u32.to_be_bytes().to_vec()));
+    assert!(com_port.contains(&(1, 3_000_000u32.to_be_bytes().to_vec())));
+    let rts: Vec<u8> = com_port
+        .iter()
+        .filter(|(command, value)| *command == 5 && matches!(value[0], 11 | 12))
+        .map(|(_, value)| value[0])
+        .collect();
+    assert_eq!(rts, [11, 12]);
+    assert!(events.contains(&ServerEvent::Data(vec![1, 0xFF, 2])));
+}
+
+/// RFC 2217 server in front of a simulated chip, standing in for ser2net.
+fn spawn_rfc2217_simulator(simulator: &Simulator) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("rfc2217://{}", listener.local_addr().unwrap());
+    let mut device = simulator.port();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        serve(stream, device.as_mut());
+    });
+    url
+}
+
+fn serve(mut stream: TcpStream, device: &mut dyn SerialPort) {
+    stream
+        .set_read_timeout(Some(Duration::from_millis(1)))
+        .unwrap();
+    let mut decoder = TelnetDecoder::default();
+    let mut buffer = [0u8; 4096];
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) => return,
+            Ok(count) => {
+                for event in decoder.feed(&buffer[..count]) {
+                    match event {
+                        ServerEvent::Data(data) => device.write_all(&data).unwrap(),
+                        ServerEvent::ComPort(1, value) if value.len() == 4 => {
+                            let baud = u32::from_be_bytes(value.try_into().unwrap());
+                            if baud != 0 {
+                                device.set_baud_rate(baud).unwrap();
+                            }
+                        }
+                        ServerEvent::ComPort(5, value) if value == [11] => {
+                            device.write_request_to_send(true).unwrap()
+                        }
+                        ServerEvent::ComPort(5, value) if value == [12] => {
+                            device.write_request_to_send(false).unwrap()
+                        }
+                        ServerEvent::ComPort(..) => {}
+                    }
+                }
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => return,
+        }
+
+        if let Ok(count) = device.read(&mut buffer) {
+            let mut escaped = Vec::with_capacity(count);
+            for &byte in &buffer[..count] {
+                escaped.push(byte);
+                if byte == IAC {
+                    escaped.push(IAC);
+                }
+            }
+            if stream.write_all(&escaped).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[test]
+fn sf32lb52_stub_loads_over_rfc2217() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let url = spawn_rfc2217_simulator(&simulator);
+
+    let base = SifliToolBase::new_with_no_progress(
+        url.clone(),
+        BeforeOperation::DefaultReset,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    );
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        port: sftool_lib::common::port::open_port(&base).unwrap(),
+        base,
+    };
+
+    tool.download_stub().unwrap();
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+
+    tool.set_speed(3_000_000).unwrap();
+    assert_eq!(simulator.baud_rate(), 3_000_000);
+}
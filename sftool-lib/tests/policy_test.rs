@@ -0,0 +1,254 @@
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use sftool_lib::common::ram_command::{Command, DownloadStub, RamCommand, Response};
+use sftool_lib::policy::{RetryPolicy, TimeoutPolicy};
+use sftool_lib::simulator::Simulator;
+use sftool_lib::utils::Utils;
+use sftool_lib::{
+    BeforeOperation, ChipType, Error, ReadFlashFile, ReadFlashParams, ReadFlashTrait, SifliToolBase,
+};
+use std::io::{self, ErrorKind, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Port that loses the device's whole reply to the first command containing
+/// `trigger`, like a glitch on a noisy USB-UART link.
+struct FlakyPort {
+    inner: Box<dyn SerialPort>,
+    trigger: &'static str,
+    state: Arc<Mutex<FlakyState>>,
+}
+
+#[derive(Default)]
+struct FlakyState {
+    triggered: bool,
+    dropping: bool,
+}
+
+impl FlakyPort {
+    fn boxed(inner: Box<dyn SerialPort>, trigger: &'static str) -> Box<dyn SerialPort> {
+        Box::new(Self {
+            inner,
+            trigger,
+            state: Arc::default(),
+        })
+    }
+}
+
+impl Read for FlakyPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.dropping {
+            let mut scratch = [0u8; 4096];
+            while self.inner.read(&mut scratch).is_ok() {}
+            state.dropping = false;
+            return Err(io::Error::new(ErrorKind::TimedOut, "reply lost"));
+        }
+        drop(state);
+        self.inner.read(buf)
+    }
+}
+
+impl Write for FlakyPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.inner.write(buf)?;
+        let mut state = self.state.lock().unwrap();
+        if !state.triggered && String::from_utf8_lossy(buf).contains(self.trigger) {
+            state.triggered = true;
+            state.dropping = true;
+        }
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl SerialPort for FlakyPort {
+    fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        self.inner.baud_rate()
+    }
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        self.inner.data_bits()
+    }
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        self.inner.flow_control()
+    }
+    fn parity(&self) -> serialport::Result<Parity> {
+        self.inner.parity()
+    }
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        self.inner.stop_bits()
+    }
+    fn timeout(&self) -> Duration {
+        self.inner.timeout()
+    }
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.inner.set_baud_rate(baud_rate)
+    }
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        self.inner.set_data_bits(data_bits)
+    }
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        self.inner.set_flow_control(flow_control)
+    }
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        self.inner.set_parity(parity)
+    }
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        self.inner.set_stop_bits(stop_bits)
+    }
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.inner.set_timeout(timeout)
+    }
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.inner.write_request_to_send(level)
+    }
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.inner.write_data_terminal_ready(level)
+    }
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        self.inner.read_clear_to_send()
+    }
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        self.inner.read_data_set_ready()
+    }
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        self.inner.read_ring_indicator()
+    }
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        self.inner.read_carrier_detect()
+    }
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        self.inner.bytes_to_read()
+    }
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        self.inner.bytes_to_write()
+    }
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        self.inner.clear(buffer_to_clear)
+    }
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(Self {
+            inner: self.inner.try_clone()?,
+            trigger: self.trigger,
+            state: self.state.clone(),
+        }))
+    }
+    fn set_break(&self) -> serialport::Result<()> {
+        self.inner.set_break()
+    }
+    fn clear_break(&self) -> serialport::Result<()> {
+        self.inner.clear_break()
+    }
+}
+
+fn flaky_tool(
+    simulator: &Simulator,
+    trigger: &'static str,
+    retries: u32,
+) -> sftool_lib::sf32lb52::SF32LB52Tool {
+    let mut base = SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::DefaultReset,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    );
+    base.timeouts.command = Duration::from_millis(300);
+    base.timeouts.read = Duration::from_millis(300);
+    base.retry = RetryPolicy {
+        retries,
+        delay: Duration::from_millis(10),
+    };
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base,
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+    tool.port = FlakyPort::boxed(simulator.port(), trigger);
+    tool
+}
+
+#[test]
+fn timeout_policy_parses_overrides() {
+    let mut policy = TimeoutPolicy::default();
+    policy.apply("read=20000").unwrap();
+    policy.apply("sd_multiplier=5").unwrap();
+    assert_eq!(policy.read, Duration::from_millis(20_000));
+    assert_eq!(policy.read_timeout("sd"), Duration::from_millis(100_000));
+    assert_eq!(policy.read_timeout("nor"), Duration::from_millis(20_000));
+
+    assert!(policy.apply("bogus=1").is_err());
+    assert!(policy.apply("read").is_err());
+    assert!(policy.apply("read=soon").is_err());
+}
+
+#[test]
+fn timeout_policy_scales_erase_by_sectors() {
+    let policy = TimeoutPolicy::default();
+    assert_eq!(
+        policy.erase_region_timeout(0x1000, "nor"),
+        Duration::from_millis(1600)
+    );
+    assert_eq!(
+        policy.command_timeout(&Command::EraseAll { address: 0 }, "nor"),
+        policy.erase
+    );
+    assert_eq!(
+        policy.command_timeout(&Command::SoftReset, "sd_type1"),
+        policy.command * 3
+    );
+}
+
+#[test]
+fn lost_verify_reply_is_retried() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    simulator.write_memory(0x1202_0000, b"firmware");
+    let mut tool = flaky_tool(&simulator, "burn_verify", 1);
+
+    let response = tool
+        .command(Command::Verify {
+            address: 0x1202_0000,
+            len: 8,
+            crc: Utils::calculate_crc32(b"firmware"),
+        })
+        .unwrap();
+    assert_eq!(response, Response::Ok);
+}
+
+#[test]
+fn lost_verify_reply_fails_without_retries() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = flaky_tool(&simulator, "burn_verify", 0);
+
+    let result = tool.command(Command::Verify {
+        address: 0x1202_0000,
+        len: 8,
+        crc: 0,
+    });
+    assert!(matches!(result, Err(Error::Timeout(_))));
+}
+
+#[test]
+fn lost_read_reply_is_retried() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let image: Vec<u8> = (0..0x800u32).map(|i| (i * 13) as u8).collect();
+    simulator.write_memory(0x1202_0000, &image);
+    let mut tool = flaky_tool(&simulator, "burn_read", 2);
+
+    let output = tempfile::NamedTempFile::new().unwrap();
+    tool.read_flash(&ReadFlashParams {
+        files: vec![ReadFlashFile {
+            file_path: output.path().to_string_lossy().into_owned(),
+            address: 0x1202_0000,
+            size: image.len() as u32,
+        }],
+    })
+    .unwrap();
+    assert_eq!(std::fs::read(output.path()).unwrap(), image);
+}
@@ -0,0 +1,190 @@
+use sftool_lib::rtt::{self, RTT_ID, RttHandler, RttLocation, RttParams, default_scan_range};
+use sftool_lib::simulator::{Simulator, SimulatorMode};
+use sftool_lib::{BeforeOperation, CancelToken, ChipType, Error, SifliToolBase};
+use std::time::Duration;
+
+struct Buffer {
+    address: u32,
+    size: u32,
+    wr_off: u32,
+    rd_off: u32,
+}
+
+/// Write a control block with one up and one down buffer
+fn write_control_block(simulator: &Simulator, address: u32, up: &Buffer, down: &Buffer) {
+    let mut block = RTT_ID.to_vec();
+    block.extend_from_slice(&1u32.to_le_bytes());
+    block.extend_from_slice(&1u32.to_le_bytes());
+    for buffer in [up, down] {
+        for word in [
+            0,
+            buffer.address,
+            buffer.size,
+            buffer.wr_off,
+            buffer.rd_off,
+            0,
+        ] {
+            block.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+    simulator.write_memory(address, &block);
+}
+
+/// Collects channel 0 output and stops the viewer once anything arrived
+struct Collector {
+    cancel_token: CancelToken,
+    down: Option<Vec<u8>>,
+    received: Vec<u8>,
+}
+
+impl RttHandler for Collector {
+    fn on_up(&mut self, channel: usize, data: &[u8]) -> sftool_lib::Result<()> {
+        assert_eq!(channel, 0);
+        self.received.extend_from_slice(data);
+        self.cancel_token.cancel();
+        Ok(())
+    }
+
+    fn poll_down(&mut self, _channel: usize) -> Option<Vec<u8>> {
+        self.down.take()
+    }
+}
+
+fn make_tool(simulator: &Simulator) -> sftool_lib::sf32lb52::SF32LB52Tool {
+    sftool_lib::sf32lb52::SF32LB52Tool {
+        base: SifliToolBase::new_with_no_progress(
+            "simulator".to_string(),
+            BeforeOperation::NoResetNoSync,
+            "nor".to_string(),
+            1_000_000,
+            3,
+            false,
+        ),
+        port: simulator.port(),
+    }
+}
+
+fn read_word(simulator: &Simulator, address: u32) -> u32 {
+    u32::from_le_bytes(simulator.read_memory(address, 4).try_into().unwrap())
+}
+
+#[test]
+fn rtt_reads_up_and_writes_down_buffers() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let control_block = 0x2000_1000;
+    write_control_block(
+        &simulator,
+        control_block,
+        &Buffer {
+            address: 0x2000_2000,
+            size: 64,
+            wr_off: 6,
+            rd_off: 0,
+        },
+        &Buffer {
+            address: 0x2000_3000,
+            size: 16,
+            wr_off: 0,
+            rd_off: 0,
+        },
+    );
+    simulator.write_memory(0x2000_2000, b"hello\n");
+
+    let mut tool = make_tool(&simulator);
+    let mut handler = Collector {
+        cancel_token: tool.base.cancel_token.clone(),
+        down: Some(b"ping".to_vec()),
+        received: Vec::new(),
+    };
+    let result = rtt::run(
+        &mut tool,
+        &RttParams {
+            location: RttLocation::Address(control_block),
+            poll_interval: Duration::from_millis(10),
+        },
+        &mut handler,
+    );
+
+    assert!(matches!(result, Err(Error::Cancelled)));
+    // Debug mode is left even though the session was cancelled
+    assert_eq!(simulator.mode(), SimulatorMode::Rom);
+    assert_eq!(handler.received, b"hello\n");
+    // Up RdOff caught up with WrOff, down data landed and WrOff moved
+    assert_eq!(read_word(&simulator, control_block + 24 + 16), 6);
+    assert_eq!(simulator.read_memory(0x2000_3000, 4), b"ping");
+    assert_eq!(read_word(&simulator, control_block + 48 + 12), 4);
+}
+
+#[test]
+fn rtt_scan_finds_block_across_chunks_and_handles_wrap() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    // The ID straddles the first 1 KiB scan chunk
+    let control_block = 0x2000_03F8;
+    write_control_block(
+        &simulator,
+        control_block,
+        &Buffer {
+            address: 0x2000_2000,
+            size: 16,
+            wr_off: 4,
+            rd_off: 12,
+        },
+        &Buffer {
+            address: 0x2000_3000,
+            size: 16,
+            wr_off: 0,
+            rd_off: 0,
+        },
+    );
+    simulator.write_memory(0x2000_2000, b"efgh");
+    simulator.write_memory(0x2000_200C, b"abcd");
+
+    let mut tool = make_tool(&simulator);
+    let mut handler = Collector {
+        cancel_token: tool.base.cancel_token.clone(),
+        down: None,
+        received: Vec::new(),
+    };
+    let result = rtt::run(
+        &mut tool,
+        &RttParams {
+            location: RttLocation::Scan(0x2000_0000..0x2000_1000),
+            poll_interval: Duration::from_millis(10),
+        },
+        &mut handler,
+    );
+
+    assert!(matches!(result, Err(Error::Cancelled)));
+    assert_eq!(handler.received, b"abcdefgh");
+    assert_eq!(read_word(&simulator, control_block + 24 + 16), 4);
+}
+
+#[test]
+fn rtt_reports_missing_control_block() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = make_tool(&simulator);
+    let mut handler = Collector {
+        cancel_token: tool.base.cancel_token.clone(),
+        down: None,
+        received: Vec::new(),
+    };
+    let err = rtt::run(
+        &mut tool,
+        &RttParams {
+            location: RttLocation::Address(0x2000_1000),
+            poll_interval: Duration::from_millis(10),
+        },
+        &mut handler,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("no RTT control block"), "{err}");
+}
+
+#[test]
+fn default_scan_range_covers_every_debug_chip() {
+    for chip in [ChipType::SF32LB52, ChipType::SF32LB56, ChipType::SF32LB57] {
+        let range = default_scan_range(&chip).unwrap();
+        assert_eq!(range.start, 0x2000_0000, "{chip:?}");
+    }
+    assert_eq!(default_scan_range(&ChipType::SF32LB58), None);
+}
@@ -0,0 +1,132 @@
+use sftool_lib::run_ram::{self, RunRamParams};
+use sftool_lib::simulator::{Simulator, SimulatorMode};
+use sftool_lib::{BeforeOperation, ChipType, Error, SifliToolBase};
+
+fn make_base() -> SifliToolBase {
+    SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::NoResetNoSync,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    )
+}
+
+fn program() -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&0x2001_0000u32.to_le_bytes());
+    data.extend_from_slice(&0x2000_0101u32.to_le_bytes());
+    data.extend((0..300u32).map(|i| i as u8));
+    data
+}
+
+#[test]
+fn load_and_run_writes_ram_and_starts_program() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+
+    let params = RunRamParams::from_bin(program(), 0x2000_0000);
+    assert_eq!(params.sp_pc().unwrap(), (0x2001_0000, 0x2000_0101));
+    run_ram::load_and_run(&mut tool, &params).unwrap();
+
+    assert_eq!(
+        simulator.read_memory(0x2000_0000, program().len()),
+        program()
+    );
+    // The core was started and debug mode left
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+}
+
+#[test]
+fn entry_overrides_reset_vector() {
+    let mut params = RunRamParams::from_bin(program(), 0x2000_0000);
+    params.entry = Some(0x2000_0201);
+    assert_eq!(params.sp_pc().unwrap(), (0x2001_0000, 0x2000_0201));
+}
+
+#[test]
+fn short_image_is_rejected_before_reset() {
+    let simulator = Simulator::new(ChipType::SF32LB56);
+    let mut tool = sftool_lib::sf32lb56::SF32LB56Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+
+    let params = RunRamParams::from_bin(vec![0; 4], 0x2000_0000);
+    let err = run_ram::load_and_run(&mut tool, &params).unwrap_err();
+    assert!(err.to_string().contains("vector table"), "{err}");
+    assert_eq!(simulator.mode(), SimulatorMode::Rom);
+}
+
+#[test]
+fn load_and_run_is_rejected_on_dfu_chips() {
+    let simulator = Simulator::new(ChipType::SF32LB55);
+    let mut tool = sftool_lib::sf32lb55::SF32LB55Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    let params = RunRamParams::from_bin(program(), 0x2000_0000);
+    assert!(matches!(
+        run_ram::load_and_run(&mut tool, &params),
+        Err(Error::UnsupportedChip(_))
+    ));
+}
+
+/// Thumb ELF with `.text` at 0x2000_0000 and `__Vectors` in `.isr_vector` at 0x2000_0100
+const ELF: &[u8] = include_bytes!("run_ram_test.elf");
+
+fn patch(data: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let index = data
+        .windows(from.len())
+        .position(|window| window == from)
+        .unwrap();
+    let mut data = data.to_vec();
+    data[index..index + to.len()].copy_from_slice(to);
+    data
+}
+
+#[test]
+fn elf_vector_table_comes_from_vectors_symbol() {
+    let params = RunRamParams::from_elf(ELF).unwrap();
+    assert_eq!(params.vector_table, Some(0x2000_0100));
+    // The lowest segment starts with code, not the vector table
+    assert_eq!(params.vector_table().unwrap(), (0x2001_0000, 0x2000_0009));
+    assert_eq!(params.sp_pc().unwrap(), (0x2001_0000, 0x2000_0009));
+}
+
+#[test]
+fn elf_vector_table_falls_back_to_isr_vector_section() {
+    // Rename the symbol so only the section is left
+    let data = patch(ELF, b"__Vectors", b"__Vectorz");
+    let params = RunRamParams::from_elf(&data).unwrap();
+    assert_eq!(params.vector_table, Some(0x2000_0100));
+    assert_eq!(params.vector_table().unwrap(), (0x2001_0000, 0x2000_0009));
+}
+
+#[test]
+fn elf_segments_outside_ram_are_rejected() {
+    // Move the first PT_LOAD (ELF32: e_phoff at 28, p_paddr at +12) into flash
+    let phoff = u32::from_le_bytes(ELF[28..32].try_into().unwrap()) as usize;
+    let mut data = ELF.to_vec();
+    data[phoff + 12..phoff + 16].copy_from_slice(&0x1200_0000u32.to_le_bytes());
+    let err = RunRamParams::from_elf(&data).unwrap_err();
+    assert!(err.to_string().contains("outside RAM"), "{err}");
+}
+
+#[test]
+fn flash_address_is_rejected_before_reset() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+
+    let params = RunRamParams::from_bin(program(), 0x1200_0000);
+    let err = run_ram::load_and_run(&mut tool, &params).unwrap_err();
+    assert!(err.to_string().contains("outside RAM"), "{err}");
+    assert_eq!(simulator.mode(), SimulatorMode::Rom);
+}
@@ -0,0 +1,123 @@
+use sftool_lib::common::ram_command::DownloadStub;
+use sftool_lib::secure_boot::{default_sig_pub_key_name, load_sig_pub_key, validate_sig_pub_key};
+use sftool_lib::simulator::{Simulator, SimulatorMode};
+use sftool_lib::{BeforeOperation, ChipType, Error, SifliToolBase};
+use std::io::Write;
+
+fn make_base() -> SifliToolBase {
+    SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::DefaultReset,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    )
+}
+
+fn key_file(data: &[u8]) -> tempfile::NamedTempFile {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(data).unwrap();
+    file
+}
+
+const RSA_OID: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    match content.len() {
+        len @ 0..0x80 => out.push(len as u8),
+        len @ 0x80..0x100 => out.extend_from_slice(&[0x81, len as u8]),
+        len => out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// SubjectPublicKeyInfo with the given algorithm OID and modulus (exponent 65537)
+fn spki(oid: &[u8], modulus: &[u8]) -> Vec<u8> {
+    let mut integer = vec![0];
+    integer.extend_from_slice(modulus);
+    let rsa_key = tlv(
+        0x30,
+        &[tlv(0x02, &integer), tlv(0x02, &[0x01, 0x00, 0x01])].concat(),
+    );
+    let bit_string = tlv(0x03, &[&[0u8][..], &rsa_key].concat());
+    let algorithm = tlv(0x30, &[tlv(0x06, oid), tlv(0x05, &[])].concat());
+    tlv(0x30, &[algorithm, bit_string].concat())
+}
+
+#[test]
+fn embedded_key_is_valid_rsa_2048() {
+    assert!(default_sig_pub_key_name(&ChipType::SF32LB52).is_none());
+    assert!(default_sig_pub_key_name(&ChipType::SF32LB55).is_some());
+
+    let key = load_sig_pub_key(&ChipType::SF32LB58, None).unwrap();
+    assert_eq!(key.len(), 294);
+    validate_sig_pub_key(&key).unwrap();
+    assert_eq!(load_sig_pub_key(&ChipType::SF32LB55, None).unwrap(), key);
+}
+
+#[test]
+fn malformed_keys_are_rejected() {
+    let key = load_sig_pub_key(&ChipType::SF32LB58, None).unwrap();
+
+    let err = validate_sig_pub_key(&key[..key.len() - 1]).unwrap_err();
+    assert!(err.to_string().contains("truncated"), "{err}");
+
+    let mut trailing = key.clone();
+    trailing.push(0);
+    let err = validate_sig_pub_key(&trailing).unwrap_err();
+    assert!(err.to_string().contains("trailing"), "{err}");
+
+    let err = validate_sig_pub_key(b"-----BEGIN PUBLIC KEY-----").unwrap_err();
+    assert!(matches!(err, Error::InvalidInput { .. }), "{err:?}");
+
+    let err = validate_sig_pub_key(&spki(RSA_OID, &[0xC5; 128])).unwrap_err();
+    assert!(err.to_string().contains("1024 bits"), "{err}");
+
+    // id-ecPublicKey
+    let ec_oid = [0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+    let err = validate_sig_pub_key(&spki(&ec_oid, &[0xC5; 256])).unwrap_err();
+    assert!(err.to_string().contains("not an RSA key"), "{err}");
+
+    validate_sig_pub_key(&spki(RSA_OID, &[0xC5; 256])).unwrap();
+}
+
+#[test]
+fn custom_key_is_sent_before_the_stub() {
+    let mut custom = load_sig_pub_key(&ChipType::SF32LB58, None).unwrap();
+    // Change a modulus byte: still a well-formed RSA-2048 key, but a different one
+    let last = custom.len() - 10;
+    custom[last] ^= 0xFF;
+    let file = key_file(&custom);
+
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    let mut base = make_base();
+    base.sig_pub_key_path = Some(file.path().to_string_lossy().to_string());
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base,
+        port: simulator.port(),
+    };
+
+    tool.download_stub().unwrap();
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+    assert_eq!(simulator.sig_pub_key(), custom);
+}
+
+#[test]
+fn invalid_custom_key_stops_before_dfu() {
+    let file = key_file(&[0x30, 0x03, 0x02, 0x01, 0x01]);
+
+    let simulator = Simulator::new(ChipType::SF32LB55);
+    let mut base = make_base();
+    base.sig_pub_key_path = Some(file.path().to_string_lossy().to_string());
+    let mut tool = sftool_lib::sf32lb55::SF32LB55Tool {
+        base,
+        port: simulator.port(),
+    };
+
+    assert!(tool.download_stub().is_err());
+    assert!(simulator.sig_pub_key().is_empty());
+    assert!(simulator.dfu_image().is_empty());
+}
@@ -0,0 +1,497 @@
+use sftool_lib::common::erase_flash::EraseOps;
+use sftool_lib::common::ram_command::DownloadStub;
+use sftool_lib::progress::{ProgressEvent, ProgressSink};
+use sftool_lib::simulator::{Simulator, SimulatorMode};
+use sftool_lib::speed::{AUTO_BAUD_LADDER, auto_baud_candidates};
+use sftool_lib::utils::Utils;
+use sftool_lib::{
+    BeforeOperation, ChipType, EraseFlashTrait, EraseRegionFile, EraseRegionParams, Error,
+    ReadFlashFile, ReadFlashParams, ReadFlashTrait, SifliToolBase, SifliToolTrait, VerifyParams,
+    WriteFlashFile, WriteFlashParams, WriteFlashTrait,
+};
+use std::io::{Seek, Write};
+use std::sync::Arc;
+
+fn make_base() -> SifliToolBase {
+    SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::DefaultReset,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    )
+}
+
+fn make_image(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 + i / 256) as u8).collect()
+}
+
+fn write_params(address: u32, data: &[u8]) -> WriteFlashParams {
+    let mut file = tempfile::tempfile().unwrap();
+    file.write_all(data).unwrap();
+    file.rewind().unwrap();
+    WriteFlashParams {
+        files: vec![WriteFlashFile {
+            address,
+            file,
+            crc32: Utils::calculate_crc32(data),
+        }],
+        verify: true,
+        no_compress: false,
+        erase_all: false,
+    }
+}
+
+#[test]
+fn sf32lb52_flashing_pipeline_runs_against_simulator() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+
+    tool.download_stub().unwrap();
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+
+    tool.set_speed(3_000_000).unwrap();
+    assert_eq!(simulator.baud_rate(), 3_000_000);
+
+    let image = make_image(200 * 1024 + 17);
+    tool.write_flash(&write_params(0x1202_0000, &image))
+        .unwrap();
+    assert_eq!(simulator.read_memory(0x1202_0000, image.len()), image);
+
+    let output = tempfile::NamedTempFile::new().unwrap();
+    tool.read_flash(&ReadFlashParams {
+        files: vec![ReadFlashFile {
+            file_path: output.path().to_string_lossy().into_owned(),
+            address: 0x1202_0000,
+            size: 0x1000,
+        }],
+    })
+    .unwrap();
+    assert_eq!(std::fs::read(output.path()).unwrap(), &image[..0x1000]);
+
+    tool.erase_region(&EraseRegionParams {
+        regions: vec![EraseRegionFile {
+            address: 0x1202_0000,
+            size: 0x1000,
+        }],
+    })
+    .unwrap();
+    assert_eq!(
+        simulator.read_memory(0x1202_0000, 0x1000),
+        vec![0xFF; 0x1000]
+    );
+    assert_eq!(
+        simulator.read_memory(0x1202_1000, 16),
+        &image[0x1000..0x1010]
+    );
+}
+
+#[test]
+fn sf32lb56_big_endian_debug_frames_load_stub() {
+    let simulator = Simulator::new(ChipType::SF32LB56);
+    let mut tool = sftool_lib::sf32lb56::SF32LB56Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+
+    tool.download_stub().unwrap();
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+
+    let image = make_image(4096);
+    tool.write_flash(&write_params(0x1000_0000, &image))
+        .unwrap();
+    assert_eq!(simulator.read_memory(0x1000_0000, image.len()), image);
+}
+
+#[test]
+fn sf32lb58_dfu_flow_starts_stub() {
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+
+    tool.download_stub().unwrap();
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+    assert!(!simulator.dfu_image().is_empty());
+
+    let image = make_image(1000);
+    tool.write_flash(&write_params(0x1200_0000, &image))
+        .unwrap();
+    assert_eq!(simulator.read_memory(0x1200_0000, image.len()), image);
+}
+
+#[test]
+fn sf32lb58_switches_baud_after_dfu_stub() {
+    let simulator = Simulator::with_memory(ChipType::SF32LB58, "nand");
+    let mut base = make_base();
+    base.memory_type = "nand".to_string();
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base,
+        port: simulator.port(),
+    };
+
+    tool.download_stub().unwrap();
+    tool.set_speed(3_000_000).unwrap();
+    assert_eq!(simulator.baud_rate(), 3_000_000);
+
+    let image = make_image(8192);
+    tool.write_flash(&write_params(0x6200_0000, &image))
+        .unwrap();
+    assert_eq!(simulator.read_memory(0x6200_0000, image.len()), image);
+}
+
+#[test]
+fn simulator_verify_rejects_wrong_crc() {
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    simulator.write_memory(0x1200_0000, b"firmware");
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+
+    let mut params = write_params(0x1200_0000, b"firmware");
+    params.files[0].crc32 ^= 1;
+    // The first verify fails, so the data is rewritten and verified again
+    assert!(tool.write_flash(&params).is_err());
+}
+
+#[test]
+fn simulator_completes_zero_length_write() {
+    use std::io::Read;
+    use std::time::{Duration, Instant};
+
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+
+    let mut port = simulator.port();
+    port.write_all(b"burn_write 0x12000000 0\r").unwrap();
+    let mut received = Vec::new();
+    let start = Instant::now();
+    while !received.ends_with(b"msh >") && start.elapsed() < Duration::from_secs(1) {
+        let mut buffer = [0u8; 64];
+        if let Ok(count) = port.read(&mut buffer) {
+            received.extend_from_slice(&buffer[..count]);
+        }
+    }
+    assert!(received.ends_with(b"OK\r\nmsh >"));
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+}
+
+#[cfg(unix)]
+#[test]
+fn simulator_is_reachable_through_pty() {
+    use std::io::Read;
+    use std::time::{Duration, Instant};
+
+    let simulator = Simulator::new(ChipType::SF32LB55);
+    let pty = simulator.open_pty().unwrap();
+    let mut port = serialport::new(pty.path(), 1_000_000)
+        .timeout(Duration::from_millis(50))
+        .preserve_dtr_on_open()
+        .open()
+        .unwrap();
+
+    port.write_all(b"dfu_recv 2\r").unwrap();
+    port.write_all(&[3, 10]).unwrap();
+
+    let mut received = Vec::new();
+    let start = Instant::now();
+    while !received.ends_with(b"OK\r\n") && start.elapsed() < Duration::from_secs(3) {
+        let mut buffer = [0u8; 64];
+        if let Ok(count) = port.read(&mut buffer) {
+            received.extend_from_slice(&buffer[..count]);
+        }
+    }
+    assert!(received.ends_with(b"OK\r\n"));
+}
+
+#[test]
+fn auto_baud_falls_back_to_fastest_working_rate() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    simulator.set_max_baud_rate(Some(2_000_000));
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+
+    let baud = tool
+        .negotiate_speed(&auto_baud_candidates(Some(3_000_000)))
+        .unwrap();
+    assert_eq!(baud, 2_000_000);
+    assert_eq!(simulator.baud_rate(), 2_000_000);
+    assert_eq!(simulator.mode(), SimulatorMode::Shell);
+
+    let image = make_image(4096);
+    tool.write_flash(&write_params(0x1202_0000, &image))
+        .unwrap();
+    assert_eq!(simulator.read_memory(0x1202_0000, image.len()), image);
+}
+
+#[test]
+fn sf32lb58_auto_baud_after_dfu_stub() {
+    let simulator = Simulator::with_memory(ChipType::SF32LB58, "nand");
+    simulator.set_max_baud_rate(Some(2_000_000));
+    let mut base = make_base();
+    base.memory_type = "nand".to_string();
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base,
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+
+    let baud = tool
+        .negotiate_speed(&auto_baud_candidates(Some(3_000_000)))
+        .unwrap();
+    assert_eq!(baud, 2_000_000);
+    assert_eq!(simulator.baud_rate(), 2_000_000);
+
+    let image = make_image(4096);
+    tool.write_flash(&write_params(0x6200_0000, &image))
+        .unwrap();
+    assert_eq!(simulator.read_memory(0x6200_0000, image.len()), image);
+}
+
+#[test]
+fn auto_baud_probe_follows_memory_type() {
+    use sftool_lib::common::speed::ProbeAddresses;
+
+    let probe = ProbeAddresses {
+        nor: 0x1000_0000,
+        nand: 0x6400_0000,
+        sd: 0xA000_0000,
+    };
+    assert_eq!(probe.for_memory("nor"), 0x1000_0000);
+    assert_eq!(probe.for_memory("NOR_TYPE1"), 0x1000_0000);
+    assert_eq!(probe.for_memory("nand"), 0x6400_0000);
+    assert_eq!(probe.for_memory("nand_nobbm"), 0x6400_0000);
+    assert_eq!(probe.for_memory("sd"), 0xA000_0000);
+}
+
+#[test]
+fn auto_baud_candidates_start_from_cached_rate() {
+    assert_eq!(auto_baud_candidates(None), AUTO_BAUD_LADDER.to_vec());
+    // A low cached rate is tried first, but faster rates stay reachable
+    assert_eq!(
+        auto_baud_candidates(Some(2_000_000)),
+        vec![2_000_000, 6_000_000, 3_000_000, 1_500_000, 1_000_000]
+    );
+    // Rates outside the ladder are kept as the first candidate
+    assert_eq!(auto_baud_candidates(Some(921_600))[0], 921_600);
+    assert_eq!(auto_baud_candidates(Some(921_600)).len(), 6);
+}
+
+#[derive(Default)]
+struct RecordingSink {
+    events: std::sync::Mutex<Vec<ProgressEvent>>,
+}
+
+impl ProgressSink for RecordingSink {
+    fn on_event(&self, event: ProgressEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+#[test]
+fn erase_region_splits_on_chunk_boundaries() {
+    let chunk = EraseOps::ERASE_CHUNK_SIZE;
+    assert_eq!(
+        EraseOps::split_region(0x1202_0000, 0x9_0000),
+        vec![
+            (0x1202_0000, 0x2_0000),
+            (0x1204_0000, chunk),
+            (0x1208_0000, 0x3_0000),
+        ]
+    );
+    assert_eq!(
+        EraseOps::split_region(0x1200_0000, 0x1000),
+        vec![(0x1200_0000, 0x1000)]
+    );
+    assert!(EraseOps::split_region(0x1200_0000, 0).is_empty());
+}
+
+#[test]
+fn large_region_erase_reports_byte_progress() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let sink = Arc::new(RecordingSink::default());
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: SifliToolBase::new_with_progress(
+            "simulator".to_string(),
+            BeforeOperation::DefaultReset,
+            "nor".to_string(),
+            1_000_000,
+            3,
+            false,
+            sink.clone(),
+        ),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+
+    let len = 600 * 1024;
+    simulator.write_memory(0x1202_0000, &make_image(len));
+    tool.erase_region(&EraseRegionParams {
+        regions: vec![EraseRegionFile {
+            address: 0x1202_0000,
+            size: len as u32,
+        }],
+    })
+    .unwrap();
+    assert!(
+        simulator
+            .read_memory(0x1202_0000, len)
+            .iter()
+            .all(|&b| b == 0xFF)
+    );
+
+    let events = sink.events.lock().unwrap();
+    let advances: Vec<u64> = events
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::Advance { delta, .. } => Some(*delta),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(advances.len(), 3);
+    assert_eq!(advances.iter().sum::<u64>(), len as u64);
+}
+
+#[test]
+fn region_erase_stops_when_cancelled() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+
+    simulator.write_memory(0x1202_0000, b"keep");
+    tool.base.cancel_token.cancel();
+    let result = tool.erase_region(&EraseRegionParams {
+        regions: vec![EraseRegionFile {
+            address: 0x1202_0000,
+            size: 0x10_0000,
+        }],
+    });
+    assert!(matches!(result, Err(Error::Cancelled)));
+    assert_eq!(simulator.read_memory(0x1202_0000, 4), b"keep");
+}
+
+#[test]
+fn verify_blank_reports_first_dirty_chunk() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+
+    let blank = EraseRegionParams {
+        regions: vec![EraseRegionFile {
+            address: 0x1230_0000,
+            size: 0x2_8000,
+        }],
+    };
+    tool.verify_blank(&blank).unwrap();
+
+    simulator.write_memory(0x1231_2345, &[0x00]);
+    let result = tool.verify_blank(&blank);
+    assert!(matches!(
+        result,
+        Err(Error::NotBlank {
+            address: 0x1231_0000,
+            len: 0x1_0000,
+        })
+    ));
+}
+
+#[test]
+fn verify_flash_reports_each_segment() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let app = make_image(0x3000);
+    simulator.write_memory(0x1202_0000, &app);
+    simulator.write_memory(0x1210_0000, b"stale data");
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+
+    let mut files = write_params(0x1202_0000, &app).files;
+    files.append(&mut write_params(0x1210_0000, b"fresh data").files);
+    let results = tool.verify_flash(&VerifyParams { files }).unwrap();
+
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| (result.address, result.matched))
+            .collect::<Vec<_>>(),
+        vec![(0x1202_0000, true), (0x1210_0000, false)]
+    );
+    assert_eq!(simulator.read_memory(0x1210_0000, 10), b"stale data");
+}
+
+#[test]
+fn flash_id_comes_from_stub_boot_log() {
+    for chip in [ChipType::SF32LB52, ChipType::SF32LB58] {
+        let simulator = Simulator::new(chip.clone());
+        simulator.set_boot_log("get_user_flash_cfg: isnand_0 type_1 id_c84018\r\n");
+        let base = make_base();
+        let flashes = match chip {
+            ChipType::SF32LB52 => {
+                let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+                    base,
+                    port: simulator.port(),
+                };
+                tool.download_stub().unwrap();
+                tool.flash_ids().unwrap()
+            }
+            _ => {
+                let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+                    base,
+                    port: simulator.port(),
+                };
+                tool.download_stub().unwrap();
+                tool.flash_ids().unwrap()
+            }
+        };
+        assert_eq!(flashes.len(), 1, "{:?}", chip);
+        assert_eq!(flashes[0].manufacturer_id, 0xC8);
+        assert_eq!(flashes[0].capacity_bytes, Some(0x0100_0000));
+    }
+}
+
+#[test]
+fn flash_id_without_stub_output_is_an_error() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+    assert!(tool.flash_ids().is_err());
+}
+
+#[test]
+fn flash_id_on_sd_memory_is_an_error() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    simulator.set_boot_log("SD card capacity 7761920 KB.\r\n");
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    tool.download_stub().unwrap();
+    tool.base.memory_type = "sd".to_string();
+    let err = tool.flash_ids().unwrap_err();
+    assert!(err.to_string().contains("SD memory"), "{}", err);
+}
@@ -1,3 +1,4 @@
+use sftool_lib::ChipType;
 use sftool_lib::stub_config::*;
 
 #[test]
@@ -54,3 +55,85 @@ fn roundtrip_stub_config() {
     let decoded = read_stub_config_from_bytes(&data).expect("read config");
     assert_eq!(decoded, config);
 }
+
+fn pin(port: PinPort, number: u8) -> PinConfig {
+    PinConfig {
+        port,
+        number,
+        level: PinLevel::High,
+    }
+}
+
+fn empty_config() -> StubConfig {
+    StubConfig {
+        pins: Vec::new(),
+        flash: Vec::new(),
+        pmic: None,
+        sd0: None,
+    }
+}
+
+#[test]
+fn validate_rejects_pins_missing_on_chip() {
+    let mut config = empty_config();
+    config.pins = vec![pin(PinPort::Pa, 44), pin(PinPort::Pa, 45)];
+    let err = validate_stub_config(&config, &ChipType::SF32LB52).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("pins[1]"), "{message}");
+    assert!(message.contains("PA45"), "{message}");
+
+    config.pins = vec![pin(PinPort::Pb, 2)];
+    let err = validate_stub_config(&config, &ChipType::SF32LB52).unwrap_err();
+    assert!(err.to_string().contains("no PB port"), "{err}");
+    validate_stub_config(&config, &ChipType::SF32LB58).expect("PB2 exists on SF32LB58");
+}
+
+#[test]
+fn validate_rejects_pmic_on_chip_without_one() {
+    let mut config = empty_config();
+    config.pmic = Some(PmicConfig {
+        disabled: false,
+        scl_port: PinPort::Pa,
+        scl_pin: 10,
+        sda_port: PinPort::Pa,
+        sda_pin: 11,
+        channels: vec![PmicChannel::Ldo33],
+    });
+    let err = validate_stub_config(&config, &ChipType::SF32LB52).unwrap_err();
+    assert!(err.to_string().contains("pmic:"), "{err}");
+    validate_stub_config(&config, &ChipType::SF32LB56).expect("SF32LB56 drives a PMIC");
+
+    config.pmic.as_mut().unwrap().disabled = true;
+    validate_stub_config(&config, &ChipType::SF32LB52).expect("disabled PMIC is ignored");
+}
+
+#[test]
+fn validate_rejects_shared_pmic_bus_pin() {
+    let mut config = empty_config();
+    config.pmic = Some(PmicConfig {
+        disabled: false,
+        scl_port: PinPort::Pb,
+        scl_pin: 3,
+        sda_port: PinPort::Pb,
+        sda_pin: 3,
+        channels: Vec::new(),
+    });
+    let err = validate_stub_config(&config, &ChipType::SF32LB58).unwrap_err();
+    assert!(err.to_string().contains("PB3"), "{err}");
+}
+
+#[test]
+fn validate_checks_sd0_pinmux_per_chip() {
+    let mut config = empty_config();
+    config.sd0 = Some(Sd0Config {
+        base_address: 0x6800_0000,
+        pinmux: Sd0Pinmux::ClkPa60OrPa39,
+        init_sequence: Sd0InitSequence::SdThenEmmc,
+    });
+    let err = validate_stub_config(&config, &ChipType::SF32LB52).unwrap_err();
+    assert!(err.to_string().contains("clk_pa60_or_pa39"), "{err}");
+    validate_stub_config(&config, &ChipType::SF32LB58).expect("PA60 exists on SF32LB58");
+
+    config.sd0.as_mut().unwrap().pinmux = Sd0Pinmux::ClkPa34OrPa09;
+    validate_stub_config(&config, &ChipType::SF32LB52).expect("PA34 exists on SF32LB52");
+}
@@ -0,0 +1,303 @@
+use sftool_lib::reg;
+use sftool_lib::simulator::Simulator;
+use sftool_lib::svd::Device;
+use sftool_lib::{BeforeOperation, ChipType, Error, SifliToolBase};
+
+const SVD: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<device>
+  <name>TEST</name>
+  <size>32</size>
+  <resetValue>0x00000000</resetValue>
+  <peripherals>
+    <peripheral>
+      <name>GPIO1</name>
+      <baseAddress>0x500A0000</baseAddress>
+      <registers>
+        <register>
+          <name>DOSR0</name>
+          <description>Data output
+            set register</description>
+          <addressOffset>0x08</addressOffset>
+          <fields>
+            <field><name>HIGH</name><bitRange>[31:16]</bitRange></field>
+            <field><name>PIN21</name><bitOffset>21</bitOffset><bitWidth>1</bitWidth></field>
+            <field><name>LOW</name><lsb>0</lsb><msb>3</msb></field>
+          </fields>
+        </register>
+        <register>
+          <name>BYTE</name>
+          <addressOffset>0x10</addressOffset>
+          <size>8</size>
+        </register>
+      </registers>
+    </peripheral>
+    <peripheral derivedFrom="GPIO1">
+      <name>GPIO2</name>
+      <baseAddress>0x40080000</baseAddress>
+    </peripheral>
+  </peripherals>
+</device>
+"#;
+
+#[test]
+fn names_resolve_to_addresses_and_fields() {
+    let device = Device::parse(SVD).unwrap();
+
+    let reg = device.resolve("gpio1.dosr0").unwrap();
+    assert_eq!(reg.name(), "GPIO1.DOSR0");
+    assert_eq!(reg.address(), 0x500A_0008);
+    assert_eq!(reg.register.size, 32);
+    assert_eq!(
+        reg.register.description.as_deref(),
+        Some("Data output set register")
+    );
+    // Fields are sorted by bit position
+    let names: Vec<_> = reg
+        .register
+        .fields
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect();
+    assert_eq!(names, ["LOW", "HIGH", "PIN21"]);
+
+    let field = device.resolve("GPIO1.DOSR0.HIGH").unwrap().field.unwrap();
+    assert_eq!((field.bit_offset, field.bit_width), (16, 16));
+    assert_eq!(field.bit_range(), "[31:16]");
+    let field = device.resolve("GPIO1.DOSR0.LOW").unwrap().field.unwrap();
+    assert_eq!((field.bit_offset, field.bit_width), (0, 4));
+
+    // Derived peripherals share registers but keep their own base address
+    assert_eq!(
+        device.resolve("GPIO2.DOSR0").unwrap().address(),
+        0x4008_0008
+    );
+    assert_eq!(device.resolve("GPIO1.BYTE").unwrap().register.size, 8);
+}
+
+#[test]
+fn unknown_names_are_reported() {
+    let device = Device::parse(SVD).unwrap();
+    for (name, message) in [
+        ("GPIO1", "expected PERIPH.REG"),
+        ("UART1.CR", "no peripheral named UART1"),
+        ("GPIO1.CR", "no register named CR"),
+        ("GPIO1.DOSR0.PIN3", "no field named PIN3"),
+    ] {
+        let err = device.resolve(name).unwrap_err();
+        assert!(err.to_string().contains(message), "{name}: {err}");
+    }
+    assert!(Device::parse("<device><peripherals>").is_err());
+}
+
+#[test]
+fn field_values_are_inserted_and_extracted() {
+    let device = Device::parse(SVD).unwrap();
+    let field = device.resolve("GPIO1.DOSR0.LOW").unwrap().field.unwrap();
+    assert_eq!(field.insert(0xFFFF_FF00, 0xA).unwrap(), 0xFFFF_FF0A);
+    assert_eq!(field.extract(0x1234_5679), 0x9);
+    assert!(field.insert(0, 0x10).is_err());
+}
+
+const ARRAY_SVD: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>TIM%s</name>
+      <dim>2</dim>
+      <dimIncrement>0x1000</dimIncrement>
+      <dimIndex>1,2</dimIndex>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <name>CCR[%s]</name>
+          <dim>4</dim>
+          <dimIncrement>4</dimIncrement>
+          <addressOffset>0x10</addressOffset>
+          <fields>
+            <field>
+              <name>EN%s</name>
+              <dim>2</dim>
+              <dimIncrement>8</dimIncrement>
+              <dimIndex>A-B</dimIndex>
+              <bitOffset>0</bitOffset>
+              <bitWidth>4</bitWidth>
+            </field>
+          </fields>
+        </register>
+        <register derivedFrom="CCR[%s]">
+          <name>ARR</name>
+          <addressOffset>0x40</addressOffset>
+          <size>16</size>
+        </register>
+        <cluster>
+          <name>CH%s</name>
+          <dim>2</dim>
+          <dimIncrement>0x20</dimIncrement>
+          <addressOffset>0x100</addressOffset>
+          <register>
+            <name>CFG</name>
+            <addressOffset>0x4</addressOffset>
+          </register>
+          <cluster>
+            <name>DMA</name>
+            <addressOffset>0x10</addressOffset>
+            <register><name>ADDR</name><addressOffset>0x0</addressOffset></register>
+          </cluster>
+        </cluster>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>
+"#;
+
+#[test]
+fn dim_arrays_clusters_and_derived_registers_are_expanded() {
+    let device = Device::parse(ARRAY_SVD).unwrap();
+
+    // Peripheral arrays use dimIndex for their names
+    assert!(device.peripheral("TIM0").is_none());
+    assert_eq!(device.resolve("TIM2.CCR0").unwrap().address(), 0x4000_1010);
+
+    // Register arrays: [%s] is replaced by the index
+    let names: Vec<_> = device
+        .peripheral("TIM1")
+        .unwrap()
+        .registers
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "CCR0",
+            "CCR1",
+            "CCR2",
+            "CCR3",
+            "ARR",
+            "CH0_CFG",
+            "CH0_DMA_ADDR",
+            "CH1_CFG",
+            "CH1_DMA_ADDR"
+        ]
+    );
+    assert_eq!(device.resolve("TIM1.CCR3").unwrap().address(), 0x4000_001C);
+
+    // Field arrays with a letter dimIndex
+    let field = device.resolve("TIM1.CCR1.ENB").unwrap().field.unwrap();
+    assert_eq!((field.bit_offset, field.bit_width), (8, 4));
+
+    // derivedFrom copies fields but keeps the register's own offset and size
+    let arr = device.resolve("TIM1.ARR").unwrap();
+    assert_eq!(arr.address(), 0x4000_0040);
+    assert_eq!(arr.register.size, 16);
+    assert!(device.resolve("TIM1.ARR.ENA").is_ok());
+
+    // Cluster offsets add up, including nested clusters
+    assert_eq!(
+        device.resolve("TIM1.CH1_CFG").unwrap().address(),
+        0x4000_0124
+    );
+    assert_eq!(
+        device.resolve("TIM2.CH0_DMA_ADDR").unwrap().address(),
+        0x4000_1110
+    );
+}
+
+#[test]
+fn unsupported_svd_constructs_are_rejected() {
+    let wrap = |registers: &str| {
+        format!(
+            "<device><peripherals><peripheral><name>P</name><baseAddress>0</baseAddress>\
+             <registers>{registers}</registers></peripheral></peripherals></device>"
+        )
+    };
+    for (registers, message) in [
+        (
+            "<register derivedFrom=\"NOPE\"><name>R</name></register>",
+            "derived from unknown register NOPE",
+        ),
+        (
+            "<register derivedFrom=\"R\"><name>R</name></register>",
+            "derivedFrom chain is too deep",
+        ),
+        (
+            "<cluster derivedFrom=\"C\"><name>D</name><addressOffset>0</addressOffset></cluster>",
+            "cluster derivedFrom (C) is not supported",
+        ),
+        (
+            "<register><name>R</name><addressOffset>0</addressOffset><fields>\
+             <field derivedFrom=\"A\"><name>B</name><bitOffset>0</bitOffset></field>\
+             </fields></register>",
+            "field derivedFrom (A) is not supported",
+        ),
+        (
+            "<register><name>R</name><dim>2</dim><dimIncrement>4</dimIncrement>\
+             <addressOffset>0</addressOffset></register>",
+            "dim array name has no %s",
+        ),
+        (
+            "<register><name>R%s</name><dim>3</dim><dimIncrement>4</dimIncrement>\
+             <dimIndex>A-B</dimIndex><addressOffset>0</addressOffset></register>",
+            "dimIndex has 2 entries but dim is 3",
+        ),
+        (
+            "<register><name>R</name><addressOffset>0</addressOffset><fields>\
+             <field><name>F%s</name><dim>2</dim><dimIncrement>16</dimIncrement>\
+             <bitOffset>8</bitOffset><bitWidth>16</bitWidth></field>\
+             </fields></register>",
+            "exceed 32 bits",
+        ),
+    ] {
+        let err = Device::parse(&wrap(registers)).unwrap_err();
+        assert!(err.to_string().contains(message), "{registers}: {err}");
+    }
+}
+
+fn make_base() -> SifliToolBase {
+    SifliToolBase::new_with_no_progress(
+        "simulator".to_string(),
+        BeforeOperation::NoResetNoSync,
+        "nor".to_string(),
+        1_000_000,
+        3,
+        false,
+    )
+}
+
+#[test]
+fn register_access_goes_through_the_debug_channel() {
+    let simulator = Simulator::new(ChipType::SF32LB52);
+    simulator.write_memory(0x500A_0008, &0x1234_5678u32.to_le_bytes());
+    let mut tool = sftool_lib::sf32lb52::SF32LB52Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+
+    assert_eq!(
+        reg::read_register(&mut tool, 0x500A_0008).unwrap(),
+        0x1234_5678
+    );
+    // Only the masked bits change
+    let field = Device::parse(SVD).unwrap();
+    let field = field.resolve("GPIO1.DOSR0.PIN21").unwrap().field.unwrap();
+    let written = reg::modify_register(
+        &mut tool,
+        0x500A_0008,
+        field.mask(),
+        field.insert(0, 1).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(written, 0x1234_5678 | 1 << 21);
+    assert_eq!(simulator.read_memory(0x500A_0008, 4), written.to_le_bytes());
+
+    let simulator = Simulator::new(ChipType::SF32LB58);
+    let mut tool = sftool_lib::sf32lb58::SF32LB58Tool {
+        base: make_base(),
+        port: simulator.port(),
+    };
+    assert!(matches!(
+        reg::read_register(&mut tool, 0x500A_0008),
+        Err(Error::UnsupportedChip(_))
+    ));
+}
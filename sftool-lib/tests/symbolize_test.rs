@@ -0,0 +1,156 @@
+use sftool_lib::symbolize::{Symbol, SymbolHit, Symbolizer, partial_token_start};
+
+fn make_symbolizer() -> Symbolizer {
+    Symbolizer::new(
+        vec![0x1202_0000..0x1203_0000, 0x2000_8000..0x2000_8100],
+        vec![
+            Symbol {
+                name: "main".to_string(),
+                address: 0x1202_0100,
+                size: 0x40,
+            },
+            Symbol {
+                name: "HardFault_Handler".to_string(),
+                address: 0x1202_0000,
+                size: 0x20,
+            },
+            Symbol {
+                name: "ram_func".to_string(),
+                address: 0x2000_8000,
+                size: 0x10,
+            },
+            Symbol {
+                name: "asm_label".to_string(),
+                address: 0x1202_0200,
+                size: 0,
+            },
+        ],
+    )
+}
+
+#[test]
+fn lookup_resolves_addresses_inside_text() {
+    let symbolizer = make_symbolizer();
+
+    assert_eq!(
+        symbolizer.lookup(0x1202_0112),
+        Some(SymbolHit {
+            name: "main",
+            offset: 0x12
+        })
+    );
+    assert_eq!(
+        symbolizer.lookup(0x1202_0280),
+        Some(SymbolHit {
+            name: "asm_label",
+            offset: 0x80
+        })
+    );
+    assert_eq!(
+        symbolizer.lookup(0x2000_8008),
+        Some(SymbolHit {
+            name: "ram_func",
+            offset: 0x8
+        })
+    );
+    // Gap between HardFault_Handler and main
+    assert_eq!(symbolizer.lookup(0x1202_0080), None);
+    // Outside the text section
+    assert_eq!(symbolizer.lookup(0x2000_0000), None);
+}
+
+#[test]
+fn annotate_line_only_touches_resolvable_addresses() {
+    let symbolizer = make_symbolizer();
+
+    let line = "PC: 0x12020104 LR:0x1202000A SP=0x20001000 id=0x12020104abc\r\n";
+    let annotated = symbolizer.annotate_line(line);
+
+    assert_eq!(
+        annotated,
+        "PC: 0x12020104 <main+0x4> LR:0x1202000A <HardFault_Handler+0xa> SP=0x20001000 id=0x12020104abc\r\n"
+    );
+}
+
+#[test]
+fn annotate_line_without_addresses_is_unchanged() {
+    let symbolizer = make_symbolizer();
+    let line = "msh >0x boot ok 0xZZ";
+
+    assert_eq!(symbolizer.annotate_line(line), line);
+}
+
+#[test]
+fn partial_lines_hold_back_a_split_address() {
+    let symbolizer = make_symbolizer();
+
+    // A read that stops in the middle of an address keeps the whole token back
+    let first = b"PC: 0x1202";
+    let end = partial_token_start(first);
+    assert_eq!(&first[..end], b"PC: ");
+
+    // Once the rest arrives the address is annotated as a whole
+    let mut line = first[end..].to_vec();
+    line.extend_from_slice(b"0104\r\n");
+    assert_eq!(
+        symbolizer.annotate_line(&String::from_utf8(line).unwrap()),
+        "0x12020104 <main+0x4>\r\n"
+    );
+
+    assert_eq!(partial_token_start(b"msh >"), 5);
+    assert_eq!(partial_token_start(b"0x12"), 0);
+}
+
+// Linked with GNU ld at 0x12020000: Reset_Handler (8 bytes), HardFault_Handler at +0x10
+// (2 bytes) and main at +0x20 (9 bytes).
+const FIXTURE_ELF: &[u8] = include_bytes!("symbolize_test.elf");
+
+#[test]
+fn from_bytes_loads_function_symbols_from_elf() {
+    let symbolizer = Symbolizer::from_bytes(FIXTURE_ELF).unwrap();
+
+    // Linker-defined NOTYPE symbols (_edata, __bss_start, ...) are not functions.
+    assert_eq!(symbolizer.len(), 3);
+    assert_eq!(
+        symbolizer.lookup(0x1202_0004),
+        Some(SymbolHit {
+            name: "Reset_Handler",
+            offset: 0x4
+        })
+    );
+    assert_eq!(
+        symbolizer.lookup(0x1202_0028),
+        Some(SymbolHit {
+            name: "main",
+            offset: 0x8
+        })
+    );
+    // Padding between HardFault_Handler and main
+    assert_eq!(symbolizer.lookup(0x1202_0018), None);
+    // Past the end of .text
+    assert_eq!(symbolizer.lookup(0x1202_0030), None);
+    assert_eq!(
+        symbolizer.annotate_line("PC=0x12020011"),
+        "PC=0x12020011 <HardFault_Handler+0x1>"
+    );
+}
+
+#[test]
+fn from_bytes_strips_thumb_bit_on_arm_elf() {
+    // Same image with e_machine patched to EM_ARM
+    let mut elf = FIXTURE_ELF.to_vec();
+    elf[18..20].copy_from_slice(&40u16.to_le_bytes());
+    let symbolizer = Symbolizer::from_bytes(&elf).unwrap();
+
+    assert_eq!(
+        symbolizer.lookup(0x1202_0021),
+        Some(SymbolHit {
+            name: "main",
+            offset: 0
+        })
+    );
+    assert_eq!(
+        symbolizer.annotate_line("LR: 0x12020011"),
+        "LR: 0x12020011 <HardFault_Handler+0x0>"
+    );
+}
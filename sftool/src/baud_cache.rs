@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Location of the per-port cache of negotiated baud rates.
+///
+/// `SFTOOL_BAUD_CACHE` overrides the default `~/.sftool/baud_cache.json`.
+fn cache_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("SFTOOL_BAUD_CACHE") {
+        return Some(PathBuf::from(path));
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".sftool").join("baud_cache.json"))
+}
+
+fn load() -> BTreeMap<String, u32> {
+    cache_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Last baud rate that passed negotiation on `port`.
+pub fn cached_baud(port: &str) -> Option<u32> {
+    load().get(port).copied()
+}
+
+/// Remember the negotiated baud rate for `port`.
+///
+/// The cache is only an optimisation, so failures to write it are ignored.
+pub fn remember_baud(port: &str, baud: u32) {
+    let Some(path) = cache_path() else {
+        return;
+    };
+    let mut cache = load();
+    cache.insert(port.to_string(), baud);
+
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(content) = serde_json::to_string_pretty(&cache) {
+        let _ = std::fs::write(path, content);
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::{Result, anyhow, bail};
 use clap::{Parser, Subcommand, ValueEnum};
+use sftool_lib::policy::{RetryPolicy, TimeoutPolicy};
 use sftool_lib::{AfterOperation, BeforeOperation, ChipType};
+use std::str::FromStr;
 use strum::{Display, EnumString};
 
 use crate::config::SfToolConfig;
@@ -9,13 +11,14 @@ pub type MergedConfig = (
     ChipType,
     String,
     String,
-    u32,
+    Baud,
     BeforeOperation,
     AfterOperation,
     i8,
     bool,
     bool,
     Option<String>, // stub path
+    Option<String>, // signature public key path
 );
 
 #[derive(EnumString, Display, Debug, Clone, ValueEnum)]
@@ -28,14 +31,41 @@ pub enum Memory {
     Nand,
     #[clap(name = "nand_type1")]
     NandType1,
+    #[clap(name = "nand_nobbm")]
+    NandNobbm,
     #[clap(name = "nand_nobbm_type1")]
     NandNobbmType1,
+    #[clap(name = "nand_6m")]
+    Nand6m,
+    #[clap(name = "nand_8m")]
+    Nand8m,
     #[clap(name = "sd")]
     Sd,
     #[clap(name = "sd_type1")]
     SdType1,
 }
 
+/// Baud rate given via `--baud`: a fixed rate or `auto`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Baud {
+    Fixed(u32),
+    /// Try a descending ladder of rates and keep the fastest one that works
+    Auto,
+}
+
+impl FromStr for Baud {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(Baud::Auto);
+        }
+        s.parse::<u32>()
+            .map(Baud::Fixed)
+            .map_err(|_| format!("invalid baud rate '{}', expected a number or 'auto'", s))
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about = "sftool CLI", long_about = None)]
 pub struct Cli {
@@ -47,13 +77,13 @@ pub struct Cli {
     #[arg(short = 'm', long = "memory", value_enum, ignore_case = true)]
     pub memory: Option<Memory>,
 
-    /// Serial port device
+    /// Serial port device, or tcp://host:port / rfc2217://host:port
     #[arg(short = 'p', long = "port")]
     pub port: Option<String>,
 
-    /// Serial port baud rate used when flashing/reading (default: 1000000)
+    /// Serial port baud rate used when flashing/reading, or `auto` to negotiate (default: 1000000)
     #[arg(short = 'b', long = "baud")]
-    pub baud: Option<u32>,
+    pub baud: Option<Baud>,
 
     /// What to do before connecting to the chip (default: default_reset)
     #[arg(long = "before", value_enum)]
@@ -75,10 +105,30 @@ pub struct Cli {
     #[arg(long = "stub")]
     pub stub: Option<String>,
 
+    /// Secure-boot public key (DER, RSA-2048) sent before the stub on SF32LB55/58
+    #[arg(long = "sig-key", value_name = "FILE")]
+    pub sig_key: Option<String>,
+
     /// Stub config JSON to apply to the stub before operations
     #[arg(long = "stub-config", global = true)]
     pub stub_config_json: Option<String>,
 
+    /// Record all serial traffic to a transcript file
+    #[arg(long = "record", value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<String>,
+
+    /// Replay a recorded transcript instead of opening the serial port
+    #[arg(long = "replay", value_name = "FILE")]
+    pub replay: Option<String>,
+
+    /// Override a timeout, e.g. `--timeout read=20000` (keys: command, erase, erase_sector, read, debug in ms; sd_multiplier)
+    #[arg(long = "timeout", value_name = "KEY=MS")]
+    pub timeouts: Vec<String>,
+
+    /// Number of retries for idempotent commands such as verify and read (default: 2)
+    #[arg(long = "retries")]
+    pub retries: Option<u32>,
+
     /// Suppress progress bar output (default: false)
     #[arg(short = 'q', long = "quiet")]
     pub quiet: bool,
@@ -97,10 +147,22 @@ pub enum Commands {
     #[command(name = "write_flash")]
     WriteFlash(WriteFlash),
 
+    /// Verify flash contents against local files without writing
+    #[command(name = "verify")]
+    Verify(Verify),
+
+    /// Compare flash contents with local files and report the differences
+    #[command(name = "diff")]
+    Diff(Diff),
+
     /// Read a binary blob from flash
     #[command(name = "read_flash")]
     ReadFlash(ReadFlash),
 
+    /// Report the JEDEC ID and capacity of the attached flash
+    #[command(name = "flash_id")]
+    FlashId(FlashIdCommand),
+
     /// Erase the entire flash
     #[command(name = "erase_flash")]
     EraseFlash(EraseFlash),
@@ -109,9 +171,45 @@ pub enum Commands {
     #[command(name = "erase_region")]
     EraseRegion(EraseRegion),
 
+    /// Check that a region of the flash is blank (all 0xFF)
+    #[command(name = "verify_blank")]
+    VerifyBlank(VerifyBlank),
+
+    /// Run the embedded factory calibration image over the debug channel (SF32LB52/56)
+    #[command(name = "factory_cali")]
+    FactoryCali(FactoryCali),
+
+    /// Read eFuse over the debug channel (SF32LB52/56/57)
+    #[command(name = "efuse")]
+    Efuse(EfuseCommand),
+
+    /// Download signed images through the ROM DFU flow, without the RAM stub (SF32LB55/58)
+    #[command(name = "dfu_write")]
+    DfuWrite(DfuWrite),
+
     /// Manage stub config in AXF/ELF driver files
     #[command(name = "stub")]
     Stub(StubCommand),
+
+    /// Print serial output from the device
+    #[command(name = "monitor")]
+    Monitor(Monitor),
+
+    /// View SEGGER RTT output over the UART debug channel (SF32LB52/56/57)
+    #[command(name = "rtt")]
+    Rtt(Rtt),
+
+    /// Load a program into RAM and run it without touching flash (SF32LB52/56/57)
+    #[command(name = "run_ram")]
+    RunRam(RunRam),
+
+    /// Read and write peripheral registers by SVD name (SF32LB52/56/57)
+    #[command(name = "reg")]
+    Reg(RegCommand),
+
+    /// Inspect DFU stub images
+    #[command(name = "image")]
+    Image(ImageCommand),
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -142,6 +240,30 @@ pub struct WriteFlash {
     pub files: Vec<String>,
 }
 
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Verify flash contents against local files without writing")]
+pub struct Verify {
+    /// Binary file (format: <filename@address>, if file format includes address info, @address is optional)
+    #[arg(required = true)]
+    pub files: Vec<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Compare flash contents with local files and report the differences")]
+pub struct Diff {
+    /// Print a hexdump of the first N differing 16-byte rows of each file
+    #[arg(long = "hexdump", value_name = "ROWS")]
+    pub hexdump: Option<usize>,
+
+    /// Print the report as JSON
+    #[arg(long = "json")]
+    pub json: bool,
+
+    /// Binary file (format: <filename@address>, if file format includes address info, @address is optional)
+    #[arg(required = true)]
+    pub files: Vec<String>,
+}
+
 #[derive(Parser, Debug, Clone)]
 #[command(about = "Read a binary blob from flash")]
 pub struct ReadFlash {
@@ -150,6 +272,14 @@ pub struct ReadFlash {
     pub files: Vec<String>,
 }
 
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Report the JEDEC ID and capacity of the attached flash")]
+pub struct FlashIdCommand {
+    /// Print the result as stub config `flash` entries
+    #[arg(long = "json")]
+    pub json: bool,
+}
+
 #[derive(Parser, Debug, Clone)]
 #[command(about = "Erase flash")]
 pub struct EraseFlash {
@@ -166,6 +296,202 @@ pub struct EraseRegion {
     pub region: Vec<String>,
 }
 
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Check that a region of the flash is blank (all 0xFF)")]
+pub struct VerifyBlank {
+    /// Region to check (format: <address:size>)
+    #[arg(required = true)]
+    pub region: Vec<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Run the embedded factory calibration image")]
+#[command(group(clap::ArgGroup::new("steps").required(true).multiple(true).args(["crystal", "battery"])))]
+pub struct FactoryCali {
+    /// Crystal calibration against a reference clock on PB<PIN>
+    #[arg(long = "crystal", value_name = "PIN,PPM,SECONDS")]
+    pub crystal: Option<String>,
+
+    /// Battery voltage calibration with the given reference and divider resistors
+    #[arg(
+        long = "battery",
+        value_name = "REF_MV,RANGE_MV,TOLERANCE_MV,UPPER_KOHM,LOWER_KOHM"
+    )]
+    pub battery: Option<String>,
+
+    /// Save a JSON report of the calibration results to a file
+    #[arg(long = "output", value_name = "FILE")]
+    pub output: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Read eFuse over the debug channel")]
+pub struct EfuseCommand {
+    #[command(subcommand)]
+    pub action: EfuseAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum EfuseAction {
+    /// Read all eFuse banks without resetting the chip; decodes the UID, the
+    /// SF32LB52 ADC calibration and the SF32LB57 package ID, other fields
+    /// (security config, trims) are printed as raw bank words
+    #[command(name = "read")]
+    Read(EfuseRead),
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(
+    about = "Read all eFuse banks and print the chip UID",
+    long_about = "Read all eFuse banks and print the chip UID.\n\nOnly the fields the RAM stubs use are decoded: the UID on every chip, the ADC calibration on SF32LB52 and the package ID on SF32LB57. Security configuration and other trims are not decoded; read them from the raw bank words against the chip manual."
+)]
+pub struct EfuseRead {
+    /// Print the result as JSON
+    #[arg(long = "json")]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Download signed DFU images through the ROM without the RAM stub")]
+pub struct DfuWrite {
+    /// Signed DFU image (format: <filename@flash_id>)
+    #[arg(required = true, value_name = "FILE@FLASH_ID")]
+    pub files: Vec<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Print serial output from the device")]
+pub struct Monitor {
+    /// ELF/AXF file used to annotate hex addresses with function+offset
+    #[arg(long = "elf")]
+    pub elf: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "View SEGGER RTT output over the UART debug channel")]
+#[command(group(clap::ArgGroup::new("location").args(["elf", "address", "scan"])))]
+pub struct Rtt {
+    /// ELF/AXF file whose _SEGGER_RTT symbol gives the control block address
+    #[arg(long = "elf")]
+    pub elf: Option<String>,
+
+    /// Control block address
+    #[arg(long = "address")]
+    pub address: Option<String>,
+
+    /// RAM range to search for the control block (format: <address:size>)
+    #[arg(long = "scan")]
+    pub scan: Option<String>,
+
+    /// Up channel to print and down channel that receives stdin
+    #[arg(long = "channel", default_value_t = 0)]
+    pub channel: usize,
+
+    /// Poll interval in milliseconds
+    #[arg(long = "interval", default_value_t = 10)]
+    pub interval: u64,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Load a program into RAM and run it without touching flash")]
+pub struct RunRam {
+    /// ELF/AXF file, or binary file with its load address (format: <filename@address>)
+    #[arg(required = true, value_name = "FILE")]
+    pub file: String,
+
+    /// Entry point (default: the ELF entry, or the reset vector of a binary)
+    #[arg(long = "entry")]
+    pub entry: Option<String>,
+
+    /// Print serial output after the program starts
+    #[arg(long = "monitor")]
+    pub monitor: bool,
+
+    /// Console baud rate for --monitor
+    #[arg(long = "monitor-baud", default_value_t = 1000000, requires = "monitor")]
+    pub monitor_baud: u32,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Read and write peripheral registers by SVD name")]
+pub struct RegCommand {
+    #[command(subcommand)]
+    pub action: RegAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum RegAction {
+    /// Read registers or fields and print the decoded field values
+    #[command(name = "read")]
+    Read(RegRead),
+
+    /// Write a register, or a single field with a read-modify-write
+    #[command(name = "write")]
+    Write(RegWrite),
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Read registers or fields and print the decoded field values")]
+pub struct RegRead {
+    /// CMSIS-SVD file describing the chip
+    #[arg(long = "svd", required = true)]
+    pub svd: String,
+
+    /// Register or field names (format: <PERIPH.REG[.FIELD]>)
+    #[arg(required = true, value_name = "NAME")]
+    pub names: Vec<String>,
+
+    /// Print the result as JSON
+    #[arg(long = "json")]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Write a register, or a single field with a read-modify-write")]
+pub struct RegWrite {
+    /// CMSIS-SVD file describing the chip
+    #[arg(long = "svd", required = true)]
+    pub svd: String,
+
+    /// Register or field name (format: <PERIPH.REG[.FIELD]>)
+    #[arg(value_name = "NAME")]
+    pub name: String,
+
+    /// Value to write
+    #[arg(value_name = "VALUE")]
+    pub value: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Inspect DFU stub images")]
+pub struct ImageCommand {
+    #[command(subcommand)]
+    pub action: ImageAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ImageAction {
+    /// Validate a DFU image (SF32LB55/58 stub format) and print its layout
+    #[command(name = "info")]
+    Info(ImageInfo),
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Validate a DFU image and print its layout")]
+pub struct ImageInfo {
+    /// DFU image file
+    #[arg(required_unless_present = "embedded", conflicts_with = "embedded")]
+    pub file: Option<String>,
+
+    /// Inspect an embedded stub by key (e.g. sf32lb58_nand) or file name
+    #[arg(long = "embedded", value_name = "KEY")]
+    pub embedded: Option<String>,
+
+    /// Print the layout as JSON
+    #[arg(long = "json")]
+    pub json: bool,
+}
+
 #[derive(Parser, Debug, Clone)]
 #[command(about = "Manage stub config in AXF/ELF driver files")]
 pub struct StubCommand {
@@ -175,7 +501,7 @@ pub struct StubCommand {
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum StubAction {
-    /// Write stub config into AXF/ELF driver files
+    /// Write stub config into AXF/ELF driver files, checked against `-c` or the chip in the file name
     #[command(name = "write")]
     Write(StubWrite),
 
@@ -186,6 +512,26 @@ pub enum StubAction {
     /// Read stub config from AXF/ELF driver files
     #[command(name = "read")]
     Read(StubRead),
+
+    /// List the flash parts that stub config `flash` entries can name with `part`
+    #[command(name = "parts")]
+    Parts,
+
+    /// Show per-field differences between two stub configs; exits with status 1 when they differ
+    #[command(name = "diff")]
+    Diff(StubDiff),
+
+    /// Overlay a partial stub config JSON onto the config in AXF/ELF driver files
+    #[command(name = "merge")]
+    Merge(StubMerge),
+
+    /// List the stub images embedded in sftool
+    #[command(name = "list")]
+    List(StubList),
+
+    /// Extract an embedded stub image to a file
+    #[command(name = "extract")]
+    Extract(StubExtract),
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -212,14 +558,59 @@ pub struct StubClear {
 #[command(about = "Read stub config from AXF/ELF driver files")]
 pub struct StubRead {
     /// Target driver files
-    #[arg(required = true)]
+    #[arg(required_unless_present = "embedded", conflicts_with = "embedded")]
     pub files: Vec<String>,
 
+    /// Read the config of an embedded stub by key (e.g. sf32lb52_nand) or file name
+    #[arg(long = "embedded", value_name = "KEY")]
+    pub embedded: Option<String>,
+
     /// Optional output JSON file (single input only)
     #[arg(long = "output")]
     pub output: Option<String>,
 }
 
+#[derive(Parser, Debug, Clone)]
+#[command(about = "List the stub images embedded in sftool")]
+pub struct StubList {
+    /// Print the list as JSON
+    #[arg(long = "json")]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Extract an embedded stub image to a file")]
+pub struct StubExtract {
+    /// Embedded stub key (e.g. sf32lb52_nand) or file name
+    pub key: String,
+
+    /// Output file
+    #[arg(short = 'o', long = "output")]
+    pub output: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Show per-field differences between two stub configs")]
+pub struct StubDiff {
+    /// First driver file or stub config JSON (*.json)
+    pub a: String,
+
+    /// Second driver file or stub config JSON (*.json)
+    pub b: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Overlay a partial stub config JSON onto the config in AXF/ELF driver files")]
+pub struct StubMerge {
+    /// Target driver files
+    #[arg(required = true)]
+    pub files: Vec<String>,
+
+    /// Partial stub config JSON; objects merge field by field, arrays replace, null removes
+    #[arg(long = "patch")]
+    pub patch: String,
+}
+
 /// Convert Memory enum to string
 fn memory_to_string(memory: &Memory) -> String {
     match memory {
@@ -227,22 +618,38 @@ fn memory_to_string(memory: &Memory) -> String {
         Memory::NorType1 => "nor_type1".to_string(),
         Memory::Nand => "nand".to_string(),
         Memory::NandType1 => "nand_type1".to_string(),
+        Memory::NandNobbm => "nand_nobbm".to_string(),
         Memory::NandNobbmType1 => "nand_nobbm_type1".to_string(),
+        Memory::Nand6m => "nand_6m".to_string(),
+        Memory::Nand8m => "nand_8m".to_string(),
         Memory::Sd => "sd".to_string(),
         Memory::SdType1 => "sd_type1".to_string(),
     }
 }
 
+const MEMORY_TYPES: &[&str] = &[
+    "nor",
+    "nor_type1",
+    "nand",
+    "nand_type1",
+    "nand_nobbm",
+    "nand_nobbm_type1",
+    "nand_6m",
+    "nand_8m",
+    "sd",
+    "sd_type1",
+];
+
 fn normalize_memory(memory: &str) -> Result<String> {
     let normalized = memory.to_ascii_lowercase();
-    match normalized.as_str() {
-        "nor" | "nor_type1" | "nand" | "nand_type1" | "nand_nobbm_type1" | "sd" | "sd_type1" => {
-            Ok(normalized)
-        }
-        _ => bail!(
-            "Invalid memory type '{}'. Must be one of: nor, nor_type1, nand, nand_type1, nand_nobbm_type1, sd, sd_type1",
-            memory
-        ),
+    if MEMORY_TYPES.contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        bail!(
+            "Invalid memory type '{}'. Must be one of: {}",
+            memory,
+            MEMORY_TYPES.join(", ")
+        )
     }
 }
 
@@ -269,7 +676,12 @@ pub fn merge_config(args: &Cli, config: Option<SfToolConfig>) -> Result<MergedCo
         .port
         .clone()
         .unwrap_or_else(|| base_config.port.clone());
-    let baud = args.baud.unwrap_or(base_config.baud);
+    let baud = match args.baud {
+        Some(baud) => baud,
+        None => base_config
+            .parse_baud()
+            .map_err(|e| anyhow!("Invalid baud rate in config: {}", e))?,
+    };
 
     let before = match &args.before {
         Some(b) => b.clone(),
@@ -291,8 +703,9 @@ pub fn merge_config(args: &Cli, config: Option<SfToolConfig>) -> Result<MergedCo
     let compat = args.compat.unwrap_or(base_config.compat);
     let quiet = args.quiet || base_config.quiet;
     let stub_path = args.stub.clone().or_else(|| base_config.stub_path.clone());
+    let sig_key = args.sig_key.clone().or_else(|| base_config.sig_key.clone());
     // 验证必需字段
-    if port.is_empty() {
+    if port.is_empty() && args.replay.is_none() {
         bail!("Port must be specified either via --port or in config file");
     }
 
@@ -307,9 +720,39 @@ pub fn merge_config(args: &Cli, config: Option<SfToolConfig>) -> Result<MergedCo
         compat,
         quiet,
         stub_path,
+        sig_key,
     ))
 }
 
+/// Build the timeout and retry policies from the config file and CLI, CLI args take precedence
+pub fn merge_policies(
+    args: &Cli,
+    config: Option<&SfToolConfig>,
+) -> Result<(TimeoutPolicy, RetryPolicy)> {
+    let mut timeouts = TimeoutPolicy::default();
+    let mut retry = RetryPolicy::default();
+
+    if let Some(config) = config {
+        for (key, value) in &config.timeouts {
+            timeouts
+                .set(key, *value)
+                .map_err(|e| anyhow!("Invalid timeout in config: {}", e))?;
+        }
+        if let Some(retries) = config.retries {
+            retry.retries = retries;
+        }
+    }
+
+    for spec in &args.timeouts {
+        timeouts.apply(spec)?;
+    }
+    if let Some(retries) = args.retries {
+        retry.retries = retries;
+    }
+
+    Ok((timeouts, retry))
+}
+
 /// Determine which command to execute from CLI args or config file
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
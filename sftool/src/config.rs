@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use sftool_lib::{AfterOperation, BeforeOperation, ChipType};
+use std::collections::BTreeMap;
 
+use crate::cli::Baud;
 use crate::stub_config_spec::StubConfigSpec;
 
 /// 应用程序的默认配置值
@@ -66,6 +68,12 @@ pub struct WriteFlashCommandConfig {
     pub files: Vec<WriteFlashFileConfig>,
 }
 
+/// 校验 Flash 命令配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyCommandConfig {
+    pub files: Vec<WriteFlashFileConfig>,
+}
+
 /// 读取 Flash 命令配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadFlashCommandConfig {
@@ -116,6 +124,14 @@ pub struct StubCommandConfig {
     pub read: Option<StubReadCommandConfig>,
 }
 
+/// `baud` 字段，可以是数字或字符串（例如 "auto"）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BaudSetting {
+    Rate(u32),
+    Name(String),
+}
+
 /// JSON 配置文件的根结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SfToolConfig {
@@ -125,8 +141,9 @@ pub struct SfToolConfig {
     pub memory: String,
     #[serde(default)]
     pub port: String,
+    /// 固定波特率，或 "auto" 自动协商
     #[serde(default = "default_baud")]
-    pub baud: u32,
+    pub baud: BaudSetting,
     #[serde(default = "default_before")]
     pub before: String,
     #[serde(default = "default_after")]
@@ -140,9 +157,20 @@ pub struct SfToolConfig {
     /// 外部 stub 文件路径，如果指定则优先使用外部文件而非内嵌文件
     #[serde(default)]
     pub stub_path: Option<String>,
+    /// 安全启动签名公钥（DER），仅 SF32LB55/58 使用，未指定时使用内嵌公钥
+    #[serde(default)]
+    pub sig_key: Option<String>,
+    /// 超时覆盖（毫秒），键与 `--timeout` 相同，例如 {"read": 20000}
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub timeouts: BTreeMap<String, u64>,
+    /// 幂等命令的重试次数
+    #[serde(default)]
+    pub retries: Option<u32>,
 
     // 命令 - 只能存在其中一个
     pub write_flash: Option<WriteFlashCommandConfig>,
+    #[serde(default)]
+    pub verify: Option<VerifyCommandConfig>,
     pub read_flash: Option<ReadFlashCommandConfig>,
     pub erase_flash: Option<EraseFlashCommandConfig>,
     pub erase_region: Option<EraseRegionCommandConfig>,
@@ -156,8 +184,8 @@ fn default_chip() -> String {
 fn default_memory() -> String {
     Defaults::MEMORY.to_string()
 }
-fn default_baud() -> u32 {
-    Defaults::BAUD
+fn default_baud() -> BaudSetting {
+    BaudSetting::Rate(Defaults::BAUD)
 }
 fn default_before() -> String {
     Defaults::BEFORE.to_string()
@@ -183,14 +211,18 @@ impl SfToolConfig {
             chip: Defaults::CHIP.to_string(), // 这将被要求用户提供
             memory: Defaults::MEMORY.to_string(),
             port: String::new(), // 这将被要求用户提供
-            baud: Defaults::BAUD,
+            baud: default_baud(),
             before: Defaults::BEFORE.to_string(),
             after: Defaults::AFTER.to_string(),
             connect_attempts: Defaults::CONNECT_ATTEMPTS,
             compat: Defaults::COMPAT,
             quiet: false,
             stub_path: None,
+            sig_key: None,
+            timeouts: BTreeMap::new(),
+            retries: None,
             write_flash: None,
+            verify: None,
             read_flash: None,
             erase_flash: None,
             erase_region: None,
@@ -210,6 +242,14 @@ impl SfToolConfig {
         }
     }
 
+    /// 将 baud 字段转换为波特率设置
+    pub fn parse_baud(&self) -> Result<Baud, String> {
+        match &self.baud {
+            BaudSetting::Rate(rate) => Ok(Baud::Fixed(*rate)),
+            BaudSetting::Name(name) => name.parse(),
+        }
+    }
+
     /// 将字符串转换为 before 操作枚举
     pub fn parse_before(&self) -> Result<BeforeOperation, String> {
         match self.before.as_str() {
@@ -251,6 +291,7 @@ impl SfToolConfig {
 
         let command_count = [
             self.write_flash.is_some(),
+            self.verify.is_some(),
             self.read_flash.is_some(),
             self.erase_flash.is_some(),
             self.erase_region.is_some(),
@@ -261,7 +302,7 @@ impl SfToolConfig {
             + stub_command;
 
         if command_count != 1 {
-            return Err("Configuration must contain exactly one command (write_flash, read_flash, erase_flash, erase_region, or stub)".to_string());
+            return Err("Configuration must contain exactly one command (write_flash, verify, read_flash, erase_flash, erase_region, or stub)".to_string());
         }
 
         if let Some(ref stub) = self.stub {
@@ -300,6 +341,16 @@ impl SfToolConfig {
             }
         }
 
+        if let Some(ref verify) = self.verify {
+            for file in &verify.files {
+                if let Some(ref addr) = file.address {
+                    addr.to_u32().map_err(|e| {
+                        format!("Invalid address in verify file '{}': {}", file.path, e)
+                    })?;
+                }
+            }
+        }
+
         if let Some(ref read_flash) = self.read_flash {
             for file in &read_flash.files {
                 file.address.to_u32().map_err(|e| {
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result, bail};
 
 use crate::config::SfToolConfig;
+use crate::verify_report::report_verify_results;
 
 /// Execute command from config file
 pub fn execute_config_command(
@@ -30,6 +31,28 @@ pub fn execute_config_command(
         siflitool
             .write_flash(&write_params)
             .context("Failed to execute write_flash command")
+    } else if let Some(ref verify) = config.verify {
+        let mut parsed_files = Vec::new();
+        for file in verify.files.iter() {
+            let address =
+                match &file.address {
+                    Some(addr) => Some(addr.to_u32().map_err(|e| {
+                        anyhow::anyhow!("Invalid verify address '{}': {}", addr.0, e)
+                    })?),
+                    None => None,
+                };
+            let mut parsed = sftool_lib::utils::Utils::parse_write_file(&file.path, address)
+                .with_context(|| format!("Failed to parse file {}", file.path))?;
+            parsed_files.append(&mut parsed);
+        }
+
+        let verify_params = sftool_lib::VerifyParams {
+            files: parsed_files,
+        };
+        let results = siflitool
+            .verify_flash(&verify_params)
+            .context("Failed to execute verify command")?;
+        report_verify_results(&results)
     } else if let Some(ref read_flash) = config.read_flash {
         let mut parsed_files = Vec::new();
         for file in read_flash.files.iter() {
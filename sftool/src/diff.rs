@@ -0,0 +1,226 @@
+use anyhow::{Context, Result, bail};
+use serde_json::{Value, json};
+use sftool_lib::diff::{DIFF_SECTOR_SIZE, DiffReport, compare};
+use sftool_lib::{ReadFlashFile, ReadFlashParams, SifliTool, WriteFlashFile};
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::cli::Diff;
+
+/// Maximum number of byte ranges and sector ranges listed per file in the text report.
+const MAX_LISTED_RANGES: usize = 32;
+const HEXDUMP_ROW: u32 = 16;
+
+struct FileDiff {
+    report: DiffReport,
+    expected: Vec<u8>,
+    actual: Vec<u8>,
+}
+
+fn read_local(file: &WriteFlashFile) -> Result<Vec<u8>> {
+    let mut handle = file.file.try_clone()?;
+    handle.seek(SeekFrom::Start(0))?;
+    let mut data = Vec::new();
+    handle.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn read_back(siflitool: &mut Box<dyn SifliTool>, address: u32, size: u32) -> Result<Vec<u8>> {
+    let output = tempfile::NamedTempFile::new().context("Failed to create temporary file")?;
+    let read_params = ReadFlashParams {
+        files: vec![ReadFlashFile {
+            file_path: output.path().to_string_lossy().into_owned(),
+            address,
+            size,
+        }],
+    };
+    siflitool
+        .read_flash(&read_params)
+        .with_context(|| format!("Failed to read back 0x{:08X}", address))?;
+    Ok(std::fs::read(output.path())?)
+}
+
+/// Start addresses of the first `rows` 16-byte rows that contain a difference.
+fn differing_rows(diff: &FileDiff, rows: usize) -> Vec<u32> {
+    let mut result: Vec<u32> = Vec::new();
+    for range in diff.report.ranges.iter() {
+        let first = range.address - range.address % HEXDUMP_ROW;
+        let last = range.address + range.len - 1;
+        let mut row = first;
+        while row <= last && result.len() < rows {
+            if result.last() != Some(&row) {
+                result.push(row);
+            }
+            row += HEXDUMP_ROW;
+        }
+        if result.len() >= rows {
+            break;
+        }
+    }
+    result
+}
+
+fn row_bytes(data: &[u8], offset: usize) -> String {
+    (offset..offset + HEXDUMP_ROW as usize)
+        .map(|i| match data.get(i) {
+            Some(byte) => format!("{:02X}", byte),
+            None => "--".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn hexdump_rows(diff: &FileDiff, rows: usize) -> Vec<(u32, String, String)> {
+    differing_rows(diff, rows)
+        .into_iter()
+        .map(|row| {
+            let offset = row.saturating_sub(diff.report.address) as usize;
+            (
+                row,
+                row_bytes(&diff.expected, offset),
+                row_bytes(&diff.actual, offset),
+            )
+        })
+        .collect()
+}
+
+fn print_text(diffs: &[FileDiff], hexdump: Option<usize>) {
+    for diff in diffs {
+        let report = &diff.report;
+        let end = report.address.saturating_add(report.len.saturating_sub(1));
+        if report.is_identical() {
+            println!("0x{:08X}..0x{:08X}: identical", report.address, end);
+            continue;
+        }
+        println!(
+            "0x{:08X}..0x{:08X}: {} byte(s) differ in {} range(s), {} sector(s)",
+            report.address,
+            end,
+            report.differing_bytes,
+            report.ranges.len(),
+            report.sectors.len()
+        );
+        for range in report.ranges.iter().take(MAX_LISTED_RANGES) {
+            println!(
+                "  0x{:08X}..0x{:08X} ({} bytes)",
+                range.address,
+                range.address + range.len - 1,
+                range.len
+            );
+        }
+        if report.ranges.len() > MAX_LISTED_RANGES {
+            println!(
+                "  ... {} more range(s)",
+                report.ranges.len() - MAX_LISTED_RANGES
+            );
+        }
+        let sector_ranges = report.sector_ranges();
+        let sectors = sector_ranges
+            .iter()
+            .take(MAX_LISTED_RANGES)
+            .map(|range| {
+                if range.len == DIFF_SECTOR_SIZE {
+                    format!("0x{:08X}", range.address)
+                } else {
+                    format!(
+                        "0x{:08X}..0x{:08X}",
+                        range.address,
+                        range.address + (range.len - 1)
+                    )
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        if sector_ranges.len() > MAX_LISTED_RANGES {
+            println!(
+                "  sectors: {} ... {} more",
+                sectors,
+                sector_ranges.len() - MAX_LISTED_RANGES
+            );
+        } else {
+            println!("  sectors: {}", sectors);
+        }
+
+        if let Some(rows) = hexdump {
+            for (row, expected, actual) in hexdump_rows(diff, rows) {
+                println!("  0x{:08X}  expected: {}", row, expected);
+                println!("              actual:   {}", actual);
+            }
+        }
+    }
+}
+
+fn to_json(diffs: &[FileDiff], hexdump: Option<usize>) -> Value {
+    let files = diffs
+        .iter()
+        .map(|diff| {
+            let report = &diff.report;
+            let mut entry = json!({
+                "address": format!("0x{:08X}", report.address),
+                "size": report.len,
+                "identical": report.is_identical(),
+                "differing_bytes": report.differing_bytes,
+                "ranges": report.ranges.iter().map(|range| json!({
+                    "address": format!("0x{:08X}", range.address),
+                    "size": range.len,
+                })).collect::<Vec<_>>(),
+                "sectors": report.sectors.iter()
+                    .map(|sector| format!("0x{:08X}", sector))
+                    .collect::<Vec<_>>(),
+            });
+            if let Some(rows) = hexdump {
+                entry["hexdump"] = hexdump_rows(diff, rows)
+                    .into_iter()
+                    .map(|(row, expected, actual)| {
+                        json!({
+                            "address": format!("0x{:08X}", row),
+                            "expected": expected,
+                            "actual": actual,
+                        })
+                    })
+                    .collect();
+            }
+            entry
+        })
+        .collect::<Vec<_>>();
+    json!({ "files": files })
+}
+
+/// Read back every file's target range, compare it with the local data and
+/// report where they differ. Fails if any file differs.
+pub fn execute_diff(params: &Diff, siflitool: &mut Box<dyn SifliTool>) -> Result<()> {
+    let mut files = Vec::new();
+    for file_str in params.files.iter() {
+        let mut parsed_files = sftool_lib::utils::Utils::parse_file_info(file_str)
+            .with_context(|| format!("Failed to parse file {}", file_str))?;
+        files.append(&mut parsed_files);
+    }
+
+    let mut diffs = Vec::new();
+    for file in files.iter() {
+        let expected = read_local(file)?;
+        let actual = read_back(siflitool, file.address, expected.len() as u32)?;
+        diffs.push(FileDiff {
+            report: compare(file.address, &expected, &actual),
+            expected,
+            actual,
+        });
+    }
+
+    if params.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&to_json(&diffs, params.hexdump))?
+        );
+    } else {
+        print_text(&diffs, params.hexdump);
+    }
+
+    let differing = diffs
+        .iter()
+        .filter(|diff| !diff.report.is_identical())
+        .count();
+    if differing > 0 {
+        bail!("{} of {} file(s) differ from flash", differing, diffs.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use sftool_lib::SifliTool;
+use sftool_lib::efuse::{self, EfuseData};
+
+use crate::cli::EfuseRead;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+fn print_text(data: &EfuseData) {
+    println!("UID:    {}", hex(&data.uid()));
+    if let Some(pid) = data.pid() {
+        println!("PID:    0x{:02X}", pid);
+    }
+    if let Some(adc) = data.adc_calibration() {
+        println!(
+            "ADC:    {} @ {} mV, {} @ {} mV, vbat {} @ {} mV",
+            adc.vol10, adc.low_mv, adc.vol25, adc.high_mv, adc.vbat_reg, adc.vbat_mv
+        );
+    }
+    for (index, bank) in data.banks.iter().enumerate() {
+        let words: Vec<String> = bank
+            .chunks_exact(4)
+            .map(|word| format!("{:08X}", u32::from_le_bytes(word.try_into().unwrap())))
+            .collect();
+        println!("Bank {}: {}", index, words.join(" "));
+    }
+}
+
+/// Read the eFuse banks over the debug channel and print the decoded fields.
+///
+/// Only the fields the RAM stubs read are decoded: the UID, the SF32LB52 ADC
+/// calibration and the SF32LB57 package ID. Security configuration and other
+/// trims are left in the raw banks.
+///
+/// Bank words are printed as little-endian 32-bit values, the order the
+/// registers are read in; the JSON output carries the raw bytes as hex.
+pub fn execute_efuse_read(params: &EfuseRead, siflitool: &mut Box<dyn SifliTool>) -> Result<()> {
+    let data = efuse::read_efuse(siflitool.as_mut()).context("Failed to read eFuse")?;
+
+    if params.json {
+        let output = json!({
+            "uid": hex(&data.uid()),
+            "pid": data.pid(),
+            "adc_calibration": data.adc_calibration().map(|adc| json!({
+                "vol10": adc.vol10,
+                "low_mv": adc.low_mv,
+                "vol25": adc.vol25,
+                "high_mv": adc.high_mv,
+                "vbat_reg": adc.vbat_reg,
+                "vbat_mv": adc.vbat_mv,
+            })),
+            "banks": data.banks.iter().map(|bank| hex(bank)).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    print_text(&data);
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use anyhow::{Context, Result, bail};
+use serde_json::json;
+use sftool_lib::factory_cali::{self, CaliOutcome, CaliStep};
+use sftool_lib::{ChipType, SifliTool};
+
+use crate::cli::FactoryCali;
+
+fn parse_numbers<const N: usize>(value: &str, option: &str) -> Result<[u32; N]> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    if parts.len() != N {
+        bail!(
+            "{} expects {} comma-separated values, got '{}'",
+            option,
+            N,
+            value
+        );
+    }
+    let mut numbers = [0u32; N];
+    for (slot, part) in numbers.iter_mut().zip(parts) {
+        *slot = sftool_lib::utils::Utils::str_to_u32(part)
+            .map_err(|e| anyhow::anyhow!("{} value '{}': {}", option, part, e))?;
+    }
+    Ok(numbers)
+}
+
+fn parse_steps(params: &FactoryCali) -> Result<Vec<CaliStep>> {
+    let mut steps = Vec::new();
+    if let Some(crystal) = &params.crystal {
+        let [pin, ppm, timeout_s] = parse_numbers::<3>(crystal, "--crystal")?;
+        let pin = u8::try_from(pin).context("--crystal pin out of range")?;
+        steps.push(CaliStep::Crystal {
+            pin,
+            ppm,
+            timeout_s,
+        });
+    }
+    if let Some(battery) = &params.battery {
+        let [reference_mv, range_mv, tolerance_mv, upper_kohm, lower_kohm] =
+            parse_numbers::<5>(battery, "--battery")?;
+        steps.push(CaliStep::Battery {
+            reference_mv,
+            range_mv,
+            tolerance_mv,
+            upper_kohm,
+            lower_kohm,
+        });
+    }
+    Ok(steps)
+}
+
+/// Load the calibration image at its link address, run the requested steps on
+/// it and print the results.
+///
+/// The image stores the crystal result in OTP itself; `--output` only saves a
+/// JSON report of what each step printed.
+pub fn execute_factory_cali(
+    params: &FactoryCali,
+    chip: &ChipType,
+    tool: &mut Box<dyn SifliTool>,
+) -> Result<()> {
+    let steps = parse_steps(params)?;
+    factory_cali::start_factory_cali(tool.as_mut(), chip)
+        .context("Failed to start the calibration image")?;
+
+    let mut outcomes: Vec<CaliOutcome> = Vec::new();
+    for step in &steps {
+        let (outcome, log) = factory_cali::run_step(tool.as_mut(), step)
+            .with_context(|| format!("{} calibration did not report a result", step.name()))?;
+        if !outcome.passed {
+            eprint!("{}", log);
+        }
+        let status = if outcome.passed { "PASS" } else { "FAIL" };
+        match (outcome.value, outcome.measured_mv) {
+            (Some(value), Some(measured_mv)) => println!(
+                "{:<8} {} (value {}, measured {} mV)",
+                step.name(),
+                status,
+                value,
+                measured_mv
+            ),
+            (Some(value), None) => println!(
+                "{:<8} {} (value 0x{:X} / {})",
+                step.name(),
+                status,
+                value,
+                value
+            ),
+            _ => println!("{:<8} {}", step.name(), status),
+        }
+        outcomes.push(outcome);
+    }
+
+    if let Some(output) = &params.output {
+        let report = outcomes
+            .iter()
+            .map(|outcome| {
+                json!({
+                    "step": outcome.step.name(),
+                    "passed": outcome.passed,
+                    "value": outcome.value,
+                    "measured_mv": outcome.measured_mv,
+                })
+            })
+            .collect::<Vec<_>>();
+        std::fs::write(output, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Failed to write calibration report to '{}'", output))?;
+    }
+
+    if let Some(failed) = outcomes.iter().find(|outcome| !outcome.passed) {
+        bail!("{} calibration failed", failed.step.name());
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use serde_json::json;
+use sftool_lib::SifliTool;
+use sftool_lib::flash_id::FlashId;
+use sftool_lib::stub_config::FlashMedia;
+
+use crate::cli::FlashIdCommand;
+
+fn media_name(media: FlashMedia) -> &'static str {
+    match media {
+        FlashMedia::Nor => "nor",
+        FlashMedia::Nand => "nand",
+    }
+}
+
+fn print_text(flashes: &[FlashId]) {
+    for (index, flash) in flashes.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!(
+            "Media:           {}",
+            media_name(flash.media).to_uppercase()
+        );
+        if let Some(part) = flash.part() {
+            println!("Part:            {}", part.name);
+        }
+        println!("Driver index:    {}", flash.driver_index);
+        println!("Manufacturer ID: 0x{:02X}", flash.manufacturer_id);
+        println!("Device type:     0x{:02X}", flash.device_type);
+        println!("Density ID:      0x{:02X}", flash.density_id);
+        match flash.capacity_bytes {
+            Some(capacity) => println!(
+                "Capacity:        0x{:08X} ({} MiB)",
+                capacity,
+                capacity / (1024 * 1024)
+            ),
+            None => println!("Capacity:        unknown"),
+        }
+    }
+}
+
+/// Report the flash parts the stub detected while starting.
+///
+/// The JSON output uses the same keys as the `flash` entries of a stub
+/// config, so it can be pasted into a `stub write` config as is.
+pub fn execute_flash_id(params: &FlashIdCommand, siflitool: &dyn SifliTool) -> Result<()> {
+    let flashes = siflitool.flash_ids()?;
+
+    if params.json {
+        let entries = flashes
+            .iter()
+            .map(|flash| {
+                let mut entry = json!({
+                    "media": media_name(flash.media),
+                    "driver_index": flash.driver_index,
+                    "manufacturer_id": flash.manufacturer_id,
+                    "device_type": flash.device_type,
+                    "density_id": flash.density_id,
+                    "flags": 0,
+                    "capacity_bytes": flash.capacity_bytes,
+                });
+                if let Some(part) = flash.part() {
+                    entry["part"] = json!(part.name);
+                }
+                entry
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "flash": entries }))?
+        );
+    } else {
+        print_text(&flashes);
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use sftool_lib::dfu_image::{DFU_BLOCK_SIZE, DfuImage};
+
+use crate::cli::ImageInfo;
+
+/// Warn when the header's declared length disagrees with the chunks; the
+/// length key is only known for the bundled stubs, so this is not an error.
+pub fn warn_declared_size(name: &str, image: &DfuImage) {
+    if !image.declared_size_matches() {
+        eprintln!(
+            "Warning: '{}' header declares {} bytes but carries {} bytes; it may be truncated or signed with a different header key",
+            name,
+            image.declared_payload_size(),
+            image.payload_size()
+        );
+    }
+}
+
+pub fn execute_image_info(params: &ImageInfo) -> Result<()> {
+    let (name, data) = match (&params.embedded, &params.file) {
+        (Some(key), _) => (key.clone(), sftool_lib::load_embedded_stub(key)?),
+        (None, Some(file)) => (
+            file.clone(),
+            std::fs::read(file).with_context(|| format!("Failed to read '{}'", file))?,
+        ),
+        (None, None) => unreachable!("clap requires a file or --embedded"),
+    };
+    let image =
+        DfuImage::parse(&data).with_context(|| format!("'{}' is not a valid DFU image", name))?;
+    warn_declared_size(&name, &image);
+    let crc32 = sftool_lib::utils::Utils::calculate_crc32(&data);
+
+    if params.json {
+        #[derive(serde::Serialize)]
+        struct ImageInfoOutput<'a> {
+            file: &'a str,
+            size: usize,
+            crc32: String,
+            header_size: usize,
+            header_payload_size: usize,
+            header_chunks: usize,
+            chunks: usize,
+            block_size: usize,
+            last_block_size: usize,
+            payload_size: usize,
+        }
+
+        let output = ImageInfoOutput {
+            file: &name,
+            size: data.len(),
+            crc32: format!("0x{:08X}", crc32),
+            header_size: image.header().len(),
+            header_payload_size: image.declared_payload_size(),
+            header_chunks: image.declared_payload_size().div_ceil(DFU_BLOCK_SIZE),
+            chunks: image.chunk_count(),
+            block_size: DFU_BLOCK_SIZE,
+            last_block_size: image.last_block_size(),
+            payload_size: image.payload_size(),
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!("File:        {}", name);
+    println!("Size:        {} bytes", data.len());
+    println!("CRC32:       0x{:08X}", crc32);
+    println!(
+        "Header:      {} bytes, declares {} bytes in {} chunks",
+        image.header().len(),
+        image.declared_payload_size(),
+        image.declared_payload_size().div_ceil(DFU_BLOCK_SIZE)
+    );
+    println!(
+        "Chunks:      {} x {} bytes (last block {} bytes)",
+        image.chunk_count(),
+        DFU_BLOCK_SIZE,
+        image.last_block_size()
+    );
+    println!("Payload:     {} bytes", image.payload_size());
+    Ok(())
+}
@@ -1,24 +1,53 @@
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use clap::Parser;
-use sftool_lib::{SifliToolBase, create_sifli_tool};
+use sftool_lib::dfu_image::DfuImage;
+use sftool_lib::speed::auto_baud_candidates;
+use sftool_lib::transcript::{ReplaySerialPort, TranscriptMode};
+use sftool_lib::{BeforeOperation, SifliToolBase, create_sifli_tool};
 
+mod baud_cache;
 mod cli;
 mod config;
 mod config_exec;
+mod diff;
+mod efuse;
+mod factory_cali;
+mod flash_id;
+mod image_ops;
+mod monitor;
 mod progress;
+mod reg;
+mod rtt;
+mod run_ram;
 mod serial;
 mod stub_config_spec;
 mod stub_ops;
+mod verify_report;
 
-use cli::{Cli, CommandSource, Commands, StubAction, get_command_source, merge_config};
-use config::SfToolConfig;
+use cli::{
+    Baud, Cli, CommandSource, Commands, EfuseAction, ImageAction, RegAction, StubAction,
+    get_command_source, merge_config, merge_policies,
+};
+use config::{Defaults, SfToolConfig};
 use config_exec::execute_config_command;
+use diff::execute_diff;
+use efuse::execute_efuse_read;
+use factory_cali::execute_factory_cali;
+use flash_id::execute_flash_id;
+use image_ops::{execute_image_info, warn_declared_size};
+use monitor::execute_monitor;
 use progress::create_progress_sink;
+use reg::{execute_reg_read, execute_reg_write};
+use rtt::execute_rtt;
+use run_ram::{execute_run_ram, run_ram_elf};
 use serial::{check_port_available, normalize_port_name};
 use stub_ops::{
-    chip_key, execute_stub_clear, execute_stub_config_command, execute_stub_read,
-    execute_stub_write, load_stub_config_spec, prepare_stub_path,
+    chip_key, execute_stub_clear, execute_stub_config_command, execute_stub_diff,
+    execute_stub_extract, execute_stub_list, execute_stub_merge, execute_stub_parts,
+    execute_stub_read, execute_stub_read_embedded, execute_stub_write, load_stub_config_spec,
+    prepare_stub_path,
 };
+use verify_report::report_verify_results;
 
 fn main() -> Result<()> {
     // Initialize tracing, set log level from environment variable
@@ -55,22 +84,52 @@ fn main() -> Result<()> {
             match &stub.action {
                 StubAction::Write(params) => {
                     let stub_spec = load_stub_config_spec(&params.stub_config)?;
-                    execute_stub_write(&params.files, &stub_spec)?;
+                    execute_stub_write(&params.files, &stub_spec, args.chip.as_ref())?;
                 }
                 StubAction::Clear(params) => {
                     execute_stub_clear(&params.files)?;
                 }
-                StubAction::Read(params) => {
-                    execute_stub_read(&params.files, params.output.as_deref())?;
+                StubAction::Read(params) => match &params.embedded {
+                    Some(name) => execute_stub_read_embedded(name, params.output.as_deref())?,
+                    None => execute_stub_read(&params.files, params.output.as_deref())?,
+                },
+                StubAction::Parts => {
+                    execute_stub_parts();
+                }
+                StubAction::List(params) => {
+                    execute_stub_list(params.json)?;
+                }
+                StubAction::Extract(params) => {
+                    execute_stub_extract(&params.key, &params.output)?;
+                }
+                StubAction::Diff(params) => {
+                    if !execute_stub_diff(&params.a, &params.b)? {
+                        std::process::exit(1);
+                    }
+                }
+                StubAction::Merge(params) => {
+                    execute_stub_merge(&params.files, &params.patch, args.chip.as_ref())?;
                 }
             }
             return Ok(());
         }
+        CommandSource::Cli(Commands::Image(image)) => {
+            match &image.action {
+                ImageAction::Info(params) => execute_image_info(params)?,
+            }
+            return Ok(());
+        }
         CommandSource::Config(cfg) => {
             if let Some(stub) = &cfg.stub
                 && (stub.write.is_some() || stub.clear.is_some() || stub.read.is_some())
             {
-                execute_stub_config_command(cfg)?;
+                let chip = match &args.chip {
+                    Some(chip) => chip.clone(),
+                    None => cfg
+                        .parse_chip_type()
+                        .map_err(|e| anyhow!("Invalid chip type in config: {}", e))?,
+                };
+                execute_stub_config_command(cfg, Some(&chip))?;
                 return Ok(());
             }
         }
@@ -89,8 +148,31 @@ fn main() -> Result<()> {
         compat,
         quiet,
         stub_path,
+        sig_key,
     ) = merge_config(&args, config.clone()).context("Configuration error")?;
+    let (timeouts, retry) =
+        merge_policies(&args, config.as_ref()).context("Configuration error")?;
+
+    if let CommandSource::Cli(Commands::Monitor(params)) = &command_source {
+        let port = normalize_port_name(&port);
+        check_port_available(&port)?;
+        let Baud::Fixed(baud) = baud else {
+            bail!("--baud auto is not supported by monitor, pass the firmware's console baud rate");
+        };
+        return execute_monitor(&port, baud, params.elf.as_deref());
+    }
 
+    let custom_stub = stub_path.is_some();
+    if let Some(path) = &stub_path
+        && matches!(
+            chip_type,
+            sftool_lib::ChipType::SF32LB55 | sftool_lib::ChipType::SF32LB58
+        )
+        && let Ok(data) = std::fs::read(path)
+        && let Ok(image) = DfuImage::parse(&data)
+    {
+        warn_declared_size(path, &image);
+    }
     let (stub_path, _stub_temp) = prepare_stub_path(
         args.stub_config_json.as_deref(),
         &chip_type,
@@ -108,35 +190,116 @@ fn main() -> Result<()> {
     let port = normalize_port_name(&port);
 
     // Check if the specified serial port exists, exit early if not
-    check_port_available(&port)?;
+    if args.replay.is_none() {
+        check_port_available(&port)?;
+    }
 
-    let mut siflitool = create_sifli_tool(
-        chip_type,
-        SifliToolBase::new_with_external_stub(
-            port.clone(),
-            before,
-            memory_type.to_lowercase(),
-            baud,
-            connect_attempts,
-            compat,
-            if quiet {
-                sftool_lib::progress::no_op_progress_sink()
-            } else {
-                create_progress_sink()
-            },
-            stub_path,
-        ),
+    let mut base = SifliToolBase::new_with_external_stub(
+        port.clone(),
+        before,
+        memory_type.to_lowercase(),
+        match baud {
+            Baud::Fixed(baud) => baud,
+            Baud::Auto => Defaults::BAUD,
+        },
+        connect_attempts,
+        compat,
+        if quiet {
+            sftool_lib::progress::no_op_progress_sink()
+        } else {
+            create_progress_sink()
+        },
+        stub_path,
     );
+    let replay = args
+        .replay
+        .as_deref()
+        .map(|path| {
+            ReplaySerialPort::open(path)
+                .with_context(|| format!("Failed to load transcript '{}'", path))
+        })
+        .transpose()?;
+    base.transcript = match (&args.record, &replay) {
+        (_, Some(replay)) => Some(TranscriptMode::Replay(replay.clone())),
+        (Some(path), None) => Some(TranscriptMode::Record(path.into())),
+        (None, None) => None,
+    };
+    base.timeouts = timeouts;
+    base.retry = retry;
+    if let Some(path) = &sig_key {
+        if sftool_lib::secure_boot::default_sig_pub_key_name(&chip_type).is_none() {
+            bail!("--sig-key only applies to SF32LB55 and SF32LB58");
+        }
+        sftool_lib::secure_boot::load_sig_pub_key(&chip_type, Some(path))
+            .with_context(|| format!("Invalid signature public key '{}'", path))?;
+    }
+    base.sig_pub_key_path = sig_key;
 
-    if baud != 1000000 {
-        siflitool
-            .set_speed(baud)
-            .with_context(|| format!("Failed to set baud rate to {}", baud))?;
+    // dfu_write talks to the ROM, the others to the debug channel directly, so
+    // the stub must not be started; factory_cali loads its own image in its place
+    let runs_without_stub = matches!(
+        &command_source,
+        CommandSource::Cli(
+            Commands::DfuWrite(_)
+                | Commands::Efuse(_)
+                | Commands::Rtt(_)
+                | Commands::RunRam(_)
+                | Commands::Reg(_)
+                | Commands::FactoryCali(_)
+        )
+    );
+    if runs_without_stub {
+        base.before = BeforeOperation::NoResetNoSync;
+    }
+    if chip_type == sftool_lib::ChipType::SF32LB55
+        && base.sig_pub_key_path.is_none()
+        && !runs_without_stub
+    {
+        // Only the 58X key ships with sftool; it matches the embedded SF32LB55 stubs
+        // but says nothing about a stub the user built and signed
+        if custom_stub {
+            bail!(
+                "SF32LB55 with a custom --stub needs --sig-key <DER> for the key it was signed with"
+            );
+        }
+        eprintln!(
+            "Warning: SF32LB55 has no embedded public key of its own, using 58X_sig_pub.der; pass --sig-key if the chip rejects the stub"
+        );
+    }
+    let mut siflitool = create_sifli_tool(chip_type.clone(), base).with_context(|| {
+        format!(
+            "Failed to connect to {} on '{}'",
+            chip_key(&chip_type),
+            port
+        )
+    })?;
+
+    match baud {
+        _ if runs_without_stub => {}
+        Baud::Fixed(baud) if baud != Defaults::BAUD => {
+            siflitool
+                .set_speed(baud)
+                .with_context(|| format!("Failed to set baud rate to {}", baud))?;
+        }
+        Baud::Fixed(_) => {}
+        Baud::Auto => {
+            let candidates = auto_baud_candidates(baud_cache::cached_baud(&port));
+            let baud = siflitool
+                .negotiate_speed(&candidates)
+                .context("Failed to negotiate baud rate")?;
+            if args.replay.is_none() {
+                baud_cache::remember_baud(&port, baud);
+            }
+            if !quiet {
+                eprintln!("Using baud rate {}", baud);
+            }
+        }
     }
 
+    let mut monitor_after = None;
     match command_source {
         CommandSource::Cli(command) => match command {
-            Commands::Stub(_) | Commands::Config(_) => {
+            Commands::Stub(_) | Commands::Config(_) | Commands::Monitor(_) | Commands::Image(_) => {
                 // handled earlier
             }
             Commands::WriteFlash(params) => {
@@ -157,6 +320,26 @@ fn main() -> Result<()> {
                     .write_flash(&write_params)
                     .context("Failed to execute write_flash command")?;
             }
+            Commands::Verify(params) => {
+                let mut files = Vec::new();
+                for file_str in params.files.iter() {
+                    let mut parsed_files = sftool_lib::utils::Utils::parse_file_info(file_str)
+                        .with_context(|| format!("Failed to parse file {}", file_str))?;
+                    files.append(&mut parsed_files);
+                }
+
+                let verify_params = sftool_lib::VerifyParams { files };
+                let results = siflitool
+                    .verify_flash(&verify_params)
+                    .context("Failed to execute verify command")?;
+                report_verify_results(&results)?;
+            }
+            Commands::Diff(params) => {
+                execute_diff(&params, &mut siflitool)?;
+            }
+            Commands::FlashId(params) => {
+                execute_flash_id(&params, siflitool.as_ref())?;
+            }
             Commands::ReadFlash(params) => {
                 let mut files = Vec::new();
                 for file_str in params.files.iter() {
@@ -192,17 +375,106 @@ fn main() -> Result<()> {
                     .erase_region(&erase_region_params)
                     .context("Failed to execute erase_region command")?;
             }
+            Commands::FactoryCali(params) => {
+                execute_factory_cali(&params, &chip_type, &mut siflitool)?;
+            }
+            Commands::Rtt(params) => execute_rtt(&params, &chip_type, &mut siflitool)?,
+            Commands::RunRam(params) => {
+                execute_run_ram(&params, &mut siflitool)?;
+                if params.monitor {
+                    monitor_after = Some(params);
+                }
+            }
+            Commands::Reg(reg) => match &reg.action {
+                RegAction::Read(params) => execute_reg_read(params, &mut siflitool)?,
+                RegAction::Write(params) => execute_reg_write(params, &mut siflitool)?,
+            },
+            Commands::Efuse(efuse) => match &efuse.action {
+                EfuseAction::Read(params) => execute_efuse_read(params, &mut siflitool)?,
+            },
+            Commands::DfuWrite(params) => {
+                let files = parse_dfu_files(&params.files)?;
+                sftool_lib::dfu_write::dfu_write(
+                    siflitool.as_mut(),
+                    &sftool_lib::DfuWriteParams { files },
+                )
+                .context("Failed to execute dfu_write command")?;
+            }
+            Commands::VerifyBlank(params) => {
+                let mut regions = Vec::new();
+                for region_str in params.region.iter() {
+                    let parsed_region = sftool_lib::utils::Utils::parse_erase_region(region_str)
+                        .with_context(|| format!("Failed to parse region {}", region_str))?;
+                    regions.push(parsed_region);
+                }
+
+                let blank_params = sftool_lib::EraseRegionParams { regions };
+                siflitool
+                    .verify_blank(&blank_params)
+                    .context("Failed to execute verify_blank command")?;
+            }
         },
         CommandSource::Config(config) => {
             execute_config_command(&config, &mut siflitool)?;
         }
     }
 
-    if after.requires_soft_reset() {
+    // Soft reset is a stub command
+    if after.requires_soft_reset() && !runs_without_stub {
         siflitool
             .soft_reset()
             .context("Failed to perform post-operation soft reset")?;
     }
 
+    if let Some(replay) = &replay {
+        check_replay(replay)?;
+    }
+
+    if let Some(params) = monitor_after {
+        // Release the port so the monitor can reopen it at the console baud rate
+        drop(siflitool);
+        return execute_monitor(&port, params.monitor_baud, run_ram_elf(&params));
+    }
+
     Ok(())
 }
+
+/// Fail when a replayed session diverged from the transcript or stopped early
+fn check_replay(replay: &ReplaySerialPort) -> Result<()> {
+    let mismatches = replay.mismatches();
+    let finished = replay.is_finished();
+    eprintln!(
+        "Replay: {} mismatch(es), transcript {}",
+        mismatches,
+        if finished {
+            "fully consumed"
+        } else {
+            "not fully consumed"
+        }
+    );
+    if mismatches > 0 || !finished {
+        bail!("replay diverged from the recorded transcript");
+    }
+    Ok(())
+}
+
+/// Parse `dfu_write` arguments of the form `<filename@flash_id>`
+fn parse_dfu_files(specs: &[String]) -> Result<Vec<sftool_lib::DfuWriteFile>> {
+    specs
+        .iter()
+        .map(|spec| {
+            let (path, flash_id) = spec
+                .rsplit_once('@')
+                .ok_or_else(|| anyhow!("'{}' is missing @flash_id", spec))?;
+            let flash_id = sftool_lib::utils::Utils::str_to_u32(flash_id)
+                .ok()
+                .and_then(|id| u8::try_from(id).ok())
+                .ok_or_else(|| anyhow!("Invalid flash id '{}' in '{}'", flash_id, spec))?;
+            let data = std::fs::read(path).with_context(|| format!("Failed to read '{}'", path))?;
+            if let Ok(image) = DfuImage::parse(&data) {
+                warn_declared_size(path, &image);
+            }
+            Ok(sftool_lib::DfuWriteFile { flash_id, data })
+        })
+        .collect()
+}
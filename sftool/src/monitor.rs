@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use sftool_lib::common::port::open_serial;
+use sftool_lib::symbolize::{Symbolizer, partial_token_start};
+use std::io::{ErrorKind, Read, Write};
+use std::time::Duration;
+
+/// Open the serial port and print everything the firmware sends.
+///
+/// When an ELF file is given, hex addresses inside its text sections are
+/// annotated with `<function+offset>` so crash backtraces can be read directly.
+pub fn execute_monitor(port_name: &str, baud: u32, elf: Option<&str>) -> Result<()> {
+    let symbolizer = match elf {
+        Some(path) => {
+            let symbolizer = Symbolizer::from_file(path)
+                .with_context(|| format!("Failed to load symbols from '{}'", path))?;
+            if symbolizer.is_empty() {
+                eprintln!("Warning: no function symbols found in '{}'", path);
+            }
+            Some(symbolizer)
+        }
+        None => None,
+    };
+
+    let mut port = open_serial(port_name, baud, Duration::from_millis(100))
+        .with_context(|| format!("Failed to open serial port '{}'", port_name))?;
+    port.write_request_to_send(false)?;
+
+    eprintln!("--- sftool monitor on {} ({} baud) ---", port_name, baud);
+
+    let stdout = std::io::stdout();
+    let emit = |bytes: &[u8]| -> Result<()> {
+        let text = String::from_utf8_lossy(bytes);
+        let text = match &symbolizer {
+            Some(symbolizer) => symbolizer.annotate_line(&text),
+            None => text.into_owned(),
+        };
+        let mut out = stdout.lock();
+        out.write_all(text.as_bytes())?;
+        out.flush()?;
+        Ok(())
+    };
+
+    let mut line = Vec::new();
+    let mut held_back = false;
+    let mut buffer = [0u8; 1024];
+    loop {
+        let count = match port.read(&mut buffer) {
+            Ok(0) => continue,
+            Ok(count) => count,
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => {
+                // Flush a partial line (e.g. a shell prompt) when the device goes quiet.
+                // A trailing word may be half of an address, so it is kept for one more
+                // quiet period in case the rest of the line is still on its way.
+                if !line.is_empty() {
+                    let end = if symbolizer.is_some() && !held_back {
+                        partial_token_start(&line)
+                    } else {
+                        line.len()
+                    };
+                    if end > 0 {
+                        emit(&line[..end])?;
+                        line.drain(..end);
+                    }
+                    held_back = !line.is_empty();
+                }
+                continue;
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e).context("Failed to read from serial port"),
+        };
+
+        held_back = false;
+        for &byte in &buffer[..count] {
+            line.push(byte);
+            if byte == b'\n' {
+                emit(&line)?;
+                line.clear();
+            }
+        }
+    }
+}
@@ -61,6 +61,10 @@ impl DefaultProgressFormatter {
             },
             ProgressOperation::EraseAllRegions => Some("Erasing all flash regions...".to_string()),
             ProgressOperation::Verify { .. } => Some("Verifying data...".to_string()),
+            ProgressOperation::VerifyBlank { address, len } => Some(format!(
+                "Checking region at 0x{:08X} (size: 0x{:08X}) is blank...",
+                address, len
+            )),
             ProgressOperation::CheckRedownload { address, .. } => Some(format!(
                 "Checking whether a re-download is necessary at address 0x{:08X}...",
                 address
@@ -71,6 +75,12 @@ impl DefaultProgressFormatter {
             ProgressOperation::ReadFlash { address, .. } => {
                 Some(format!("Reading from 0x{:08X}...", address))
             }
+            ProgressOperation::DfuWrite { flash_id, .. } => {
+                Some(format!("DFU download to flash id {}...", flash_id))
+            }
+            ProgressOperation::LoadRam { address, .. } => {
+                Some(format!("Loading into RAM at 0x{:08X}...", address))
+            }
         }
     }
 
@@ -146,6 +156,16 @@ impl DefaultProgressFormatter {
                 ProgressStatus::Aborted => Some("Aborted".to_string()),
                 _ => None,
             },
+            ProgressOperation::VerifyBlank { address, len } => match status {
+                ProgressStatus::Success => Some(format!(
+                    "Region 0x{:08X}..0x{:08X} is blank",
+                    address,
+                    address.saturating_add(len.saturating_sub(1))
+                )),
+                ProgressStatus::Failed(detail) => Some(format!("Blank check failed: {}", detail)),
+                ProgressStatus::Aborted => Some("Aborted".to_string()),
+                _ => None,
+            },
             ProgressOperation::CheckRedownload { .. } => match status {
                 ProgressStatus::Skipped => Some("No need to re-download, skip!".to_string()),
                 ProgressStatus::Required => Some("Need to re-download".to_string()),
@@ -171,6 +191,24 @@ impl DefaultProgressFormatter {
                 ProgressStatus::Aborted => Some("Aborted".to_string()),
                 _ => None,
             },
+            ProgressOperation::DfuWrite { flash_id, size } => match status {
+                ProgressStatus::Success => Some(format!(
+                    "DFU downloaded {} bytes to flash id {}",
+                    size, flash_id
+                )),
+                ProgressStatus::Failed(detail) => Some(format!("DFU download failed: {}", detail)),
+                ProgressStatus::Aborted => Some("Aborted".to_string()),
+                _ => None,
+            },
+            ProgressOperation::LoadRam { address, size } => match status {
+                ProgressStatus::Success => Some(format!(
+                    "Loaded {} bytes into RAM at 0x{:08X}",
+                    size, address
+                )),
+                ProgressStatus::Failed(detail) => Some(format!("RAM load failed: {}", detail)),
+                ProgressStatus::Aborted => Some("Aborted".to_string()),
+                _ => None,
+            },
         }
     }
 }
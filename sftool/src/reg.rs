@@ -0,0 +1,131 @@
+use anyhow::{Context, Result, bail};
+use serde_json::json;
+use sftool_lib::SifliTool;
+use sftool_lib::reg;
+use sftool_lib::svd::{Device, RegisterRef};
+use sftool_lib::utils::Utils;
+
+use crate::cli::{RegRead, RegWrite};
+
+fn load_device(path: &str) -> Result<Device> {
+    Device::from_file(path).with_context(|| format!("Failed to load SVD file '{}'", path))
+}
+
+fn resolve<'a>(device: &'a Device, name: &str) -> Result<RegisterRef<'a>> {
+    let target = device.resolve(name)?;
+    // The debug channel only does word accesses
+    if target.register.size != 32 {
+        bail!(
+            "{} is a {}-bit register, only 32-bit registers are supported",
+            target.name(),
+            target.register.size
+        );
+    }
+    Ok(target)
+}
+
+fn print_text(target: &RegisterRef, value: u32, arrow: &str) {
+    match target.field {
+        Some(field) => {
+            let field_value = field.extract(value);
+            println!(
+                "{} @ 0x{:08X} {} {} 0x{:X} ({})",
+                target.name(),
+                target.address(),
+                field.bit_range(),
+                arrow,
+                field_value,
+                field_value
+            );
+        }
+        None => {
+            println!(
+                "{} @ 0x{:08X} {} 0x{:08X}",
+                target.name(),
+                target.address(),
+                arrow,
+                value
+            );
+            let width = target
+                .register
+                .fields
+                .iter()
+                .map(|field| field.name.len())
+                .max()
+                .unwrap_or(0);
+            for field in &target.register.fields {
+                println!(
+                    "  {:<width$} {:<8} = 0x{:X}",
+                    field.name,
+                    field.bit_range(),
+                    field.extract(value),
+                    width = width
+                );
+            }
+        }
+    }
+}
+
+fn to_json(target: &RegisterRef, value: u32) -> serde_json::Value {
+    match target.field {
+        Some(field) => json!({
+            "name": target.name(),
+            "address": format!("0x{:08X}", target.address()),
+            "bits": field.bit_range(),
+            "value": field.extract(value),
+        }),
+        None => json!({
+            "name": target.name(),
+            "address": format!("0x{:08X}", target.address()),
+            "value": format!("0x{:08X}", value),
+            "fields": target.register.fields.iter().map(|field| json!({
+                "name": field.name,
+                "bits": field.bit_range(),
+                "value": field.extract(value),
+            })).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+/// Read registers by name and print their decoded fields.
+pub fn execute_reg_read(params: &RegRead, siflitool: &mut Box<dyn SifliTool>) -> Result<()> {
+    let device = load_device(&params.svd)?;
+    // Resolve every name before touching the chip
+    let targets = params
+        .names
+        .iter()
+        .map(|name| resolve(&device, name))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut results = Vec::new();
+    for target in &targets {
+        let value = reg::read_register(siflitool.as_mut(), target.address())
+            .with_context(|| format!("Failed to read {}", target.name()))?;
+        if params.json {
+            results.push(to_json(target, value));
+        } else {
+            print_text(target, value, "=");
+        }
+    }
+    if params.json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    }
+    Ok(())
+}
+
+/// Write a register, or one of its fields with a read-modify-write.
+pub fn execute_reg_write(params: &RegWrite, siflitool: &mut Box<dyn SifliTool>) -> Result<()> {
+    let device = load_device(&params.svd)?;
+    let target = resolve(&device, &params.name)?;
+    let value = Utils::str_to_u32(&params.value)
+        .with_context(|| format!("Invalid value '{}'", params.value))?;
+
+    let (mask, value) = match target.field {
+        Some(field) => (field.mask(), field.insert(0, value)?),
+        None => (u32::MAX, value),
+    };
+    let written = reg::modify_register(siflitool.as_mut(), target.address(), mask, value)
+        .with_context(|| format!("Failed to write {}", target.name()))?;
+    print_text(&target, written, "<-");
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result, bail};
+use sftool_lib::rtt::{
+    self, RttHandler, RttLocation, RttParams, default_scan_range, find_rtt_symbol,
+};
+use sftool_lib::{ChipType, SifliTool};
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+use crate::cli::Rtt;
+
+fn resolve_location(params: &Rtt, chip: &ChipType) -> Result<RttLocation> {
+    if let Some(path) = &params.elf {
+        let data = std::fs::read(path).with_context(|| format!("Failed to read '{}'", path))?;
+        let address = find_rtt_symbol(&data)
+            .with_context(|| format!("Failed to parse ELF file '{}'", path))?
+            .with_context(|| format!("No _SEGGER_RTT symbol in '{}'", path))?;
+        return Ok(RttLocation::Address(address));
+    }
+    if let Some(address) = &params.address {
+        let address = sftool_lib::utils::Utils::str_to_u32(address)
+            .with_context(|| format!("Invalid control block address '{}'", address))?;
+        return Ok(RttLocation::Address(address));
+    }
+    if let Some(region) = &params.scan {
+        let region = sftool_lib::utils::Utils::parse_erase_region(region)
+            .with_context(|| format!("Failed to parse scan range {}", region))?;
+        let end = region
+            .address
+            .checked_add(region.size)
+            .context("Scan range overflows the address space")?;
+        return Ok(RttLocation::Scan(region.address..end));
+    }
+    match default_scan_range(chip) {
+        Some(range) => Ok(RttLocation::Scan(range)),
+        None => {
+            bail!("No default RAM range to search on this chip, pass --elf, --address or --scan")
+        }
+    }
+}
+
+/// Prints one up channel to stdout and forwards stdin lines to the matching down channel.
+struct TerminalHandler {
+    channel: usize,
+    input: Receiver<Vec<u8>>,
+    input_closed: bool,
+}
+
+impl RttHandler for TerminalHandler {
+    fn on_attach(&mut self, address: u32, up_channels: usize, down_channels: usize) {
+        eprintln!(
+            "--- sftool rtt: control block at 0x{:08X} ({} up, {} down channels) ---",
+            address, up_channels, down_channels
+        );
+        if self.channel >= up_channels {
+            eprintln!(
+                "Warning: firmware has no up channel {}, nothing will be printed",
+                self.channel
+            );
+        }
+    }
+
+    fn on_up(&mut self, channel: usize, data: &[u8]) -> sftool_lib::Result<()> {
+        if channel == self.channel {
+            let mut out = std::io::stdout().lock();
+            out.write_all(data)?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn poll_down(&mut self, channel: usize) -> Option<Vec<u8>> {
+        if channel != self.channel || self.input_closed {
+            return None;
+        }
+        let mut data = Vec::new();
+        loop {
+            match self.input.try_recv() {
+                Ok(line) => data.extend_from_slice(&line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.input_closed = true;
+                    break;
+                }
+            }
+        }
+        (!data.is_empty()).then_some(data)
+    }
+}
+
+/// Attach to the running firmware and stream RTT output until interrupted.
+pub fn execute_rtt(
+    params: &Rtt,
+    chip: &ChipType,
+    siflitool: &mut Box<dyn SifliTool>,
+) -> Result<()> {
+    let location = resolve_location(params, chip)?;
+
+    let (sender, input) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().split(b'\n') {
+            let Ok(mut line) = line else { break };
+            line.push(b'\n');
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Ctrl-C stops polling through the cancel token so the debug channel is
+    // left cleanly; a second Ctrl-C exits right away
+    let cancel_token = siflitool.base().cancel_token.clone();
+    ctrlc::set_handler(move || {
+        if cancel_token.is_cancelled() {
+            std::process::exit(130);
+        }
+        cancel_token.cancel();
+    })
+    .context("Failed to install the Ctrl-C handler")?;
+
+    let mut handler = TerminalHandler {
+        channel: params.channel,
+        input,
+        input_closed: false,
+    };
+    let result = rtt::run(
+        siflitool.as_mut(),
+        &RttParams {
+            location,
+            poll_interval: Duration::from_millis(params.interval),
+        },
+        &mut handler,
+    );
+    match result {
+        Err(sftool_lib::Error::Cancelled) => Ok(()),
+        result => result.context("RTT session ended"),
+    }
+}
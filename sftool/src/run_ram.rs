@@ -0,0 +1,55 @@
+use anyhow::{Context, Result, bail};
+use sftool_lib::SifliTool;
+use sftool_lib::run_ram::{self, RunRamParams};
+use sftool_lib::utils::{ELF_MAGIC, Utils};
+
+use crate::cli::RunRam;
+
+/// Build the load parameters from `<file.elf>` or `<file.bin@address>`.
+fn parse_run_ram(params: &RunRam) -> Result<RunRamParams> {
+    let mut run_params = match params.file.rsplit_once('@') {
+        Some((path, address)) => {
+            let address = Utils::str_to_u32(address)
+                .with_context(|| format!("Invalid load address '{}'", address))?;
+            let data = std::fs::read(path).with_context(|| format!("Failed to read '{}'", path))?;
+            if data.starts_with(ELF_MAGIC) {
+                bail!("ELF files do not support @address format");
+            }
+            RunRamParams::from_bin(data, address)
+        }
+        None => {
+            let path = &params.file;
+            let data = std::fs::read(path).with_context(|| format!("Failed to read '{}'", path))?;
+            if !data.starts_with(ELF_MAGIC) {
+                bail!("For binary files, please use the <file@address> format");
+            }
+            RunRamParams::from_elf(&data)
+                .with_context(|| format!("Failed to parse ELF file '{}'", path))?
+        }
+    };
+    if let Some(entry) = &params.entry {
+        run_params.entry = Some(
+            Utils::str_to_u32(entry).with_context(|| format!("Invalid entry point '{}'", entry))?,
+        );
+    }
+    Ok(run_params)
+}
+
+/// ELF file to annotate monitor output with, when the program was loaded from one
+pub fn run_ram_elf(params: &RunRam) -> Option<&str> {
+    params
+        .file
+        .rsplit_once('@')
+        .is_none()
+        .then_some(params.file.as_str())
+}
+
+/// Load the program into RAM and start it.
+pub fn execute_run_ram(params: &RunRam, siflitool: &mut Box<dyn SifliTool>) -> Result<()> {
+    let run_params = parse_run_ram(params)?;
+    let (sp, pc) = run_params.sp_pc()?;
+    run_ram::load_and_run(siflitool.as_mut(), &run_params)
+        .context("Failed to execute run_ram command")?;
+    eprintln!("Started program at 0x{:08X} (SP 0x{:08X})", pc, sp);
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use anyhow::{Result, anyhow, bail};
+use sftool_lib::common::net_port::is_network_port;
 
 /// Convert macOS /dev/tty.* ports to /dev/cu.* ports
 ///
@@ -26,6 +27,11 @@ pub fn normalize_port_name(port_name: &str) -> String {
 /// # Returns
 /// * `Result<(), String>` - Returns Ok(()) if the port is available; otherwise returns an Err with error message
 pub fn check_port_available(port_name: &str) -> Result<()> {
+    // Network ports (tcp://, rfc2217://) are not enumerable, connecting is the check
+    if is_network_port(port_name) {
+        return Ok(());
+    }
+
     match serialport::available_ports() {
         Ok(ports) => {
             // On macOS, only use /dev/cu.* ports, not /dev/tty.* ports
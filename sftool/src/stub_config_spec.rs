@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use sftool_lib::flash_parts::find_part;
 use sftool_lib::stub_config as lib;
 use sftool_lib::utils::Utils;
 
@@ -44,15 +45,76 @@ pub enum FlashMediaSpec {
     Nand,
 }
 
+/// Flash entry; with `part` set, the ID and capacity fields default to the
+/// values from the built-in part table and only need to be given to override.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlashSpec {
-    pub media: FlashMediaSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<FlashMediaSpec>,
     pub driver_index: u8,
-    pub manufacturer_id: u8,
-    pub device_type: u8,
-    pub density_id: u8,
-    pub flags: u8,
-    pub capacity_bytes: SizeValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manufacturer_id: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_type: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub density_id: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity_bytes: Option<SizeValue>,
+}
+
+impl FlashSpec {
+    fn to_flash_config(&self, index: usize) -> Result<lib::FlashConfig> {
+        let part = match &self.part {
+            Some(name) => Some(find_part(name).with_context(|| {
+                format!(
+                    "flash[{index}]: unknown part '{name}', run `sftool stub parts` to list known parts"
+                )
+            })?),
+            None => None,
+        };
+        let missing = |field: &str| {
+            anyhow::anyhow!("flash[{index}]: missing {field} (set it or use \"part\")")
+        };
+
+        let media = match (self.media, part) {
+            (Some(media), _) => media.into(),
+            (None, Some(part)) => part.media,
+            (None, None) => return Err(missing("media")),
+        };
+        let capacity_bytes = match (&self.capacity_bytes, part) {
+            (Some(capacity), _) => capacity.to_u32("capacity_bytes")?,
+            (None, Some(part)) => part.capacity_bytes,
+            (None, None) => return Err(missing("capacity_bytes")),
+        };
+        let flags = match (self.flags, part) {
+            (Some(flags), _) => flags,
+            (None, Some(_)) => 0,
+            (None, None) => return Err(missing("flags")),
+        };
+
+        Ok(lib::FlashConfig {
+            media,
+            driver_index: self.driver_index,
+            manufacturer_id: self
+                .manufacturer_id
+                .or(part.map(|part| part.manufacturer_id))
+                .ok_or_else(|| missing("manufacturer_id"))?,
+            device_type: self
+                .device_type
+                .or(part.map(|part| part.device_type))
+                .ok_or_else(|| missing("device_type"))?,
+            density_id: self
+                .density_id
+                .or(part.map(|part| part.density_id))
+                .ok_or_else(|| missing("density_id"))?,
+            flags,
+            capacity_bytes,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -184,17 +246,8 @@ impl StubConfigSpec {
         let flash = self
             .flash
             .iter()
-            .map(|entry| {
-                Ok(lib::FlashConfig {
-                    media: entry.media.into(),
-                    driver_index: entry.driver_index,
-                    manufacturer_id: entry.manufacturer_id,
-                    device_type: entry.device_type,
-                    density_id: entry.density_id,
-                    flags: entry.flags,
-                    capacity_bytes: entry.capacity_bytes.to_u32("capacity_bytes")?,
-                })
-            })
+            .enumerate()
+            .map(|(index, entry)| entry.to_flash_config(index))
             .collect::<Result<Vec<_>>>()?;
 
         let pmic = self.pmic.as_ref().map(|pmic| lib::PmicConfig {
@@ -238,13 +291,14 @@ impl StubConfigSpec {
             .flash
             .iter()
             .map(|entry| FlashSpec {
-                media: entry.media.into(),
+                part: None,
+                media: Some(entry.media.into()),
                 driver_index: entry.driver_index,
-                manufacturer_id: entry.manufacturer_id,
-                device_type: entry.device_type,
-                density_id: entry.density_id,
-                flags: entry.flags,
-                capacity_bytes: SizeValue::Number(entry.capacity_bytes as u64),
+                manufacturer_id: Some(entry.manufacturer_id),
+                device_type: Some(entry.device_type),
+                density_id: Some(entry.density_id),
+                flags: Some(entry.flags),
+                capacity_bytes: Some(SizeValue::Number(entry.capacity_bytes as u64)),
             })
             .collect();
 
@@ -1,4 +1,6 @@
 use anyhow::{Context, Result, anyhow, bail};
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::io::Write;
 
 use sftool_lib::ChipType;
@@ -14,15 +16,219 @@ pub fn load_stub_config_spec(path: &str) -> Result<StubConfigSpec> {
     Ok(spec)
 }
 
-pub fn execute_stub_write(files: &[String], spec: &StubConfigSpec) -> Result<()> {
+/// Guess the chip from a stub file name such as `ram_patch_58X_NAND.bin`.
+fn chip_from_stub_file_name(file: &str) -> Option<ChipType> {
+    let name = std::path::Path::new(file)
+        .file_name()?
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    [
+        ("52x", ChipType::SF32LB52),
+        ("55x", ChipType::SF32LB55),
+        ("56x", ChipType::SF32LB56),
+        ("57x", ChipType::SF32LB57),
+        ("58x", ChipType::SF32LB58),
+    ]
+    .into_iter()
+    .find(|(tag, _)| name.contains(tag))
+    .map(|(_, chip)| chip)
+}
+
+/// Write the config into each file, validating it against `chip` or, when no
+/// chip is given, the chip guessed from the file name.
+pub fn execute_stub_write(
+    files: &[String],
+    spec: &StubConfigSpec,
+    chip: Option<&ChipType>,
+) -> Result<()> {
     let config = spec.to_stub_config().context("Invalid stub config")?;
     for file in files {
+        let file_chip = chip.cloned().or_else(|| chip_from_stub_file_name(file));
+        if let Some(file_chip) = file_chip {
+            sftool_lib::stub_config::validate_stub_config(&config, &file_chip).with_context(
+                || {
+                    format!(
+                        "Invalid stub config for {} ('{}')",
+                        chip_key(&file_chip),
+                        file
+                    )
+                },
+            )?;
+        }
         sftool_lib::stub_config::write_stub_config_to_file(file, &config)
             .with_context(|| format!("Failed to write stub config to '{}'", file))?;
     }
     Ok(())
 }
 
+/// Load a stub config from a JSON spec (`*.json`) or from the block inside a driver file.
+fn load_stub_config_source(path: &str) -> Result<sftool_lib::stub_config::StubConfig> {
+    let is_json = std::path::Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        load_stub_config_spec(path)?
+            .to_stub_config()
+            .with_context(|| format!("Invalid stub config in '{}'", path))
+    } else {
+        sftool_lib::stub_config::read_stub_config_from_file(path)
+            .with_context(|| format!("Failed to read stub config from '{}'", path))
+    }
+}
+
+/// Flatten a JSON value into `path = value` leaves; nulls are treated as absent.
+///
+/// Keys are zero-padded array indices so `pins[10]` sorts after `pins[2]`.
+fn flatten_json(
+    sort_prefix: &str,
+    prefix: &str,
+    value: &Value,
+    out: &mut BTreeMap<String, (String, String)>,
+) {
+    match value {
+        Value::Null => {}
+        Value::Object(map) => {
+            for (key, child) in map {
+                let (sort_path, path) = if prefix.is_empty() {
+                    (key.clone(), key.clone())
+                } else {
+                    (
+                        format!("{}.{}", sort_prefix, key),
+                        format!("{}.{}", prefix, key),
+                    )
+                };
+                flatten_json(&sort_path, &path, child, out);
+            }
+        }
+        Value::Array(items) if items.iter().any(Value::is_object) => {
+            for (index, child) in items.iter().enumerate() {
+                flatten_json(
+                    &format!("{}[{:02}]", sort_prefix, index),
+                    &format!("{}[{}]", prefix, index),
+                    child,
+                    out,
+                );
+            }
+        }
+        Value::String(text) => {
+            out.insert(sort_prefix.to_string(), (prefix.to_string(), text.clone()));
+        }
+        _ => {
+            out.insert(
+                sort_prefix.to_string(),
+                (prefix.to_string(), value.to_string()),
+            );
+        }
+    }
+}
+
+fn stub_config_fields(
+    config: &sftool_lib::stub_config::StubConfig,
+) -> Result<BTreeMap<String, (String, String)>> {
+    let value = serde_json::to_value(StubConfigSpec::from_stub_config(config))?;
+    let mut fields = BTreeMap::new();
+    flatten_json("", "", &value, &mut fields);
+    Ok(fields)
+}
+
+/// List the fields that differ as `path: old -> new`, `-` standing for a missing field.
+fn diff_stub_fields(
+    left: &BTreeMap<String, (String, String)>,
+    right: &BTreeMap<String, (String, String)>,
+) -> Vec<String> {
+    let mut paths: Vec<&String> = left.keys().chain(right.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut lines = Vec::new();
+    for key in paths {
+        let (left_path, old) = left.get(key).map(|(p, v)| (p, v.as_str())).unzip();
+        let (right_path, new) = right.get(key).map(|(p, v)| (p, v.as_str())).unzip();
+        let (old, new) = (old.unwrap_or("-"), new.unwrap_or("-"));
+        if old != new {
+            let path = left_path.or(right_path).map(String::as_str).unwrap_or(key);
+            lines.push(format!("{}: {} -> {}", path, old, new));
+        }
+    }
+    lines
+}
+
+/// Print the differences between two stub configs.
+///
+/// Returns `false` when they differ so the caller can exit with status 1, like `cmp`.
+pub fn execute_stub_diff(a: &str, b: &str) -> Result<bool> {
+    let left = stub_config_fields(&load_stub_config_source(a)?)?;
+    let right = stub_config_fields(&load_stub_config_source(b)?)?;
+
+    let lines = diff_stub_fields(&left, &right);
+    if lines.is_empty() {
+        println!("Stub configs are identical");
+        return Ok(true);
+    }
+    for line in &lines {
+        println!("{}", line);
+    }
+    println!(
+        "{} field(s) differ between '{}' and '{}'",
+        lines.len(),
+        a,
+        b
+    );
+    Ok(false)
+}
+
+/// Apply `patch` onto `target` following JSON merge patch rules (RFC 7386).
+fn merge_json(target: &mut Value, patch: &Value) {
+    let Value::Object(patch_map) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    let Value::Object(target_map) = target else {
+        return;
+    };
+    for (key, value) in patch_map {
+        if value.is_null() {
+            target_map.remove(key);
+        } else {
+            merge_json(target_map.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+pub fn execute_stub_merge(
+    files: &[String],
+    patch_path: &str,
+    chip: Option<&ChipType>,
+) -> Result<()> {
+    let content = std::fs::read_to_string(patch_path)
+        .with_context(|| format!("Failed to read stub config file '{}'", patch_path))?;
+    let patch: Value =
+        serde_json::from_str(&content).with_context(|| "Failed to parse stub config JSON")?;
+    let Value::Object(patch_map) = &patch else {
+        bail!("Partial stub config must be a JSON object");
+    };
+    if let Some(key) = patch_map
+        .keys()
+        .find(|key| !["pins", "flash", "pmic", "sd0"].contains(&key.as_str()))
+    {
+        bail!("Unknown stub config field '{}'", key);
+    }
+
+    for file in files {
+        let current = sftool_lib::stub_config::read_stub_config_from_file(file)
+            .with_context(|| format!("Failed to read stub config from '{}'", file))?;
+        let mut merged = serde_json::to_value(StubConfigSpec::from_stub_config(&current))?;
+        merge_json(&mut merged, &patch);
+        let spec: StubConfigSpec = serde_json::from_value(merged)
+            .with_context(|| format!("Merged stub config for '{}' is invalid", file))?;
+        execute_stub_write(std::slice::from_ref(file), &spec, chip)?;
+    }
+    Ok(())
+}
+
 pub fn execute_stub_clear(files: &[String]) -> Result<()> {
     for file in files {
         sftool_lib::stub_config::clear_stub_config_in_file(file)
@@ -31,6 +237,93 @@ pub fn execute_stub_clear(files: &[String]) -> Result<()> {
     Ok(())
 }
 
+pub fn execute_stub_parts() {
+    println!(
+        "{:<14} {:<5} {:>6} {:>6} {:>8} {:>10}",
+        "PART", "MEDIA", "MFR", "TYPE", "DENSITY", "CAPACITY"
+    );
+    for part in sftool_lib::flash_parts::FLASH_PARTS {
+        let media = match part.media {
+            sftool_lib::stub_config::FlashMedia::Nor => "nor",
+            sftool_lib::stub_config::FlashMedia::Nand => "nand",
+        };
+        println!(
+            "{:<14} {:<5}   0x{:02X}   0x{:02X}     0x{:02X} {:>6} MiB",
+            part.name,
+            media,
+            part.manufacturer_id,
+            part.device_type,
+            part.density_id,
+            part.capacity_bytes / (1024 * 1024)
+        );
+    }
+}
+
+pub fn execute_stub_list(json: bool) -> Result<()> {
+    let stubs = sftool_lib::list_embedded_stubs();
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct StubListItem<'a> {
+            key: Option<&'a str>,
+            file: &'a str,
+            size: usize,
+            crc32: String,
+            driver_config: bool,
+        }
+
+        let items: Vec<StubListItem> = stubs
+            .iter()
+            .map(|stub| StubListItem {
+                key: stub.key,
+                file: &stub.file_name,
+                size: stub.size,
+                crc32: format!("0x{:08X}", stub.crc32),
+                driver_config: stub.has_driver_config,
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&items)?);
+        return Ok(());
+    }
+
+    println!(
+        "{:<26} {:<36} {:>8} {:>10} {:<6}",
+        "KEY", "FILE", "SIZE", "CRC32", "CONFIG"
+    );
+    for stub in &stubs {
+        println!(
+            "{:<26} {:<36} {:>8} 0x{:08X} {}",
+            stub.key.unwrap_or("-"),
+            stub.file_name,
+            stub.size,
+            stub.crc32,
+            if stub.has_driver_config { "yes" } else { "no" }
+        );
+    }
+    Ok(())
+}
+
+pub fn execute_stub_extract(name: &str, output: &str) -> Result<()> {
+    let data = sftool_lib::load_embedded_stub(name)?;
+    std::fs::write(output, &data)
+        .with_context(|| format!("Failed to write stub to '{}'", output))?;
+    println!("Extracted {} ({} bytes) to {}", name, data.len(), output);
+    Ok(())
+}
+
+pub fn execute_stub_read_embedded(name: &str, output: Option<&str>) -> Result<()> {
+    let data = sftool_lib::load_embedded_stub(name)?;
+    let config = sftool_lib::stub_config::read_stub_config_from_bytes(&data)
+        .with_context(|| format!("Failed to read stub config from embedded stub '{}'", name))?;
+    let json = serde_json::to_string_pretty(&StubConfigSpec::from_stub_config(&config))?;
+    match output {
+        Some(output_path) => std::fs::write(output_path, json)
+            .with_context(|| format!("Failed to write stub config to '{}'", output_path))?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
 pub fn execute_stub_read(files: &[String], output: Option<&str>) -> Result<()> {
     if let Some(output_path) = output {
         if files.len() != 1 {
@@ -71,14 +364,14 @@ pub fn execute_stub_read(files: &[String], output: Option<&str>) -> Result<()> {
     Ok(())
 }
 
-pub fn execute_stub_config_command(config: &SfToolConfig) -> Result<()> {
+pub fn execute_stub_config_command(config: &SfToolConfig, chip: Option<&ChipType>) -> Result<()> {
     let stub = config
         .stub
         .as_ref()
         .ok_or_else(|| anyhow!("No stub command found in config file"))?;
 
     if let Some(ref stub_write) = stub.write {
-        execute_stub_write(&stub_write.files, &stub_write.config)
+        execute_stub_write(&stub_write.files, &stub_write.config, chip)
     } else if let Some(ref stub_clear) = stub.clear {
         execute_stub_clear(&stub_clear.files)
     } else if let Some(ref stub_read) = stub.read {
@@ -111,6 +404,8 @@ pub fn prepare_stub_path(
 
     let spec = load_stub_config_spec(config_path)?;
     let config = spec.to_stub_config().context("Invalid stub config")?;
+    sftool_lib::stub_config::validate_stub_config(&config, chip_type)
+        .context("Invalid stub config")?;
 
     let mut data =
         sftool_lib::load_stub_bytes(stub_path.as_deref(), chip_type.clone(), memory_type)
@@ -135,3 +430,57 @@ pub fn prepare_stub_path(
     let path = temp_file.path().to_string_lossy().to_string();
     Ok((Some(path), Some(temp_file)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_json_removes_null_fields_and_replaces_arrays() {
+        let mut target = json!({
+            "pins": [{"port": "PA", "number": 1, "level": "high"}],
+            "pmic": {"disabled": false, "channels": ["ldo33"]},
+            "sd0": {"base_address": 1},
+        });
+        let patch = json!({
+            "pins": [{"port": "PA", "number": 2, "level": "low"}],
+            "pmic": {"disabled": true},
+            "sd0": null,
+        });
+        merge_json(&mut target, &patch);
+        assert_eq!(
+            target,
+            json!({
+                "pins": [{"port": "PA", "number": 2, "level": "low"}],
+                "pmic": {"disabled": true, "channels": ["ldo33"]},
+            })
+        );
+    }
+
+    #[test]
+    fn diff_lists_changed_added_and_removed_fields() {
+        let fields = |value: Value| {
+            let mut out = BTreeMap::new();
+            flatten_json("", "", &value, &mut out);
+            out
+        };
+        let left = fields(json!({
+            "pins": [{"number": 1}, {"number": 2}],
+            "sd0": {"pinmux": "clk_pa34_or_pa09"},
+        }));
+        let right = fields(json!({
+            "pins": [{"number": 1}, {"number": 3}],
+            "pmic": {"disabled": true},
+        }));
+        assert_eq!(
+            diff_stub_fields(&left, &right),
+            vec![
+                "pins[1].number: 2 -> 3",
+                "pmic.disabled: - -> true",
+                "sd0.pinmux: clk_pa34_or_pa09 -> -",
+            ]
+        );
+        assert!(diff_stub_fields(&left, &left).is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::{Result, bail};
+use sftool_lib::VerifyResult;
+
+/// Print one line per verified segment and fail if any segment mismatched.
+pub fn report_verify_results(results: &[VerifyResult]) -> Result<()> {
+    for result in results {
+        println!(
+            "0x{:08X}..0x{:08X}  size 0x{:08X}  crc32 0x{:08X}  {}",
+            result.address,
+            result.address.saturating_add(result.len.saturating_sub(1)),
+            result.len,
+            result.crc32,
+            if result.matched { "OK" } else { "MISMATCH" }
+        );
+    }
+
+    let failed = results.iter().filter(|result| !result.matched).count();
+    if failed > 0 {
+        bail!(
+            "{} of {} segment(s) failed verification",
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}